@@ -0,0 +1,604 @@
+//! Reading and writing messages framed by the LSP base protocol.
+//!
+//! Every message consists of a header part and a JSON content part separated by `\r\n`:
+//!
+//! ```text
+//! Content-Length: 52\r\n
+//! \r\n
+//! {"jsonrpc":"2.0","id":1,"method":"shutdown"}
+//! ```
+//!
+//! Based on <https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#baseProtocol>
+
+use std::{
+    fmt,
+    io::{self, BufRead, Read, Write},
+};
+
+use crate::jsonrpc::Message;
+
+/// The default upper bound for the `Content-Length` of a message (64 MiB).
+pub const DEFAULT_MAX_CONTENT_LENGTH: usize = 64 * 1024 * 1024;
+
+/// The upper bound for the length of a single header line, including the line terminator.
+pub const MAX_HEADER_LINE_LENGTH: usize = 4 * 1024;
+
+/// The upper bound for the length of the header part of a message, including the empty line
+/// that ends it.
+pub const MAX_HEADER_LENGTH: usize = 16 * 1024;
+
+/// An error encountered while reading or writing a framed message.
+#[derive(Debug)]
+pub enum FrameError {
+    /// An error of the underlying reader or writer.
+    Io(io::Error),
+    /// The stream ended in the middle of a message.
+    UnexpectedEof,
+    /// A header line is not of the form `name: value\r\n`.
+    MalformedHeader(String),
+    /// The header part is longer than [`MAX_HEADER_LENGTH`].
+    HeaderTooLarge,
+    /// The header part does not contain a `Content-Length` header.
+    MissingContentLength,
+    /// The value of the `Content-Length` header is not a valid length.
+    InvalidContentLength(String),
+    /// The `Content-Length` exceeds the configured maximum.
+    ContentTooLarge { length: usize, max: usize },
+    /// The `Content-Type` header declares a charset other than `utf-8`.
+    UnsupportedCharset(String),
+    /// The content part is not valid UTF-8.
+    InvalidUtf8(std::string::FromUtf8Error),
+    /// The content part is not a valid JSON-RPC message.
+    Json(serde_json::Error),
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "i/o error: {err}"),
+            Self::UnexpectedEof => write!(f, "unexpected end of stream in the middle of a message"),
+            Self::MalformedHeader(header) => write!(f, "malformed header: {header:?}"),
+            Self::HeaderTooLarge => {
+                write!(
+                    f,
+                    "header part exceeds the maximum of {MAX_HEADER_LENGTH} bytes"
+                )
+            }
+            Self::MissingContentLength => write!(f, "missing `Content-Length` header"),
+            Self::InvalidContentLength(value) => {
+                write!(f, "invalid `Content-Length` header: {value:?}")
+            }
+            Self::ContentTooLarge { length, max } => {
+                write!(f, "content length {length} exceeds the maximum of {max}")
+            }
+            Self::UnsupportedCharset(charset) => write!(f, "unsupported charset: {charset:?}"),
+            Self::InvalidUtf8(err) => write!(f, "content is not valid utf-8: {err}"),
+            Self::Json(err) => write!(f, "content is not a valid message: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::InvalidUtf8(err) => Some(err),
+            Self::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for FrameError {
+    fn from(from: io::Error) -> Self {
+        Self::Io(from)
+    }
+}
+
+impl From<serde_json::Error> for FrameError {
+    fn from(from: serde_json::Error) -> Self {
+        Self::Json(from)
+    }
+}
+
+/// The header part of a message.
+#[derive(Debug, Default)]
+struct Headers {
+    content_length: Option<usize>,
+}
+
+impl Headers {
+    /// Parses a header line without its `\r\n` terminator.
+    fn parse_line(&mut self, line: &str) -> Result<(), FrameError> {
+        let malformed = || FrameError::MalformedHeader(line.to_owned());
+
+        let (name, value) = line.split_once(':').ok_or_else(malformed)?;
+        let name = name.trim();
+        let value = value.trim();
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(malformed());
+        }
+
+        if name.eq_ignore_ascii_case("Content-Length") {
+            if self.content_length.is_some() {
+                return Err(malformed());
+            }
+            let length = value
+                .parse()
+                .map_err(|_| FrameError::InvalidContentLength(value.to_owned()))?;
+            self.content_length = Some(length);
+        } else if name.eq_ignore_ascii_case("Content-Type") {
+            check_charset(value)?;
+        }
+        // Unknown headers are ignored.
+        Ok(())
+    }
+
+    fn content_length(&self, max: usize) -> Result<usize, FrameError> {
+        let length = self
+            .content_length
+            .ok_or(FrameError::MissingContentLength)?;
+        if length > max {
+            return Err(FrameError::ContentTooLarge { length, max });
+        }
+        Ok(length)
+    }
+}
+
+/// Checks the `charset` parameter of a `Content-Type` value. Only `utf-8` is supported,
+/// `utf8` is accepted for backwards compatibility.
+fn check_charset(content_type: &str) -> Result<(), FrameError> {
+    for parameter in content_type.split(';').skip(1) {
+        let Some((name, value)) = parameter.split_once('=') else {
+            continue;
+        };
+        if name.trim().eq_ignore_ascii_case("charset") {
+            let charset = value.trim().trim_matches('"');
+            if !charset.eq_ignore_ascii_case("utf-8") && !charset.eq_ignore_ascii_case("utf8") {
+                return Err(FrameError::UnsupportedCharset(charset.to_owned()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads framed messages from a [`BufRead`].
+#[derive(Debug)]
+pub struct FrameReader<R> {
+    reader: R,
+    max_content_length: usize,
+}
+
+impl<R: BufRead> FrameReader<R> {
+    #[must_use]
+    pub const fn new(reader: R) -> Self {
+        Self {
+            reader,
+            max_content_length: DEFAULT_MAX_CONTENT_LENGTH,
+        }
+    }
+
+    /// Sets the upper bound for the `Content-Length` of a message.
+    #[must_use]
+    pub const fn with_max_content_length(mut self, max_content_length: usize) -> Self {
+        self.max_content_length = max_content_length;
+        self
+    }
+
+    /// Reads the content part of the next message.
+    ///
+    /// Returns `None` if the stream ended before the next message.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stream can not be read or the message is not properly framed.
+    pub fn read_frame(&mut self) -> Result<Option<String>, FrameError> {
+        let mut headers = Headers::default();
+        let mut line = String::new();
+        let mut header_length = 0;
+        loop {
+            line.clear();
+            let read = (&mut self.reader)
+                .take(MAX_HEADER_LINE_LENGTH as u64)
+                .read_line(&mut line)
+                .map_err(|err| match err.kind() {
+                    io::ErrorKind::InvalidData => FrameError::MalformedHeader(line.clone()),
+                    _ => FrameError::Io(err),
+                })?;
+            if read == 0 {
+                return if header_length == 0 {
+                    Ok(None)
+                } else {
+                    Err(FrameError::UnexpectedEof)
+                };
+            }
+            header_length += read;
+            if header_length > MAX_HEADER_LENGTH {
+                return Err(FrameError::HeaderTooLarge);
+            }
+
+            let Some(line) = line.strip_suffix("\r\n") else {
+                if line.ends_with('\n') || read == MAX_HEADER_LINE_LENGTH {
+                    return Err(FrameError::MalformedHeader(line));
+                }
+                return Err(FrameError::UnexpectedEof);
+            };
+            if line.is_empty() {
+                break;
+            }
+            headers.parse_line(line)?;
+        }
+
+        let length = headers.content_length(self.max_content_length)?;
+        let mut content = vec![0; length];
+        self.reader
+            .read_exact(&mut content)
+            .map_err(|err| match err.kind() {
+                io::ErrorKind::UnexpectedEof => FrameError::UnexpectedEof,
+                _ => FrameError::Io(err),
+            })?;
+        String::from_utf8(content)
+            .map(Some)
+            .map_err(FrameError::InvalidUtf8)
+    }
+
+    /// Reads and parses the next message.
+    ///
+    /// Returns `None` if the stream ended before the next message.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the stream can not be read, the message is not properly framed
+    /// or its content is not a valid message.
+    pub fn read_message(&mut self) -> Result<Option<Message>, FrameError> {
+        match self.read_frame()? {
+            Some(content) => Ok(Some(serde_json::from_str(&content)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// Writes framed messages to a [`Write`].
+#[derive(Debug)]
+pub struct FrameWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> FrameWriter<W> {
+    #[must_use]
+    pub const fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Writes `content` as a single message and flushes the writer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the writer fails.
+    pub fn write_frame(&mut self, content: &str) -> io::Result<()> {
+        write!(self.writer, "Content-Length: {}\r\n\r\n", content.len())?;
+        self.writer.write_all(content.as_bytes())?;
+        self.writer.flush()
+    }
+
+    /// Serializes and writes a message.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message can not be serialized or the writer fails.
+    pub fn write_message(&mut self, message: &Message) -> Result<(), FrameError> {
+        let content = serde_json::to_string(message)?;
+        self.write_frame(&content)?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// A [`tokio_util::codec`] implementation of the base protocol that decodes and
+/// encodes [`Message`]s.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct MessageCodec {
+    max_content_length: usize,
+    /// The length of the content part, once the header part of the current message was read.
+    content_length: Option<usize>,
+    /// How many bytes of the header part were searched for its end already.
+    scanned: usize,
+}
+
+#[cfg(feature = "tokio")]
+impl MessageCodec {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            max_content_length: DEFAULT_MAX_CONTENT_LENGTH,
+            content_length: None,
+            scanned: 0,
+        }
+    }
+
+    /// Sets the upper bound for the `Content-Length` of a message.
+    #[must_use]
+    pub const fn with_max_content_length(mut self, max_content_length: usize) -> Self {
+        self.max_content_length = max_content_length;
+        self
+    }
+
+    /// Parses the header part at the start of `src`, if it is complete.
+    fn decode_headers(&mut self, src: &mut bytes::BytesMut) -> Result<Option<usize>, FrameError> {
+        // The end may start in the last 3 bytes that were searched before.
+        let start = self.scanned.saturating_sub(3);
+        let Some(end) = src[start..]
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .map(|end| start + end)
+        else {
+            self.scanned = src.len();
+            if src.len() > MAX_HEADER_LENGTH {
+                return Err(FrameError::HeaderTooLarge);
+            }
+            // Reject header parts that do not contain a line terminator early.
+            let last_line = src
+                .rsplit(|&byte| byte == b'\n')
+                .next()
+                .map_or(0, <[u8]>::len);
+            if last_line >= MAX_HEADER_LINE_LENGTH {
+                return Err(FrameError::MalformedHeader(
+                    String::from_utf8_lossy(&src[..MAX_HEADER_LINE_LENGTH]).into_owned(),
+                ));
+            }
+            return Ok(None);
+        };
+
+        self.scanned = 0;
+        if end + 4 > MAX_HEADER_LENGTH {
+            return Err(FrameError::HeaderTooLarge);
+        }
+        let header_part = src.split_to(end + 4);
+        let header_part = std::str::from_utf8(&header_part[..end]).map_err(|_| {
+            FrameError::MalformedHeader(String::from_utf8_lossy(&header_part).into_owned())
+        })?;
+        let mut headers = Headers::default();
+        for line in header_part.split("\r\n") {
+            headers.parse_line(line)?;
+        }
+        headers.content_length(self.max_content_length).map(Some)
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl Default for MessageCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio_util::codec::Decoder for MessageCodec {
+    type Item = Message;
+    type Error = FrameError;
+
+    fn decode(&mut self, src: &mut bytes::BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let length = match self.content_length {
+            Some(length) => length,
+            None => match self.decode_headers(src)? {
+                Some(length) => {
+                    self.content_length = Some(length);
+                    length
+                }
+                None => return Ok(None),
+            },
+        };
+
+        if src.len() < length {
+            src.reserve(length - src.len());
+            return Ok(None);
+        }
+
+        self.content_length = None;
+        let content = src.split_to(length);
+        let content = String::from_utf8(content.to_vec()).map_err(FrameError::InvalidUtf8)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+
+    fn decode_eof(&mut self, src: &mut bytes::BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        match self.decode(src)? {
+            Some(message) => Ok(Some(message)),
+            None if src.is_empty() && self.content_length.is_none() => Ok(None),
+            None => Err(FrameError::UnexpectedEof),
+        }
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio_util::codec::Encoder<Message> for MessageCodec {
+    type Error = FrameError;
+
+    fn encode(&mut self, item: Message, dst: &mut bytes::BytesMut) -> Result<(), Self::Error> {
+        let content = serde_json::to_string(&item)?;
+        let header = format!("Content-Length: {}\r\n\r\n", content.len());
+        dst.reserve(header.len() + content.len());
+        dst.extend_from_slice(header.as_bytes());
+        dst.extend_from_slice(content.as_bytes());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+    use crate::jsonrpc::{Notification, RequestId, Response};
+
+    /// A reader that returns at most one byte per read.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some((first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            if buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = *first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    fn read_frames(input: &[u8]) -> Result<Vec<String>, FrameError> {
+        let mut reader = FrameReader::new(BufReader::with_capacity(1, Trickle(input)))
+            .with_max_content_length(64);
+        let mut frames = vec![];
+        while let Some(frame) = reader.read_frame()? {
+            frames.push(frame);
+        }
+        Ok(frames)
+    }
+
+    #[test]
+    fn round_trip() {
+        let messages = vec![
+            Message::Notification(Notification {
+                method: "exit".into(),
+                params: serde_json::Value::Null,
+            }),
+            Message::Response(Response::new_ok(RequestId::Number(1), "ü").unwrap()),
+        ];
+
+        let mut writer = FrameWriter::new(vec![]);
+        for message in &messages {
+            writer.write_message(message).unwrap();
+        }
+        let bytes = writer.into_inner();
+
+        let mut reader = FrameReader::new(BufReader::with_capacity(1, Trickle(&bytes)));
+        let mut read = vec![];
+        while let Some(message) = reader.read_message().unwrap() {
+            read.push(message);
+        }
+        assert_eq!(read, messages);
+    }
+
+    #[test]
+    fn headers() {
+        assert_eq!(
+            read_frames(b"content-length: 2\r\nContent-Type: application/vscode-jsonrpc; charset=utf8\r\nX-Custom: 1\r\n\r\n{}")
+                .unwrap(),
+            vec!["{}".to_owned()]
+        );
+        assert!(matches!(
+            read_frames(b"Content-Length: 2\r\nContent-Type: application/vscode-jsonrpc; charset=latin1\r\n\r\n{}"),
+            Err(FrameError::UnsupportedCharset(charset)) if charset == "latin1"
+        ));
+        assert!(matches!(
+            read_frames(b"Content-Length 2\r\n\r\n{}"),
+            Err(FrameError::MalformedHeader(_))
+        ));
+        assert!(matches!(
+            read_frames(b"Content-Length: 2\n\n{}"),
+            Err(FrameError::MalformedHeader(_))
+        ));
+        assert!(matches!(
+            read_frames(b"Content-Length: 2\r\nContent-Length: 2\r\n\r\n{}"),
+            Err(FrameError::MalformedHeader(_))
+        ));
+        assert!(matches!(
+            read_frames(b"Content-Length: -2\r\n\r\n{}"),
+            Err(FrameError::InvalidContentLength(_))
+        ));
+        assert!(matches!(
+            read_frames(b"Content-Type: application/vscode-jsonrpc\r\n\r\n{}"),
+            Err(FrameError::MissingContentLength)
+        ));
+        assert!(matches!(
+            read_frames(b"Content-Length: 65\r\n\r\n"),
+            Err(FrameError::ContentTooLarge {
+                length: 65,
+                max: 64
+            })
+        ));
+        assert!(matches!(
+            read_frames("X: y\r\n".repeat(MAX_HEADER_LENGTH).as_bytes()),
+            Err(FrameError::HeaderTooLarge)
+        ));
+    }
+
+    #[test]
+    fn truncated() {
+        assert!(read_frames(b"").unwrap().is_empty());
+        assert!(matches!(
+            read_frames(b"Content-Length: 2\r\n"),
+            Err(FrameError::UnexpectedEof)
+        ));
+        assert!(matches!(
+            read_frames(b"Content-Length: 2\r\n\r\n{"),
+            Err(FrameError::UnexpectedEof)
+        ));
+        assert!(matches!(
+            read_frames(b"Content-Length: 2\r\n\r\n\xff\xfe"),
+            Err(FrameError::InvalidUtf8(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "tokio")]
+    fn codec() {
+        use bytes::BytesMut;
+        use tokio_util::codec::{Decoder, Encoder};
+
+        let message = Message::Response(Response::new_ok(RequestId::Number(1), "ü").unwrap());
+        let mut codec = MessageCodec::new();
+        let mut encoded = BytesMut::new();
+        codec.encode(message.clone(), &mut encoded).unwrap();
+        codec.encode(message.clone(), &mut encoded).unwrap();
+
+        // Feed the encoded messages one byte at a time.
+        let mut src = BytesMut::new();
+        let mut decoded = vec![];
+        for byte in encoded {
+            src.extend_from_slice(&[byte]);
+            if let Some(message) = codec.decode(&mut src).unwrap() {
+                decoded.push(message);
+            }
+        }
+        assert_eq!(decoded, vec![message.clone(), message]);
+        assert!(codec.decode_eof(&mut src).unwrap().is_none());
+
+        let mut src = BytesMut::from(&b"Content-Length: 10\r\n\r\n{}"[..]);
+        assert!(matches!(
+            codec.decode_eof(&mut src),
+            Err(FrameError::UnexpectedEof)
+        ));
+
+        let mut codec = MessageCodec::new().with_max_content_length(1);
+        let mut src = BytesMut::from(&b"Content-Length: 2\r\n\r\n{}"[..]);
+        assert!(matches!(
+            codec.decode(&mut src),
+            Err(FrameError::ContentTooLarge { length: 2, max: 1 })
+        ));
+
+        // Headers that never end are rejected.
+        let mut codec = MessageCodec::new();
+        let mut src = BytesMut::new();
+        let error = loop {
+            src.extend_from_slice(b"X: y\r\n");
+            match codec.decode(&mut src) {
+                Ok(None) => {}
+                Ok(Some(_)) => unreachable!(),
+                Err(error) => break error,
+            }
+        };
+        assert!(matches!(error, FrameError::HeaderTooLarge));
+        assert!(src.len() <= MAX_HEADER_LENGTH + 6);
+    }
+}
@@ -0,0 +1,369 @@
+//! A negotiated view of the [`ClientCapabilities`] and [`ServerCapabilities`] of a session.
+//!
+//! The capabilities are deeply nested and almost every property is optional. The accessors of
+//! [`NegotiatedCapabilities`] look up a single feature and apply the default of the
+//! specification if a property is omitted.
+
+use crate::lsp::{
+    ClientCapabilities, CodeActionKindLiteralSupport, CompletionItemCapability,
+    FailureHandlingKind, MarkupKind, PositionEncodingKind, ResourceOperationKind,
+    ServerCapabilities, TextDocumentClientCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, WorkspaceClientCapabilities, WorkspaceEditClientCapabilities,
+};
+
+/// Selects the position encoding a server should announce in
+/// [`ServerCapabilities::position_encoding`].
+///
+/// This is the first encoding of the client's `general.positionEncodings`, which are ordered by
+/// the preference of the client, that the server `supports`. If there is none, it is UTF-16,
+/// which every client and server has to support.
+#[must_use]
+pub fn negotiate_position_encoding(
+    client: &ClientCapabilities,
+    supported: &[PositionEncodingKind],
+) -> PositionEncodingKind {
+    client
+        .general
+        .as_ref()
+        .and_then(|general| general.position_encodings.as_ref())
+        .and_then(|encodings| {
+            encodings
+                .iter()
+                .find(|encoding| supported.contains(encoding))
+        })
+        .cloned()
+        .unwrap_or(PositionEncodingKind::UTF16)
+}
+
+/// The features both the client and the server of a session support.
+#[derive(Debug, Clone, Copy)]
+pub struct NegotiatedCapabilities<'a> {
+    client: &'a ClientCapabilities,
+    server: &'a ServerCapabilities,
+}
+
+impl<'a> NegotiatedCapabilities<'a> {
+    #[must_use]
+    pub const fn new(client: &'a ClientCapabilities, server: &'a ServerCapabilities) -> Self {
+        Self { client, server }
+    }
+
+    #[must_use]
+    pub const fn client(&self) -> &'a ClientCapabilities {
+        self.client
+    }
+
+    #[must_use]
+    pub const fn server(&self) -> &'a ServerCapabilities {
+        self.server
+    }
+
+    /// The position encoding of the session, UTF-16 unless the server announced another one.
+    #[must_use]
+    pub fn position_encoding(&self) -> PositionEncodingKind {
+        self.server
+            .position_encoding
+            .clone()
+            .unwrap_or(PositionEncodingKind::UTF16)
+    }
+
+    /// How the client synchronizes text documents with the server.
+    #[must_use]
+    pub fn text_document_sync_kind(&self) -> TextDocumentSyncKind {
+        match &self.server.text_document_sync {
+            Some(TextDocumentSyncCapability::Kind(kind)) => *kind,
+            Some(TextDocumentSyncCapability::Options(options)) => {
+                options.change.unwrap_or(TextDocumentSyncKind::NONE)
+            }
+            None => TextDocumentSyncKind::NONE,
+        }
+    }
+
+    /// The format the content of hovers should be sent in.
+    #[must_use]
+    pub fn hover_content_format(&self) -> MarkupKind {
+        preferred_markup_kind(
+            self.text_document()
+                .and_then(|text_document| text_document.hover.as_ref())
+                .and_then(|hover| hover.content_format.as_deref()),
+        )
+    }
+
+    /// The format the documentation of completion items should be sent in.
+    #[must_use]
+    pub fn completion_documentation_format(&self) -> MarkupKind {
+        preferred_markup_kind(
+            self.completion_item()
+                .and_then(|item| item.documentation_format.as_deref()),
+        )
+    }
+
+    /// The format the documentation of signatures should be sent in.
+    #[must_use]
+    pub fn signature_help_documentation_format(&self) -> MarkupKind {
+        preferred_markup_kind(
+            self.text_document()
+                .and_then(|text_document| text_document.signature_help.as_ref())
+                .and_then(|signature_help| signature_help.signature_information.as_ref())
+                .and_then(|information| information.documentation_format.as_deref()),
+        )
+    }
+
+    /// Whether completion items can be snippets.
+    #[must_use]
+    pub fn completion_snippets(&self) -> bool {
+        self.completion_item()
+            .and_then(|item| item.snippet_support)
+            .unwrap_or(false)
+    }
+
+    /// Whether completion items can have an `InsertReplaceEdit`.
+    #[must_use]
+    pub fn completion_insert_replace(&self) -> bool {
+        self.completion_item()
+            .and_then(|item| item.insert_replace_support)
+            .unwrap_or(false)
+    }
+
+    /// Whether completion items can have label details.
+    #[must_use]
+    pub fn completion_label_details(&self) -> bool {
+        self.completion_item()
+            .and_then(|item| item.label_details_support)
+            .unwrap_or(false)
+    }
+
+    /// The code action kinds the client supports, or `None` if it only supports commands as
+    /// the result of a code action request.
+    #[must_use]
+    pub fn code_action_kinds(&self) -> Option<&'a CodeActionKindLiteralSupport> {
+        self.text_document()
+            .and_then(|text_document| text_document.code_action.as_ref())
+            .and_then(|code_action| code_action.code_action_literal_support.as_ref())
+            .map(|literal_support| &literal_support.code_action_kind)
+    }
+
+    /// Whether the server can send `workspace/applyEdit` requests.
+    #[must_use]
+    pub fn apply_edit(&self) -> bool {
+        self.workspace()
+            .and_then(|workspace| workspace.apply_edit)
+            .unwrap_or(false)
+    }
+
+    /// Whether workspace edits can use `documentChanges`.
+    #[must_use]
+    pub fn document_changes(&self) -> bool {
+        self.workspace_edit()
+            .and_then(|workspace_edit| workspace_edit.document_changes)
+            .unwrap_or(false)
+    }
+
+    /// The resource operations workspace edits can contain.
+    #[must_use]
+    pub fn resource_operations(&self) -> &'a [ResourceOperationKind] {
+        self.workspace_edit()
+            .and_then(|workspace_edit| workspace_edit.resource_operations.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// How the client handles a workspace edit that fails to apply, if it announced it.
+    #[must_use]
+    pub fn failure_handling(&self) -> Option<FailureHandlingKind> {
+        self.workspace_edit()
+            .and_then(|workspace_edit| workspace_edit.failure_handling)
+    }
+
+    /// Whether workspace edits can contain change annotations.
+    #[must_use]
+    pub fn change_annotations(&self) -> bool {
+        self.workspace_edit()
+            .is_some_and(|workspace_edit| workspace_edit.change_annotation_support.is_some())
+    }
+
+    /// Whether workspace edits can contain snippet text edits.
+    #[must_use]
+    pub fn snippet_text_edits(&self) -> bool {
+        self.workspace_edit()
+            .and_then(|workspace_edit| workspace_edit.snippet_edit_support)
+            .unwrap_or(false)
+    }
+
+    /// Whether both the client and the server support workspace folders.
+    #[must_use]
+    pub fn workspace_folders(&self) -> bool {
+        let client = self
+            .workspace()
+            .and_then(|workspace| workspace.workspace_folders)
+            .unwrap_or(false);
+        let server = self
+            .server
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.workspace_folders.as_ref())
+            .and_then(|workspace_folders| workspace_folders.supported)
+            .unwrap_or(false);
+        client && server
+    }
+
+    /// Whether the server can send `workspace/configuration` requests.
+    #[must_use]
+    pub fn configuration(&self) -> bool {
+        self.workspace()
+            .and_then(|workspace| workspace.configuration)
+            .unwrap_or(false)
+    }
+
+    /// Whether the server can create work done progress with `window/workDoneProgress/create`.
+    #[must_use]
+    pub fn work_done_progress(&self) -> bool {
+        self.client
+            .window
+            .as_ref()
+            .and_then(|window| window.work_done_progress)
+            .unwrap_or(false)
+    }
+
+    /// Whether the server can send `window/showDocument` requests.
+    #[must_use]
+    pub fn show_document(&self) -> bool {
+        self.client
+            .window
+            .as_ref()
+            .and_then(|window| window.show_document.as_ref())
+            .is_some_and(|show_document| show_document.support)
+    }
+
+    const fn text_document(&self) -> Option<&'a TextDocumentClientCapabilities> {
+        self.client.text_document.as_ref()
+    }
+
+    fn completion_item(&self) -> Option<&'a CompletionItemCapability> {
+        self.text_document()
+            .and_then(|text_document| text_document.completion.as_ref())
+            .and_then(|completion| completion.completion_item.as_ref())
+    }
+
+    const fn workspace(&self) -> Option<&'a WorkspaceClientCapabilities> {
+        self.client.workspace.as_ref()
+    }
+
+    fn workspace_edit(&self) -> Option<&'a WorkspaceEditClientCapabilities> {
+        self.workspace()
+            .and_then(|workspace| workspace.workspace_edit.as_ref())
+    }
+}
+
+/// The first of the formats a client supports, in the order of its preference, or plain text.
+fn preferred_markup_kind(formats: Option<&[MarkupKind]>) -> MarkupKind {
+    formats
+        .and_then(<[MarkupKind]>::first)
+        .cloned()
+        .unwrap_or(MarkupKind::PlainText)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn defaults() {
+        let client = ClientCapabilities::default();
+        let server = ServerCapabilities::default();
+        let capabilities = NegotiatedCapabilities::new(&client, &server);
+        assert_eq!(
+            capabilities.position_encoding(),
+            PositionEncodingKind::UTF16
+        );
+        assert_eq!(
+            capabilities.text_document_sync_kind(),
+            TextDocumentSyncKind::NONE
+        );
+        assert_eq!(capabilities.hover_content_format(), MarkupKind::PlainText);
+        assert!(!capabilities.completion_snippets());
+        assert!(!capabilities.document_changes());
+        assert_eq!(capabilities.resource_operations(), []);
+        assert_eq!(capabilities.code_action_kinds(), None);
+        assert!(!capabilities.workspace_folders());
+        assert!(!capabilities.show_document());
+    }
+
+    #[test]
+    fn negotiated() {
+        let client: ClientCapabilities = serde_json::from_value(json!({
+            "general": { "positionEncodings": ["utf-32", "utf-8", "utf-16"] },
+            "textDocument": {
+                "hover": { "contentFormat": ["markdown", "plaintext"] },
+                "completion": {
+                    "completionItem": { "snippetSupport": true, "labelDetailsSupport": true }
+                },
+                "codeAction": {
+                    "codeActionLiteralSupport": { "codeActionKind": { "valueSet": ["quickfix"] } }
+                }
+            },
+            "workspace": {
+                "workspaceFolders": true,
+                "workspaceEdit": {
+                    "documentChanges": true,
+                    "resourceOperations": ["create", "rename"],
+                    "failureHandling": "undo"
+                }
+            },
+            "window": { "showDocument": { "support": true } }
+        }))
+        .unwrap();
+        let encoding = negotiate_position_encoding(
+            &client,
+            &[PositionEncodingKind::UTF8, PositionEncodingKind::UTF16],
+        );
+        assert_eq!(encoding, PositionEncodingKind::UTF8);
+        assert_eq!(
+            negotiate_position_encoding(&ClientCapabilities::default(), &[]),
+            PositionEncodingKind::UTF16
+        );
+
+        let server = ServerCapabilities {
+            position_encoding: Some(encoding),
+            text_document_sync: Some(TextDocumentSyncCapability::Kind(
+                TextDocumentSyncKind::INCREMENTAL,
+            )),
+            ..ServerCapabilities::default()
+        };
+        let capabilities = NegotiatedCapabilities::new(&client, &server);
+        assert_eq!(capabilities.position_encoding(), PositionEncodingKind::UTF8);
+        assert_eq!(
+            capabilities.text_document_sync_kind(),
+            TextDocumentSyncKind::INCREMENTAL
+        );
+        assert_eq!(capabilities.hover_content_format(), MarkupKind::Markdown);
+        assert_eq!(
+            capabilities.completion_documentation_format(),
+            MarkupKind::PlainText
+        );
+        assert!(capabilities.completion_snippets());
+        assert!(capabilities.completion_label_details());
+        assert!(!capabilities.completion_insert_replace());
+        assert_eq!(
+            capabilities
+                .code_action_kinds()
+                .map(|kinds| kinds.value_set.as_slice()),
+            Some(["quickfix".to_owned()].as_slice())
+        );
+        assert!(capabilities.document_changes());
+        assert_eq!(
+            capabilities.resource_operations(),
+            [ResourceOperationKind::Create, ResourceOperationKind::Rename]
+        );
+        assert_eq!(
+            capabilities.failure_handling(),
+            Some(FailureHandlingKind::Undo)
+        );
+        assert!(!capabilities.change_annotations());
+        // The server does not support workspace folders.
+        assert!(!capabilities.workspace_folders());
+        assert!(capabilities.show_document());
+        assert!(!capabilities.work_done_progress());
+    }
+}
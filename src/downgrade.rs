@@ -0,0 +1,970 @@
+//! Rewriting of results a server computed for the features of the latest specification into
+//! results an older or less capable client understands.
+//!
+//! A server can compute its results once, using `DocumentSymbol`s, `LocationLink`s, markdown
+//! and so on, and let a [`Downgrader`] rewrite them according to the [`ClientCapabilities`]
+//! right before they are sent.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::{
+    Uri,
+    lsp::{
+        ClientCapabilities, CompletionClientCapabilities, CompletionItem, CompletionItemTag,
+        CompletionListItemDefaults, CompletionListItemDefaultsEditRange, CompletionResponse,
+        CompletionTextEdit, DocumentChangeOperation, DocumentChanges, DocumentSymbol,
+        DocumentSymbolResponse, Documentation, GotoCapability, GotoDefinitionResponse, Hover,
+        HoverContents, Location, MarkedString, MarkupContent, MarkupKind, ResourceOp,
+        ResourceOperationKind, SymbolInformation, SymbolTag, TagSupport,
+        TextDocumentClientCapabilities, TextDocumentEdit, TextDocumentEditItem, TextEdit,
+        WorkspaceEdit, WorkspaceEditClientCapabilities,
+    },
+};
+
+/// An error returned if a [`WorkspaceEdit`] can not be expressed with the capabilities of the
+/// client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DowngradeError {
+    /// The edit creates, renames or deletes a file, which the client does not support.
+    UnsupportedResourceOperation(ResourceOperationKind),
+    /// The edit contains a snippet text edit, which the client does not support.
+    UnsupportedSnippetEdit(Uri),
+    /// The edit changes a document more than once, which can not be expressed with `changes`.
+    RepeatedDocument(Uri),
+}
+
+impl fmt::Display for DowngradeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedResourceOperation(kind) => {
+                let kind = match kind {
+                    ResourceOperationKind::Create => "create",
+                    ResourceOperationKind::Rename => "rename",
+                    ResourceOperationKind::Delete => "delete",
+                };
+                write!(f, "the client does not support `{kind}` operations")
+            }
+            Self::UnsupportedSnippetEdit(uri) => write!(
+                f,
+                "the client does not support snippet edits of `{}`",
+                uri.as_str()
+            ),
+            Self::RepeatedDocument(uri) => write!(
+                f,
+                "`{}` is changed more than once, which the client does not support",
+                uri.as_str()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DowngradeError {}
+
+/// Rewrites results according to the [`ClientCapabilities`] of a client.
+#[derive(Debug, Clone, Copy)]
+pub struct Downgrader<'a> {
+    client: &'a ClientCapabilities,
+}
+
+impl<'a> Downgrader<'a> {
+    #[must_use]
+    pub const fn new(client: &'a ClientCapabilities) -> Self {
+        Self { client }
+    }
+
+    /// Downgrades the items of a `textDocument/completion` response, and the item defaults of
+    /// a completion list like the items. Item defaults the client does not list in its
+    /// `completionList.itemDefaults` capability are dropped.
+    pub fn completion_response(&self, response: &mut CompletionResponse) {
+        let items = match response {
+            CompletionResponse::Array(items) => items,
+            CompletionResponse::List(list) => {
+                if let Some(defaults) = &mut list.item_defaults {
+                    completion_item_defaults(
+                        defaults,
+                        self.client
+                            .text_document
+                            .as_ref()
+                            .and_then(|text_document| text_document.completion.as_ref()),
+                    );
+                    if *defaults == CompletionListItemDefaults::default() {
+                        list.item_defaults = None;
+                    }
+                }
+                &mut list.items
+            }
+        };
+        for item in items {
+            self.completion_item(item);
+        }
+    }
+
+    /// Downgrades a completion item:
+    /// - markdown documentation is converted to plain text, which is a plain string if the
+    ///   client does not declare the documentation formats it supports,
+    /// - an `InsertReplaceEdit` is replaced by a `TextEdit` of its insert range,
+    /// - label details are dropped, their detail becomes the detail of the item if it has none,
+    /// - unsupported tags are dropped, the deprecated tag is replaced by the `deprecated`
+    ///   property if the client supports it.
+    pub fn completion_item(&self, item: &mut CompletionItem) {
+        let capability = self
+            .client
+            .text_document
+            .as_ref()
+            .and_then(|text_document| text_document.completion.as_ref())
+            .and_then(|completion| completion.completion_item.as_ref());
+
+        if let Some(Documentation::MarkupContent(content)) = &mut item.documentation {
+            let formats = capability.and_then(|item| item.documentation_format.as_deref());
+            downgrade_markup(content, formats);
+            if formats.is_none() {
+                item.documentation =
+                    Some(Documentation::String(std::mem::take(&mut content.value)));
+            }
+        }
+
+        let insert_replace = capability
+            .and_then(|item| item.insert_replace_support)
+            .unwrap_or(false);
+        if !insert_replace
+            && let Some(CompletionTextEdit::InsertAndReplace(edit)) = &mut item.text_edit
+        {
+            let edit = TextEdit::new(edit.insert, std::mem::take(&mut edit.new_text));
+            item.text_edit = Some(CompletionTextEdit::Edit(edit));
+        }
+
+        let label_details = capability
+            .and_then(|item| item.label_details_support)
+            .unwrap_or(false);
+        if !label_details && let Some(details) = item.label_details.take() {
+            item.detail = item.detail.take().or(details.detail);
+        }
+
+        let deprecated_support = capability
+            .and_then(|item| item.deprecated_support)
+            .unwrap_or(false);
+        if let Some(tags) = &mut item.tags {
+            let deprecated = tags.contains(&CompletionItemTag::DEPRECATED);
+            retain_supported(tags, capability.and_then(|item| item.tag_support.as_ref()));
+            if deprecated && !tags.contains(&CompletionItemTag::DEPRECATED) && deprecated_support {
+                item.deprecated = Some(true);
+            }
+            if tags.is_empty() {
+                item.tags = None;
+            }
+        }
+        if !deprecated_support {
+            item.deprecated = None;
+        }
+    }
+
+    /// Downgrades the contents of a hover. Markdown is converted to plain text if the client
+    /// does not support it.
+    ///
+    /// `MarkedString`s are only converted if the client declares the content formats it
+    /// supports, as clients that do not declare them predate `MarkupContent` and may not
+    /// understand it.
+    pub fn hover(&self, hover: &mut Hover) {
+        let formats = self
+            .client
+            .text_document
+            .as_ref()
+            .and_then(|text_document| text_document.hover.as_ref())
+            .and_then(|hover| hover.content_format.as_deref());
+        if supports_markdown(formats) {
+            return;
+        }
+        let value = match &mut hover.contents {
+            HoverContents::Markup(content) => {
+                downgrade_markup(content, formats);
+                return;
+            }
+            _ if formats.is_none() => return,
+            HoverContents::Scalar(marked) => marked_string_to_plaintext(marked),
+            HoverContents::Array(marked) => marked
+                .iter()
+                .map(marked_string_to_plaintext)
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        };
+        hover.contents = HoverContents::Markup(MarkupContent {
+            kind: MarkupKind::PlainText,
+            value,
+        });
+    }
+
+    /// Downgrades a `textDocument/documentSymbol` response of the document `uri`.
+    ///
+    /// Hierarchical symbols are flattened into `SymbolInformation`s in pre-order if the client
+    /// does not support them, with the name of the parent as the container name. Unsupported
+    /// tags are dropped.
+    pub fn document_symbols(&self, response: &mut DocumentSymbolResponse, uri: &Uri) {
+        let capability = self
+            .client
+            .text_document
+            .as_ref()
+            .and_then(|text_document| text_document.document_symbol.as_ref());
+        let tag_support = capability.and_then(|symbol| symbol.tag_support.as_ref());
+        let hierarchical = capability
+            .and_then(|symbol| symbol.hierarchical_document_symbol_support)
+            .unwrap_or(false);
+
+        if let DocumentSymbolResponse::Nested(symbols) = response
+            && !hierarchical
+        {
+            let mut flat = Vec::new();
+            flatten_symbols(std::mem::take(symbols), uri, None, &mut flat);
+            *response = DocumentSymbolResponse::Flat(flat);
+        }
+
+        match response {
+            DocumentSymbolResponse::Flat(symbols) => {
+                for symbol in symbols {
+                    retain_supported_tags(&mut symbol.tags, tag_support);
+                }
+            }
+            DocumentSymbolResponse::Nested(symbols) => {
+                retain_supported_symbol_tags(symbols, tag_support);
+            }
+        }
+    }
+
+    /// Downgrades a `textDocument/definition` response.
+    pub fn definition(&self, response: &mut GotoDefinitionResponse) {
+        downgrade_links(
+            response,
+            self.goto(|text_document| &text_document.definition),
+        );
+    }
+
+    /// Downgrades a `textDocument/declaration` response.
+    pub fn declaration(&self, response: &mut GotoDefinitionResponse) {
+        downgrade_links(
+            response,
+            self.goto(|text_document| &text_document.declaration),
+        );
+    }
+
+    /// Downgrades a `textDocument/typeDefinition` response.
+    pub fn type_definition(&self, response: &mut GotoDefinitionResponse) {
+        downgrade_links(
+            response,
+            self.goto(|text_document| &text_document.type_definition),
+        );
+    }
+
+    /// Downgrades a `textDocument/implementation` response.
+    pub fn implementation(&self, response: &mut GotoDefinitionResponse) {
+        downgrade_links(
+            response,
+            self.goto(|text_document| &text_document.implementation),
+        );
+    }
+
+    /// Downgrades a workspace edit:
+    /// - `documentChanges` are converted to `changes` if the client does not support them,
+    /// - annotated text edits become plain text edits and the change annotations are dropped
+    ///   if the client does not support change annotations.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the edit contains a resource operation or a snippet text edit the
+    /// client does not support, or if it has to be converted to `changes` but changes a
+    /// document more than once.
+    pub fn workspace_edit(&self, edit: &mut WorkspaceEdit) -> Result<(), DowngradeError> {
+        let capability = self
+            .client
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.workspace_edit.as_ref());
+        let document_changes = capability
+            .and_then(|workspace_edit| workspace_edit.document_changes)
+            .unwrap_or(false);
+        let annotations = capability
+            .is_some_and(|workspace_edit| workspace_edit.change_annotation_support.is_some());
+        let snippets = capability
+            .and_then(|workspace_edit| workspace_edit.snippet_edit_support)
+            .unwrap_or(false);
+
+        let Some(changes) = &mut edit.document_changes else {
+            return Ok(());
+        };
+        let edits = match changes {
+            DocumentChanges::Edits(edits) => edits.iter_mut().collect::<Vec<_>>(),
+            DocumentChanges::Operations(operations) => {
+                let mut edits = Vec::new();
+                for operation in operations {
+                    match operation {
+                        DocumentChangeOperation::Op(op) => check_resource_op(op, capability)?,
+                        DocumentChangeOperation::Edit(edit) => edits.push(edit),
+                    }
+                }
+                edits
+            }
+        };
+        for text_document_edit in &edits {
+            if !snippets || !document_changes {
+                check_snippets(text_document_edit)?;
+            }
+        }
+
+        if !document_changes {
+            let mut uris = HashSet::new();
+            for text_document_edit in &edits {
+                let uri = &text_document_edit.text_document.uri;
+                if !uris.insert(uri)
+                    || edit
+                        .changes
+                        .as_ref()
+                        .is_some_and(|changes| changes.contains_key(uri))
+                {
+                    return Err(DowngradeError::RepeatedDocument(uri.clone()));
+                }
+            }
+
+            let changes = edit.changes.get_or_insert_with(HashMap::new);
+            for text_document_edit in edits {
+                let uri = text_document_edit.text_document.uri.clone();
+                let text_edits = std::mem::take(&mut text_document_edit.edits)
+                    .into_iter()
+                    .filter_map(|item| match item {
+                        TextDocumentEditItem::Annotated(edit) => Some(edit.text_edit),
+                        TextDocumentEditItem::Text(edit) => Some(edit),
+                        TextDocumentEditItem::Snippet(_) => None,
+                    })
+                    .collect();
+                changes.insert(uri, text_edits);
+            }
+            edit.document_changes = None;
+            edit.change_annotations = None;
+        } else if !annotations {
+            for text_document_edit in edits {
+                for item in &mut text_document_edit.edits {
+                    match item {
+                        TextDocumentEditItem::Annotated(edit) => {
+                            *item = TextDocumentEditItem::Text(edit.text_edit.clone());
+                        }
+                        TextDocumentEditItem::Snippet(edit) => edit.annotation_id = None,
+                        TextDocumentEditItem::Text(_) => {}
+                    }
+                }
+            }
+            if let Some(DocumentChanges::Operations(operations)) = &mut edit.document_changes {
+                for operation in operations {
+                    if let DocumentChangeOperation::Op(op) = operation {
+                        match op {
+                            ResourceOp::Create(create) => create.annotation_id = None,
+                            ResourceOp::Rename(rename) => rename.annotation_id = None,
+                            ResourceOp::Delete(delete) => delete.annotation_id = None,
+                        }
+                    }
+                }
+            }
+            edit.change_annotations = None;
+        }
+        Ok(())
+    }
+
+    /// Whether the client supports `LocationLink`s for the goto request of `capability`.
+    fn goto(
+        self,
+        capability: impl FnOnce(&'a TextDocumentClientCapabilities) -> &'a Option<GotoCapability>,
+    ) -> bool {
+        self.client
+            .text_document
+            .as_ref()
+            .and_then(|text_document| capability(text_document).as_ref())
+            .and_then(|goto| goto.link_support)
+            .unwrap_or(false)
+    }
+}
+
+/// Converts markdown to plain text by removing the markers of code fences and headings and
+/// backslash escapes.
+#[must_use]
+pub fn markdown_to_plaintext(markdown: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_code_block = false;
+    for line in markdown.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            lines.push(line.to_owned());
+            continue;
+        }
+        let line = trimmed
+            .strip_prefix('#')
+            .map_or(line, |heading| heading.trim_start_matches('#').trim_start());
+        lines.push(unescape(line));
+    }
+    lines.join("\n")
+}
+
+fn unescape(line: &str) -> String {
+    let mut unescaped = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped) if escaped.is_ascii_punctuation() => unescaped.push(escaped),
+                Some(other) => {
+                    unescaped.push('\\');
+                    unescaped.push(other);
+                }
+                None => unescaped.push('\\'),
+            },
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+/// Drops the item defaults the client does not support and reduces an insert and replace edit
+/// range to its insert range.
+fn completion_item_defaults(
+    defaults: &mut CompletionListItemDefaults,
+    completion: Option<&CompletionClientCapabilities>,
+) {
+    let supported = completion
+        .and_then(|completion| completion.completion_list.as_ref())
+        .and_then(|list| list.item_defaults.as_deref())
+        .unwrap_or_default();
+    let supports = |name: &str| supported.iter().any(|supported| supported == name);
+    if !supports("commitCharacters") {
+        defaults.commit_characters = None;
+    }
+    if !supports("editRange") {
+        defaults.edit_range = None;
+    }
+    if !supports("insertTextFormat") {
+        defaults.insert_text_format = None;
+    }
+    if !supports("insertTextMode") {
+        defaults.insert_text_mode = None;
+    }
+    if !supports("data") {
+        defaults.data = None;
+    }
+
+    let insert_replace = completion
+        .and_then(|completion| completion.completion_item.as_ref())
+        .and_then(|item| item.insert_replace_support)
+        .unwrap_or(false);
+    if !insert_replace
+        && let Some(CompletionListItemDefaultsEditRange::InsertAndReplace { insert, .. }) =
+            defaults.edit_range
+    {
+        defaults.edit_range = Some(CompletionListItemDefaultsEditRange::Range(insert));
+    }
+}
+
+fn supports_markdown(formats: Option<&[MarkupKind]>) -> bool {
+    formats.is_some_and(|formats| formats.contains(&MarkupKind::Markdown))
+}
+
+fn downgrade_markup(content: &mut MarkupContent, formats: Option<&[MarkupKind]>) {
+    if content.kind == MarkupKind::Markdown && !supports_markdown(formats) {
+        content.value = markdown_to_plaintext(&content.value);
+        content.kind = MarkupKind::PlainText;
+    }
+}
+
+fn marked_string_to_plaintext(marked: &MarkedString) -> String {
+    match marked {
+        MarkedString::String(markdown) => markdown_to_plaintext(markdown),
+        MarkedString::LanguageString(code) => code.value.clone(),
+    }
+}
+
+/// Drops the tags the client does not support, all of them if it does not support tags.
+fn retain_supported<T: PartialEq>(tags: &mut Vec<T>, support: Option<&TagSupport<T>>) {
+    tags.retain(|tag| support.is_some_and(|support| support.value_set.contains(tag)));
+}
+
+fn retain_supported_tags<T: PartialEq>(tags: &mut Option<Vec<T>>, support: Option<&TagSupport<T>>) {
+    if let Some(list) = tags {
+        retain_supported(list, support);
+        if list.is_empty() {
+            *tags = None;
+        }
+    }
+}
+
+fn retain_supported_symbol_tags(
+    symbols: &mut [DocumentSymbol],
+    support: Option<&TagSupport<SymbolTag>>,
+) {
+    for symbol in symbols {
+        retain_supported_tags(&mut symbol.tags, support);
+        if let Some(children) = &mut symbol.children {
+            retain_supported_symbol_tags(children, support);
+        }
+    }
+}
+
+fn flatten_symbols(
+    symbols: Vec<DocumentSymbol>,
+    uri: &Uri,
+    container_name: Option<&str>,
+    flat: &mut Vec<SymbolInformation>,
+) {
+    for symbol in symbols {
+        #[expect(
+            deprecated,
+            reason = "the deprecated property is still part of the protocol"
+        )]
+        let (information, children) = (
+            SymbolInformation {
+                name: symbol.name,
+                kind: symbol.kind,
+                tags: symbol.tags,
+                deprecated: symbol.deprecated,
+                location: Location::new(uri.clone(), symbol.range),
+                container_name: container_name.map(ToOwned::to_owned),
+            },
+            symbol.children,
+        );
+        let name = information.name.clone();
+        flat.push(information);
+        if let Some(children) = children {
+            flatten_symbols(children, uri, Some(&name), flat);
+        }
+    }
+}
+
+fn downgrade_links(response: &mut GotoDefinitionResponse, link_support: bool) {
+    if let GotoDefinitionResponse::Link(links) = response
+        && !link_support
+    {
+        let locations = links
+            .drain(..)
+            .map(|link| Location::new(link.target_uri, link.target_selection_range))
+            .collect();
+        *response = GotoDefinitionResponse::Array(locations);
+    }
+}
+
+fn check_resource_op(
+    op: &ResourceOp,
+    capability: Option<&WorkspaceEditClientCapabilities>,
+) -> Result<(), DowngradeError> {
+    let kind = match op {
+        ResourceOp::Create(_) => ResourceOperationKind::Create,
+        ResourceOp::Rename(_) => ResourceOperationKind::Rename,
+        ResourceOp::Delete(_) => ResourceOperationKind::Delete,
+    };
+    let supported = capability
+        .and_then(|workspace_edit| workspace_edit.resource_operations.as_ref())
+        .is_some_and(|kinds| kinds.contains(&kind))
+        && capability
+            .and_then(|workspace_edit| workspace_edit.document_changes)
+            .unwrap_or(false);
+    if supported {
+        Ok(())
+    } else {
+        Err(DowngradeError::UnsupportedResourceOperation(kind))
+    }
+}
+
+fn check_snippets(edit: &TextDocumentEdit) -> Result<(), DowngradeError> {
+    if edit
+        .edits
+        .iter()
+        .any(|item| matches!(item, TextDocumentEditItem::Snippet(_)))
+    {
+        Err(DowngradeError::UnsupportedSnippetEdit(
+            edit.text_document.uri.clone(),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::lsp::{
+        AnnotatedTextEdit, ChangeAnnotation, CompletionList, CreateFile, InsertReplaceEdit,
+        LocationLink, OptionalVersionedTextDocumentIdentifier, Position, Range, SymbolKind,
+    };
+
+    fn capabilities(value: serde_json::Value) -> ClientCapabilities {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn range(line: u32) -> Range {
+        Range::new(Position::new(line, 0), Position::new(line, 4))
+    }
+
+    #[test]
+    fn markdown() {
+        assert_eq!(
+            markdown_to_plaintext("# Title\n\nSome \\*text\\*\n```rust\nfn main() {}\n```"),
+            "Title\n\nSome *text*\nfn main() {}"
+        );
+
+        let mut hover = Hover {
+            contents: HoverContents::Array(vec![
+                MarkedString::from_language_code("rust".into(), "fn main()".into()),
+                MarkedString::from_markdown("## Docs".into()),
+            ]),
+            range: None,
+        };
+        let contents = hover.contents.clone();
+        Downgrader::new(&ClientCapabilities::default()).hover(&mut hover);
+        assert_eq!(hover.contents, contents);
+
+        let client = capabilities(json!({
+            "textDocument": { "hover": { "contentFormat": ["plaintext"] } }
+        }));
+        Downgrader::new(&client).hover(&mut hover);
+        assert_eq!(
+            hover.contents,
+            HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::PlainText,
+                value: "fn main()\n\nDocs".into(),
+            })
+        );
+
+        let client = capabilities(json!({
+            "textDocument": { "hover": { "contentFormat": ["markdown"] } }
+        }));
+        let contents = HoverContents::Scalar(MarkedString::from_markdown("**bold**".into()));
+        let mut hover = Hover {
+            contents: contents.clone(),
+            range: None,
+        };
+        Downgrader::new(&client).hover(&mut hover);
+        assert_eq!(hover.contents, contents);
+    }
+
+    #[test]
+    fn completion_item() {
+        let client = capabilities(json!({
+            "textDocument": {
+                "completion": {
+                    "completionItem": { "deprecatedSupport": true, "tagSupport": { "valueSet": [] } }
+                }
+            }
+        }));
+        let mut item = CompletionItem {
+            label: "foo".into(),
+            label_details: Some(crate::lsp::CompletionItemLabelDetails {
+                detail: Some("(a: i32)".into()),
+                description: None,
+            }),
+            documentation: Some(Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: "`foo`".into(),
+            })),
+            text_edit: Some(CompletionTextEdit::InsertAndReplace(InsertReplaceEdit {
+                new_text: "foo".into(),
+                insert: range(0),
+                replace: range(1),
+            })),
+            tags: Some(vec![CompletionItemTag::DEPRECATED]),
+            ..CompletionItem::default()
+        };
+        let mut response = CompletionResponse::Array(vec![item.clone()]);
+        Downgrader::new(&client).completion_response(&mut response);
+        let CompletionResponse::Array(items) = response else {
+            unreachable!()
+        };
+        assert_eq!(items[0].label_details, None);
+        assert_eq!(items[0].detail.as_deref(), Some("(a: i32)"));
+        assert_eq!(
+            items[0].text_edit,
+            Some(CompletionTextEdit::Edit(TextEdit::new(
+                range(0),
+                "foo".into()
+            )))
+        );
+        assert_eq!(items[0].tags, None);
+        assert_eq!(items[0].deprecated, Some(true));
+        assert_eq!(
+            items[0].documentation,
+            Some(Documentation::String("`foo`".into()))
+        );
+
+        let client = capabilities(json!({
+            "textDocument": {
+                "completion": { "completionItem": { "documentationFormat": ["plaintext"] } }
+            }
+        }));
+        let mut plaintext = item.clone();
+        Downgrader::new(&client).completion_item(&mut plaintext);
+        assert_eq!(
+            plaintext.documentation,
+            Some(Documentation::MarkupContent(MarkupContent {
+                kind: MarkupKind::PlainText,
+                value: "`foo`".into(),
+            }))
+        );
+
+        let client = capabilities(json!({
+            "textDocument": {
+                "completion": {
+                    "completionItem": {
+                        "documentationFormat": ["markdown"],
+                        "insertReplaceSupport": true,
+                        "labelDetailsSupport": true,
+                        "tagSupport": { "valueSet": [1] }
+                    }
+                }
+            }
+        }));
+        let expected = item.clone();
+        Downgrader::new(&client).completion_item(&mut item);
+        assert_eq!(item, expected);
+    }
+
+    #[test]
+    fn completion_list() {
+        let list = CompletionResponse::List(CompletionList {
+            item_defaults: Some(CompletionListItemDefaults {
+                commit_characters: Some(vec![".".into()]),
+                edit_range: Some(CompletionListItemDefaultsEditRange::InsertAndReplace {
+                    insert: range(0),
+                    replace: range(1),
+                }),
+                data: Some(json!(1)),
+                ..CompletionListItemDefaults::default()
+            }),
+            ..CompletionList::default()
+        });
+
+        let mut response = list.clone();
+        Downgrader::new(&ClientCapabilities::default()).completion_response(&mut response);
+        let CompletionResponse::List(downgraded) = &response else {
+            unreachable!()
+        };
+        assert_eq!(downgraded.item_defaults, None);
+
+        let client = capabilities(json!({
+            "textDocument": {
+                "completion": { "completionList": { "itemDefaults": ["editRange", "data"] } }
+            }
+        }));
+        let mut response = list.clone();
+        Downgrader::new(&client).completion_response(&mut response);
+        let CompletionResponse::List(downgraded) = &response else {
+            unreachable!()
+        };
+        assert_eq!(
+            downgraded.item_defaults,
+            Some(CompletionListItemDefaults {
+                edit_range: Some(CompletionListItemDefaultsEditRange::Range(range(0))),
+                data: Some(json!(1)),
+                ..CompletionListItemDefaults::default()
+            })
+        );
+
+        let client = capabilities(json!({
+            "textDocument": {
+                "completion": {
+                    "completionItem": { "insertReplaceSupport": true },
+                    "completionList": {
+                        "itemDefaults": ["commitCharacters", "editRange", "data"]
+                    }
+                }
+            }
+        }));
+        let mut response = list.clone();
+        Downgrader::new(&client).completion_response(&mut response);
+        assert_eq!(response, list);
+    }
+
+    #[test]
+    #[expect(
+        deprecated,
+        reason = "the deprecated property is still part of the protocol"
+    )]
+    fn document_symbols() {
+        let uri: Uri = "file:///a.rs".parse().unwrap();
+        let symbol = |name: &str, line, children| DocumentSymbol {
+            name: name.into(),
+            detail: None,
+            kind: SymbolKind::FUNCTION,
+            tags: Some(vec![SymbolTag::DEPRECATED]),
+            deprecated: None,
+            range: range(line),
+            selection_range: range(line),
+            children,
+        };
+        let information = |name: &str, line, container_name: Option<&str>| SymbolInformation {
+            name: name.into(),
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            deprecated: None,
+            location: Location::new(uri.clone(), range(line)),
+            container_name: container_name.map(ToOwned::to_owned),
+        };
+
+        let mut response = DocumentSymbolResponse::Nested(vec![
+            symbol(
+                "a",
+                0,
+                Some(vec![symbol("b", 1, Some(vec![symbol("c", 2, None)]))]),
+            ),
+            symbol("d", 3, None),
+        ]);
+        Downgrader::new(&ClientCapabilities::default()).document_symbols(&mut response, &uri);
+        assert_eq!(
+            response,
+            DocumentSymbolResponse::Flat(vec![
+                information("a", 0, None),
+                information("b", 1, Some("a")),
+                information("c", 2, Some("b")),
+                information("d", 3, None),
+            ])
+        );
+
+        let client = capabilities(json!({
+            "textDocument": { "documentSymbol": { "hierarchicalDocumentSymbolSupport": true } }
+        }));
+        let mut response = DocumentSymbolResponse::Nested(vec![symbol("a", 0, None)]);
+        Downgrader::new(&client).document_symbols(&mut response, &uri);
+        let DocumentSymbolResponse::Nested(symbols) = response else {
+            unreachable!()
+        };
+        assert_eq!(symbols[0].tags, None);
+    }
+
+    #[test]
+    fn location_links() {
+        let uri: Uri = "file:///a.rs".parse().unwrap();
+        let links = GotoDefinitionResponse::Link(vec![LocationLink {
+            origin_selection_range: None,
+            target_uri: uri.clone(),
+            target_range: range(0),
+            target_selection_range: range(1),
+        }]);
+
+        let mut response = links.clone();
+        let client = capabilities(json!({
+            "textDocument": { "definition": { "linkSupport": true } }
+        }));
+        Downgrader::new(&client).definition(&mut response);
+        assert_eq!(response, links);
+        Downgrader::new(&client).implementation(&mut response);
+        assert_eq!(
+            response,
+            GotoDefinitionResponse::Array(vec![Location::new(uri, range(1))])
+        );
+    }
+
+    #[test]
+    fn workspace_edit() {
+        let uri: Uri = "file:///a.rs".parse().unwrap();
+        let text_document_edit = |items| {
+            DocumentChangeOperation::Edit(TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier {
+                    uri: uri.clone(),
+                    version: Some(1),
+                },
+                edits: items,
+            })
+        };
+        let annotated = TextDocumentEditItem::Annotated(AnnotatedTextEdit {
+            text_edit: TextEdit::new(range(0), "a".into()),
+            annotation_id: "id".into(),
+        });
+        let edit = WorkspaceEdit {
+            changes: None,
+            document_changes: Some(DocumentChanges::Operations(vec![text_document_edit(vec![
+                annotated,
+            ])])),
+            change_annotations: Some(HashMap::from([(
+                "id".to_owned(),
+                ChangeAnnotation {
+                    label: "rename".into(),
+                    needs_confirmation: None,
+                    description: None,
+                },
+            )])),
+        };
+
+        let mut downgraded = edit.clone();
+        Downgrader::new(&ClientCapabilities::default())
+            .workspace_edit(&mut downgraded)
+            .unwrap();
+        assert_eq!(
+            downgraded,
+            WorkspaceEdit::new(HashMap::from([(
+                uri.clone(),
+                vec![TextEdit::new(range(0), "a".into())]
+            )]))
+        );
+
+        let client = capabilities(json!({
+            "workspace": { "workspaceEdit": { "documentChanges": true } }
+        }));
+        let mut downgraded = edit;
+        Downgrader::new(&client)
+            .workspace_edit(&mut downgraded)
+            .unwrap();
+        assert_eq!(downgraded.change_annotations, None);
+        assert_eq!(
+            downgraded.document_changes,
+            Some(DocumentChanges::Operations(vec![text_document_edit(vec![
+                TextDocumentEditItem::Text(TextEdit::new(range(0), "a".into()))
+            ])]))
+        );
+
+        let mut create = WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(vec![
+                DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+                    uri: uri.clone(),
+                    options: None,
+                    annotation_id: None,
+                })),
+            ])),
+            ..WorkspaceEdit::default()
+        };
+        assert_eq!(
+            Downgrader::new(&client).workspace_edit(&mut create),
+            Err(DowngradeError::UnsupportedResourceOperation(
+                ResourceOperationKind::Create
+            ))
+        );
+
+        let repeated = WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(vec![
+                text_document_edit(vec![TextDocumentEditItem::Text(TextEdit::new(
+                    range(0),
+                    "a".into(),
+                ))]),
+                text_document_edit(vec![]),
+            ])),
+            ..WorkspaceEdit::default()
+        };
+        let mut downgraded = repeated.clone();
+        assert_eq!(
+            Downgrader::new(&ClientCapabilities::default()).workspace_edit(&mut downgraded),
+            Err(DowngradeError::RepeatedDocument(uri.clone()))
+        );
+        assert_eq!(downgraded, repeated);
+
+        let repeated = WorkspaceEdit {
+            changes: Some(HashMap::from([(uri.clone(), vec![])])),
+            document_changes: Some(DocumentChanges::Operations(vec![text_document_edit(vec![
+                TextDocumentEditItem::Text(TextEdit::new(range(0), "a".into())),
+            ])])),
+            ..WorkspaceEdit::default()
+        };
+        let mut downgraded = repeated.clone();
+        assert_eq!(
+            Downgrader::new(&ClientCapabilities::default()).workspace_edit(&mut downgraded),
+            Err(DowngradeError::RepeatedDocument(uri))
+        );
+        assert_eq!(downgraded, repeated);
+    }
+}
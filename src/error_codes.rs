@@ -1,6 +1,7 @@
-//! In this module we only define constants for lsp specific error codes.
-//! There are other error codes that are defined in the
-//! [JSON RPC specification](https://www.jsonrpc.org/specification#error_object).
+//! Error codes used in [`ResponseError`](crate::jsonrpc::ResponseError)s.
+//!
+//! Besides the lsp specific error codes this module also defines the error codes
+//! of the [JSON RPC specification](https://www.jsonrpc.org/specification#error_object).
 
 use serde::{Deserialize, Serialize};
 
@@ -10,8 +11,36 @@ use crate::macros::lsp_enum;
 #[serde(transparent)]
 pub struct ErrorCode(i32);
 
+impl ErrorCode {
+    #[must_use]
+    pub const fn new(code: i32) -> Self {
+        Self(code)
+    }
+
+    #[must_use]
+    pub const fn code(self) -> i32 {
+        self.0
+    }
+}
+
 lsp_enum! {
     impl ErrorCode {
+        /// Invalid JSON was received by the server. An error occurred on
+        /// the server while parsing the JSON text.
+        const PARSE_ERROR = -32700;
+
+        /// The JSON sent is not a valid Request object.
+        const INVALID_REQUEST = -32600;
+
+        /// The method does not exist / is not available.
+        const METHOD_NOT_FOUND = -32601;
+
+        /// Invalid method parameter(s).
+        const INVALID_PARAMS = -32602;
+
+        /// Internal JSON-RPC error.
+        const INTERNAL_ERROR = -32603;
+
         /// Defined in the LSP specification but in the range reserved for JSON-RPC error codes,
         /// namely the -32099 to -32000 "Reserved for implementation-defined server-errors." range.
         /// The code has, nonetheless, been left in this range for backwards compatibility reasons.
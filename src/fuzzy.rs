@@ -0,0 +1,257 @@
+//! Fuzzy matching of completion items against the text a user typed.
+//!
+//! Clients filter and sort completion items themselves, similar to `fuzzyScore` of VS Code:
+//! the characters of the typed prefix have to appear in the filter text of an item in order,
+//! and matches at the start of words and consecutive matches score higher. [`rank_completions`]
+//! mirrors that, so that servers can trim long completion lists before sending them and tests
+//! can predict the order a client shows.
+
+use std::cmp::Reverse;
+
+use crate::lsp::CompletionItem;
+
+/// The bonus of a match of the first character of the word.
+const FIRST_CHAR_BONUS: i32 = 8;
+
+/// The bonus of a match at the start of a word after a separator or at a camel case hump.
+const WORD_START_BONUS: i32 = 4;
+
+/// The bonus of a match directly after the match of the previous character of the pattern.
+const CONSECUTIVE_BONUS: i32 = 5;
+
+/// The bonus of a match with the same case.
+const SAME_CASE_BONUS: i32 = 1;
+
+/// The score of a match of a pattern in a word.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyScore {
+    /// The score, higher is better.
+    pub score: i32,
+    /// The indices of the characters of the word the characters of the pattern matched.
+    pub matches: Vec<usize>,
+}
+
+/// Scores how well `pattern` matches `word`, or returns `None` if it does not match.
+///
+/// The characters of `pattern` have to appear in `word` in order, ignoring case, and the first
+/// one has to match at the start of a word: the start of `word`, after a separator like `_`
+/// or `.`, or at a camel case hump. An empty pattern matches every word with a score of `0`.
+///
+/// # Examples
+///
+/// ```
+/// use ls_types::fuzzy::fuzzy_score;
+///
+/// let score = fuzzy_score("gcb", "getCodeBlock").unwrap();
+/// assert_eq!(score.matches, [0, 3, 7]);
+/// assert!(score.score > fuzzy_score("gcb", "getcodeblock").unwrap().score);
+/// assert_eq!(fuzzy_score("block", "getBlob"), None);
+/// ```
+#[must_use]
+pub fn fuzzy_score(pattern: &str, word: &str) -> Option<FuzzyScore> {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let word: Vec<char> = word.chars().collect();
+    if pattern.is_empty() {
+        return Some(FuzzyScore {
+            score: 0,
+            matches: vec![],
+        });
+    }
+    if pattern.len() > word.len() {
+        return None;
+    }
+
+    // The best score of matching `pattern[..=i]` with `pattern[i]` matching `word[j]`, and the
+    // index the previous character of the pattern matched for that score.
+    let mut scores = vec![vec![None; word.len()]; pattern.len()];
+    let mut previous = vec![vec![0; word.len()]; pattern.len()];
+    for (i, &pattern_char) in pattern.iter().enumerate() {
+        // The best score of the previous row before `j - 1`, that is with a gap.
+        let mut best_gap: Option<(i32, usize)> = None;
+        for j in i..word.len() {
+            if i > 0
+                && j >= 2
+                && let Some(score) = scores[i - 1][j - 2]
+                && best_gap.is_none_or(|(best, _)| score > best)
+            {
+                best_gap = Some((score, j - 2));
+            }
+            if !eq_ignore_case(pattern_char, word[j]) {
+                continue;
+            }
+
+            let word_start = is_word_start(&word, j);
+            let mut score = 1;
+            if pattern_char == word[j] {
+                score += SAME_CASE_BONUS;
+            }
+            if j == 0 {
+                score += FIRST_CHAR_BONUS;
+            } else if word_start {
+                score += WORD_START_BONUS;
+            }
+
+            if i == 0 {
+                if word_start {
+                    scores[i][j] = Some(score);
+                }
+                continue;
+            }
+            let consecutive =
+                scores[i - 1][j - 1].map(|previous| (previous + CONSECUTIVE_BONUS, j - 1));
+            let best = match (consecutive, best_gap) {
+                (Some(consecutive), Some(gap)) if gap.0 > consecutive.0 => Some(gap),
+                (Some(consecutive), _) => Some(consecutive),
+                (None, gap) => gap,
+            };
+            if let Some((best, k)) = best {
+                scores[i][j] = Some(best + score);
+                previous[i][j] = k;
+            }
+        }
+    }
+
+    let last = pattern.len() - 1;
+    let (score, mut j) = scores[last]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| score.map(|score| (score, j)))
+        .max_by_key(|&(score, j)| (score, Reverse(j)))?;
+    let mut matches = vec![0; pattern.len()];
+    for i in (0..pattern.len()).rev() {
+        matches[i] = j;
+        j = previous[i][j];
+    }
+    Some(FuzzyScore { score, matches })
+}
+
+/// Filters and sorts completion items like a client does after the user typed `prefix`.
+///
+/// Items whose `filter_text`, or `label` if there is none, does not match `prefix` are
+/// removed. The others are sorted by their [`fuzzy_score`], then by their `sort_text`, or
+/// `label` if there is none, and then by their `label`.
+pub fn rank_completions(items: &mut Vec<CompletionItem>, prefix: &str) {
+    let mut scored: Vec<(i32, CompletionItem)> = items
+        .drain(..)
+        .filter_map(|item| {
+            let filter_text = item.filter_text.as_deref().unwrap_or(&item.label);
+            fuzzy_score(prefix, filter_text).map(|score| (score.score, item))
+        })
+        .collect();
+    scored.sort_by(|(score_a, a), (score_b, b)| {
+        score_b
+            .cmp(score_a)
+            .then_with(|| sort_text(a).cmp(sort_text(b)))
+            .then_with(|| a.label.cmp(&b.label))
+    });
+    items.extend(scored.into_iter().map(|(_, item)| item));
+}
+
+/// The index of the item a client selects in ranked completion items: the first item that
+/// is `preselect`ed, otherwise the first item.
+#[must_use]
+pub fn selected_completion(items: &[CompletionItem]) -> Option<usize> {
+    if items.is_empty() {
+        return None;
+    }
+    Some(
+        items
+            .iter()
+            .position(|item| item.preselect == Some(true))
+            .unwrap_or(0),
+    )
+}
+
+fn sort_text(item: &CompletionItem) -> &str {
+    item.sort_text.as_deref().unwrap_or(&item.label)
+}
+
+fn eq_ignore_case(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Whether the character at `index` starts a word: the first character, a character after a
+/// separator, an upper case character after a lower case one or a digit after a non-digit.
+#[expect(
+    clippy::suspicious_operation_groupings,
+    reason = "a camel case hump is a change of case"
+)]
+fn is_word_start(word: &[char], index: usize) -> bool {
+    let Some(previous) = index.checked_sub(1).map(|previous| word[previous]) else {
+        return true;
+    };
+    let current = word[index];
+    (!previous.is_alphanumeric() && current.is_alphanumeric())
+        || (previous.is_lowercase() && current.is_uppercase())
+        || (!previous.is_numeric() && current.is_numeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(label: &str) -> CompletionItem {
+        CompletionItem {
+            label: label.to_owned(),
+            ..CompletionItem::default()
+        }
+    }
+
+    fn labels(items: &[CompletionItem]) -> Vec<&str> {
+        items.iter().map(|item| item.label.as_str()).collect()
+    }
+
+    #[test]
+    fn score() {
+        assert_eq!(fuzzy_score("", "anything").unwrap().score, 0);
+        assert_eq!(fuzzy_score("abc", "ab"), None);
+        assert_eq!(fuzzy_score("ba", "ab"), None);
+        // The first character has to match at the start of a word.
+        assert_eq!(fuzzy_score("oo", "foo"), None);
+        assert_eq!(fuzzy_score("bar", "foo_bar").unwrap().matches, [4, 5, 6]);
+
+        let score = |pattern, word| fuzzy_score(pattern, word).unwrap().score;
+        assert!(score("foo", "foo") > score("foo", "Foo"));
+        assert!(score("foo", "fooBar") > score("foo", "f_o_o"));
+        assert!(score("fb", "fooBar") > score("fb", "foobar"));
+        // The consecutive match is preferred over the earlier scattered one.
+        assert_eq!(fuzzy_score("ab", "a_ab").unwrap().matches, [2, 3]);
+    }
+
+    #[test]
+    fn rank() {
+        let mut items = vec![
+            item("format"),
+            item("for_each"),
+            CompletionItem {
+                sort_text: Some("0".to_owned()),
+                ..item("from_fn")
+            },
+            item("filter"),
+            item("fold"),
+            CompletionItem {
+                filter_text: Some("iter".to_owned()),
+                ..item("into_iter")
+            },
+            CompletionItem {
+                preselect: Some(true),
+                ..item("for")
+            },
+        ];
+        rank_completions(&mut items, "fo");
+        assert_eq!(
+            labels(&items),
+            ["fold", "for", "for_each", "format", "from_fn"]
+        );
+        assert_eq!(selected_completion(&items), Some(1));
+
+        rank_completions(&mut items, "");
+        assert_eq!(
+            labels(&items),
+            ["from_fn", "fold", "for", "for_each", "format"]
+        );
+        rank_completions(&mut items, "xyz");
+        assert!(items.is_empty());
+        assert_eq!(selected_completion(&items), None);
+    }
+}
@@ -0,0 +1,649 @@
+//! Matching of the glob patterns of [`GlobPattern`], [`FileSystemWatcher`] and
+//! [`FileOperationFilter`].
+//!
+//! Glob patterns can have the following syntax:
+//! - `*` to match zero or more characters in a path segment
+//! - `?` to match on one character in a path segment
+//! - `**` to match any number of path segments, including none
+//! - `{}` to group sub patterns into an OR expression, for example `**/*.{ts,js}`
+//! - `[]` to declare a range of characters to match in a path segment, for example
+//!   `example.[0-9]`
+//! - `[!...]` to negate a range of characters to match in a path segment, for example
+//!   `example.[!0-9]`
+//!
+//! [`FileSystemWatcher`]: crate::lsp::FileSystemWatcher
+
+use std::{fmt, path::Path};
+
+use crate::{
+    Uri,
+    lsp::{FileOperationFilter, FileOperationPatternKind, GlobPattern, OneOf},
+};
+
+/// The maximum number of alternatives the groups of a pattern may expand to.
+const MAX_ALTERNATIVES: usize = 1024;
+
+/// An error in a glob pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlobError {
+    /// The group opened at byte `index` is not closed.
+    UnclosedGroup { index: usize },
+    /// The range opened at byte `index` is not closed.
+    UnclosedRange { index: usize },
+    /// The range opened at byte `index` contains a range whose end is before its start.
+    InvalidRange { index: usize },
+    /// The groups of the pattern expand to too many alternatives.
+    TooManyAlternatives,
+}
+
+impl fmt::Display for GlobError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnclosedGroup { index } => write!(f, "unclosed `{{` at {index}"),
+            Self::UnclosedRange { index } => write!(f, "unclosed `[` at {index}"),
+            Self::InvalidRange { index } => write!(f, "invalid character range at {index}"),
+            Self::TooManyAlternatives => write!(
+                f,
+                "the groups expand to more than {MAX_ALTERNATIVES} alternatives"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GlobError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    /// `?`
+    Any,
+    /// `*`
+    Star,
+    /// `**` as a whole path segment, matching any number of segments.
+    GlobStar,
+    /// `**/`, matching zero or more segments including their trailing `/`.
+    GlobStarSlash,
+    /// `/**` at the end of the pattern, matching nothing or `/` followed by anything.
+    SlashGlobStar,
+    Range {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    /// `{...}`, only used while parsing.
+    Group(Vec<Vec<Self>>),
+}
+
+/// A compiled glob pattern that is matched against `/` separated paths.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    /// The pattern with its groups expanded.
+    alternatives: Vec<Vec<Token>>,
+    ignore_case: bool,
+}
+
+impl Glob {
+    /// Compiles a glob pattern.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a group or range is not closed, a range is invalid or the groups
+    /// expand to too many alternatives.
+    pub fn new(pattern: &str) -> Result<Self, GlobError> {
+        let mut parser = Parser {
+            chars: pattern.char_indices().peekable(),
+        };
+        let tokens = parser.parse_sequence(false)?;
+        let alternatives = expand(tokens)?.into_iter().map(normalize).collect();
+        Ok(Self {
+            alternatives,
+            ignore_case: false,
+        })
+    }
+
+    /// Matches characters regardless of their case.
+    #[must_use]
+    pub const fn ignore_case(mut self, ignore_case: bool) -> Self {
+        self.ignore_case = ignore_case;
+        self
+    }
+
+    /// Whether the `/` separated `path` matches the pattern.
+    #[must_use]
+    pub fn is_match(&self, path: &str) -> bool {
+        let chars: Vec<char> = path.chars().collect();
+        self.alternatives.iter().any(|tokens| {
+            Matcher {
+                tokens,
+                chars: &chars,
+                ignore_case: self.ignore_case,
+                failed: vec![false; (tokens.len() + 1) * (chars.len() + 1)],
+            }
+            .is_match(0, 0)
+        })
+    }
+
+    /// Whether the path of the `uri` matches the pattern.
+    #[must_use]
+    pub fn is_match_uri(&self, uri: &Uri) -> bool {
+        self.is_match(&decoded_path(uri))
+    }
+
+    /// Whether the file system `path` matches the pattern.
+    #[must_use]
+    pub fn is_match_path(&self, path: &Path) -> bool {
+        self.is_match(&slash_path(path))
+    }
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+    /// Parses tokens until the end of the pattern or, in a group, until a `,` or `}`.
+    fn parse_sequence(&mut self, in_group: bool) -> Result<Vec<Token>, GlobError> {
+        let mut tokens = vec![];
+        while let Some(&(index, c)) = self.chars.peek() {
+            if in_group && matches!(c, ',' | '}') {
+                break;
+            }
+            self.chars.next();
+            tokens.push(match c {
+                '?' => Token::Any,
+                '*' => Token::Star,
+                '[' => self.parse_range(index)?,
+                '{' => self.parse_group(index)?,
+                c => Token::Char(c),
+            });
+        }
+        Ok(tokens)
+    }
+
+    fn parse_group(&mut self, index: usize) -> Result<Token, GlobError> {
+        let mut alternatives = vec![];
+        loop {
+            alternatives.push(self.parse_sequence(true)?);
+            match self.chars.next() {
+                Some((_, ',')) => {}
+                Some((_, '}')) => return Ok(Token::Group(alternatives)),
+                _ => return Err(GlobError::UnclosedGroup { index }),
+            }
+        }
+    }
+
+    fn parse_range(&mut self, index: usize) -> Result<Token, GlobError> {
+        let negated = self.chars.next_if(|&(_, c)| c == '!' || c == '^').is_some();
+        let mut ranges = vec![];
+        loop {
+            let start = match self.chars.next() {
+                Some((_, ']')) if !ranges.is_empty() => break,
+                Some((_, c)) => c,
+                None => return Err(GlobError::UnclosedRange { index }),
+            };
+            let mut end = start;
+            if self.chars.next_if(|&(_, c)| c == '-').is_some() {
+                match self.chars.next() {
+                    // A trailing `-` is matched literally.
+                    Some((_, ']')) => {
+                        ranges.push((start, start));
+                        ranges.push(('-', '-'));
+                        break;
+                    }
+                    Some((_, c)) => end = c,
+                    None => return Err(GlobError::UnclosedRange { index }),
+                }
+            }
+            if end < start {
+                return Err(GlobError::InvalidRange { index });
+            }
+            ranges.push((start, end));
+        }
+        Ok(Token::Range { negated, ranges })
+    }
+}
+
+/// Expands the groups of `tokens` into the alternatives they describe.
+fn expand(tokens: Vec<Token>) -> Result<Vec<Vec<Token>>, GlobError> {
+    let mut alternatives = vec![vec![]];
+    for token in tokens {
+        match token {
+            Token::Group(group) => {
+                let mut expanded_group = vec![];
+                for alternative in group {
+                    expanded_group.extend(expand(alternative)?);
+                }
+                if alternatives.len() * expanded_group.len() > MAX_ALTERNATIVES {
+                    return Err(GlobError::TooManyAlternatives);
+                }
+                alternatives = alternatives
+                    .iter()
+                    .flat_map(|prefix| {
+                        expanded_group.iter().map(move |suffix| {
+                            let mut alternative: Vec<Token> = prefix.clone();
+                            alternative.extend(suffix.iter().cloned());
+                            alternative
+                        })
+                    })
+                    .collect();
+            }
+            token => {
+                for alternative in &mut alternatives {
+                    alternative.push(token.clone());
+                }
+            }
+        }
+    }
+    Ok(alternatives)
+}
+
+/// Replaces runs of `*` with a single [`Token::Star`], or with a globstar if they form a whole
+/// path segment.
+fn normalize(tokens: Vec<Token>) -> Vec<Token> {
+    let mut normalized: Vec<Token> = Vec::with_capacity(tokens.len());
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        if token != Token::Star {
+            normalized.push(token);
+            continue;
+        }
+        let mut stars = 1;
+        while tokens.next_if_eq(&Token::Star).is_some() {
+            stars += 1;
+        }
+        let segment_start = matches!(normalized.last(), None | Some(Token::Char('/')));
+        if stars == 1 || !segment_start {
+            normalized.push(Token::Star);
+        } else if tokens.next_if_eq(&Token::Char('/')).is_some() {
+            normalized.push(Token::GlobStarSlash);
+        } else if tokens.peek().is_some() {
+            normalized.push(Token::Star);
+        } else if normalized.last() == Some(&Token::Char('/')) {
+            normalized.pop();
+            normalized.push(Token::SlashGlobStar);
+        } else {
+            normalized.push(Token::GlobStar);
+        }
+    }
+    normalized
+}
+
+struct Matcher<'a> {
+    tokens: &'a [Token],
+    chars: &'a [char],
+    ignore_case: bool,
+    /// The states `(token, char)` that are known not to match.
+    failed: Vec<bool>,
+}
+
+impl Matcher<'_> {
+    fn is_match(&mut self, token: usize, char: usize) -> bool {
+        let state = token * (self.chars.len() + 1) + char;
+        if self.failed[state] {
+            return false;
+        }
+        let rest = &self.chars[char..];
+        let is_match = match self.tokens.get(token) {
+            None => rest.is_empty(),
+            Some(Token::Char(c)) => {
+                rest.first().is_some_and(|&next| self.eq(*c, next))
+                    && self.is_match(token + 1, char + 1)
+            }
+            Some(Token::Any) => {
+                rest.first().is_some_and(|&next| next != '/') && self.is_match(token + 1, char + 1)
+            }
+            Some(Token::Range { negated, ranges }) => {
+                rest.first().is_some_and(|&next| {
+                    next != '/'
+                        && ranges
+                            .iter()
+                            .any(|&(start, end)| self.in_range(next, start, end))
+                            != *negated
+                }) && self.is_match(token + 1, char + 1)
+            }
+            Some(Token::Star) => {
+                let segment_len = rest.iter().take_while(|&&c| c != '/').count();
+                (0..=segment_len).any(|len| self.is_match(token + 1, char + len))
+            }
+            Some(Token::GlobStar) => {
+                (0..=rest.len()).any(|len| self.is_match(token + 1, char + len))
+            }
+            Some(Token::GlobStarSlash) => {
+                self.is_match(token + 1, char)
+                    || (0..rest.len())
+                        .filter(|&len| rest[len] == '/')
+                        .any(|len| self.is_match(token + 1, char + len + 1))
+            }
+            Some(Token::SlashGlobStar) => rest.is_empty() || rest[0] == '/',
+            Some(Token::Group(_)) => unreachable!("groups are expanded"),
+        };
+        if !is_match {
+            self.failed[state] = true;
+        }
+        is_match
+    }
+
+    fn eq(&self, a: char, b: char) -> bool {
+        a == b || (self.ignore_case && a.to_lowercase().eq(b.to_lowercase()))
+    }
+
+    fn in_range(&self, c: char, start: char, end: char) -> bool {
+        (start..=end).contains(&c)
+            || (self.ignore_case
+                && c.to_lowercase()
+                    .chain(c.to_uppercase())
+                    .any(|c| (start..=end).contains(&c)))
+    }
+}
+
+/// The base a [`RelativePattern`](crate::lsp::RelativePattern) is matched relative to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Base {
+    /// The URI without the path, like `file://host`.
+    prefix: String,
+    /// The decoded path without a trailing `/`.
+    path: String,
+}
+
+impl Base {
+    fn new(uri: &Uri) -> Self {
+        let path = decoded_path(uri);
+        Self {
+            prefix: uri_prefix(uri),
+            path: path.strip_suffix('/').unwrap_or(&path).to_owned(),
+        }
+    }
+
+    /// Strips the base from `path`, returning the path relative to the base.
+    fn strip<'a>(&self, path: &'a str) -> Option<&'a str> {
+        let rest = path.strip_prefix(&self.path)?;
+        if rest.is_empty() {
+            Some(rest)
+        } else {
+            rest.strip_prefix('/')
+        }
+    }
+}
+
+/// A compiled [`GlobPattern`].
+///
+/// A string pattern is matched against the whole path, a relative pattern against the path
+/// relative to its base.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GlobPatternMatcher {
+    base: Option<Base>,
+    glob: Glob,
+}
+
+impl GlobPatternMatcher {
+    /// Compiles a glob pattern.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pattern is invalid, see [`Glob::new`].
+    pub fn new(pattern: &GlobPattern) -> Result<Self, GlobError> {
+        Ok(match pattern {
+            GlobPattern::String(pattern) => Self {
+                base: None,
+                glob: Glob::new(pattern)?,
+            },
+            GlobPattern::Relative(relative) => Self {
+                base: Some(Base::new(match &relative.base_uri {
+                    OneOf::Left(folder) => &folder.uri,
+                    OneOf::Right(uri) => uri,
+                })),
+                glob: Glob::new(&relative.pattern)?,
+            },
+        })
+    }
+
+    /// Whether the `uri` matches the pattern.
+    #[must_use]
+    pub fn is_match(&self, uri: &Uri) -> bool {
+        self.is_match_relative(&decoded_path(uri), |base| base.prefix == uri_prefix(uri))
+    }
+
+    /// Whether the file system `path` matches the pattern.
+    ///
+    /// A relative pattern only matches paths if its base is a `file` URI.
+    #[must_use]
+    pub fn is_match_path(&self, path: &Path) -> bool {
+        self.is_match_relative(&slash_path(path), |base| base.prefix.starts_with("file:"))
+    }
+
+    /// Matches `path`, relative to the base if `in_base` accepts the base.
+    fn is_match_relative(&self, path: &str, in_base: impl FnOnce(&Base) -> bool) -> bool {
+        self.base.as_ref().map_or_else(
+            || self.glob.is_match(path),
+            |base| {
+                in_base(base)
+                    && base
+                        .strip(path)
+                        .is_some_and(|path| self.glob.is_match(path))
+            },
+        )
+    }
+}
+
+/// A compiled [`FileOperationFilter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileOperationFilterMatcher {
+    scheme: Option<String>,
+    kind: Option<FileOperationPatternKind>,
+    glob: Glob,
+}
+
+impl FileOperationFilterMatcher {
+    /// Compiles a file operation filter.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the glob of the pattern is invalid, see [`Glob::new`].
+    pub fn new(filter: &FileOperationFilter) -> Result<Self, GlobError> {
+        let pattern = &filter.pattern;
+        let ignore_case = pattern
+            .options
+            .as_ref()
+            .and_then(|options| options.ignore_case)
+            .unwrap_or(false);
+        Ok(Self {
+            scheme: filter.scheme.clone(),
+            kind: pattern.matches.clone(),
+            glob: Glob::new(&pattern.glob)?.ignore_case(ignore_case),
+        })
+    }
+
+    /// Whether the file or, if `is_folder` is set, folder `uri` matches the filter.
+    #[must_use]
+    pub fn is_match(&self, uri: &Uri, is_folder: bool) -> bool {
+        let kind_matches = match self.kind {
+            None => true,
+            Some(FileOperationPatternKind::File) => !is_folder,
+            Some(FileOperationPatternKind::Folder) => is_folder,
+        };
+        kind_matches
+            && self
+                .scheme
+                .as_ref()
+                .is_none_or(|scheme| scheme.eq_ignore_ascii_case(uri.scheme().as_str()))
+            && self.glob.is_match_uri(uri)
+    }
+}
+
+fn decoded_path(uri: &Uri) -> String {
+    uri.path().decode().into_string_lossy().into_owned()
+}
+
+/// The scheme and authority of a URI.
+fn uri_prefix(uri: &Uri) -> String {
+    let scheme = uri.scheme().as_str().to_ascii_lowercase();
+    uri.authority().map_or_else(
+        || format!("{scheme}:"),
+        |authority| format!("{scheme}://{}", authority.as_str()),
+    )
+}
+
+/// Converts a file system path to a `/` separated path that starts with `/`.
+fn slash_path(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    let path = if cfg!(windows) {
+        path.replace('\\', "/")
+    } else {
+        path.into_owned()
+    };
+    if path.starts_with('/') {
+        path
+    } else {
+        format!("/{path}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::{
+        FileOperationPattern, FileOperationPatternOptions, RelativePattern, WorkspaceFolder,
+    };
+
+    fn is_match(pattern: &str, path: &str) -> bool {
+        Glob::new(pattern).unwrap().is_match(path)
+    }
+
+    fn uri(uri: &str) -> Uri {
+        uri.parse().unwrap()
+    }
+
+    #[test]
+    fn syntax() {
+        assert!(is_match("*.rs", "main.rs"));
+        assert!(is_match("*.rs", ".rs"));
+        assert!(!is_match("*.rs", "src/main.rs"));
+        assert!(is_match("src/*", "src/main.rs"));
+        assert!(!is_match("src/*", "src/bin/main.rs"));
+
+        assert!(is_match("**", "src/bin/main.rs"));
+        assert!(is_match("**/*.rs", "main.rs"));
+        assert!(is_match("**/*.rs", "/src/bin/main.rs"));
+        assert!(is_match("src/**/*.rs", "src/main.rs"));
+        assert!(is_match("src/**/*.rs", "src/a/b/main.rs"));
+        assert!(!is_match("src/**/*.rs", "srcx/main.rs"));
+        assert!(is_match("src/**", "src"));
+        assert!(is_match("src/**", "src/a/b"));
+        assert!(!is_match("src/**", "srcx"));
+        // `**` that is not a whole segment is a `*`.
+        assert!(is_match("a**", "abc"));
+        assert!(!is_match("a**", "a/b"));
+
+        assert!(is_match("file.?s", "file.rs"));
+        assert!(!is_match("file.?s", "file.s"));
+        assert!(!is_match("a?b", "a/b"));
+
+        assert!(is_match("**/*.{ts,js}", "a/b.js"));
+        assert!(is_match("**/*.{ts,js}", "a/b.ts"));
+        assert!(!is_match("**/*.{ts,js}", "a/b.rs"));
+        assert!(is_match("{src/**,tests}/*.rs", "src/a/b.rs"));
+        assert!(is_match("{src/**,tests}/*.rs", "tests/b.rs"));
+        assert!(is_match("{a,{b,c}d}", "cd"));
+        assert!(is_match("a{,.bak}", "a"));
+
+        assert!(is_match("example.[0-9]", "example.5"));
+        assert!(!is_match("example.[0-9]", "example.a"));
+        assert!(is_match("example.[!0-9]", "example.a"));
+        assert!(!is_match("example.[!0-9]", "example.5"));
+        assert!(is_match("[]a-]", "]"));
+        assert!(is_match("[]a-]", "-"));
+        assert!(!is_match("[!a]", "/"));
+
+        assert_eq!(
+            Glob::new("a{b,c"),
+            Err(GlobError::UnclosedGroup { index: 1 })
+        );
+        assert_eq!(
+            Glob::new("a[bc"),
+            Err(GlobError::UnclosedRange { index: 1 })
+        );
+        assert_eq!(
+            Glob::new("[z-a]"),
+            Err(GlobError::InvalidRange { index: 0 })
+        );
+        assert_eq!(
+            Glob::new(&"{a,b}".repeat(11)),
+            Err(GlobError::TooManyAlternatives)
+        );
+    }
+
+    #[test]
+    fn ignore_case() {
+        let glob = Glob::new("**/*.[a-z]S").unwrap();
+        assert!(!glob.is_match("SRC/MAIN.RS"));
+        let glob = glob.ignore_case(true);
+        assert!(glob.is_match("SRC/MAIN.RS"));
+        assert!(glob.is_match("src/main.rs"));
+    }
+
+    #[test]
+    fn glob_pattern() {
+        let matcher = GlobPatternMatcher::new(&GlobPattern::String("**/*.rs".to_owned())).unwrap();
+        assert!(matcher.is_match(&uri("file:///project/src/main.rs")));
+        assert!(matcher.is_match(&uri("untitled:new.rs")));
+        assert!(matcher.is_match(&uri("file:///my%20project/main.rs")));
+        assert!(matcher.is_match_path(Path::new("/project/main.rs")));
+
+        let folder = WorkspaceFolder {
+            uri: uri("file:///my%20project/"),
+            name: "project".to_owned(),
+        };
+        let matcher = GlobPatternMatcher::new(&GlobPattern::Relative(RelativePattern {
+            base_uri: OneOf::Left(folder),
+            pattern: "src/*.rs".to_owned(),
+        }))
+        .unwrap();
+        assert!(matcher.is_match(&uri("file:///my%20project/src/main.rs")));
+        assert!(!matcher.is_match(&uri("file:///other/src/main.rs")));
+        assert!(!matcher.is_match(&uri("file:///my%20projects/src/main.rs")));
+        assert!(!matcher.is_match(&uri("file://host/my%20project/src/main.rs")));
+        assert!(!matcher.is_match(&uri("untitled:/my%20project/src/main.rs")));
+        assert!(matcher.is_match_path(Path::new("/my project/src/main.rs")));
+
+        let matcher = GlobPatternMatcher::new(&GlobPattern::Relative(RelativePattern {
+            base_uri: OneOf::Right(uri("file:///project")),
+            pattern: "**".to_owned(),
+        }))
+        .unwrap();
+        assert!(matcher.is_match(&uri("file:///project")));
+        assert!(matcher.is_match(&uri("file:///project/a/b")));
+    }
+
+    #[test]
+    fn file_operation_filter() {
+        let filter = |scheme: Option<&str>, kind, ignore_case| {
+            FileOperationFilterMatcher::new(&FileOperationFilter {
+                scheme: scheme.map(ToOwned::to_owned),
+                pattern: FileOperationPattern {
+                    glob: "**/*.rs".to_owned(),
+                    matches: kind,
+                    options: Some(FileOperationPatternOptions {
+                        ignore_case: Some(ignore_case),
+                    }),
+                },
+            })
+            .unwrap()
+        };
+        let file = uri("file:///src/main.rs");
+
+        assert!(filter(None, None, false).is_match(&file, false));
+        assert!(filter(None, None, false).is_match(&file, true));
+        assert!(filter(Some("file"), None, false).is_match(&file, false));
+        assert!(!filter(Some("untitled"), None, false).is_match(&file, false));
+
+        let files = filter(None, Some(FileOperationPatternKind::File), false);
+        assert!(files.is_match(&file, false));
+        assert!(!files.is_match(&file, true));
+        let folders = filter(None, Some(FileOperationPatternKind::Folder), false);
+        assert!(!folders.is_match(&file, false));
+        assert!(folders.is_match(&file, true));
+
+        let upper = uri("file:///SRC/MAIN.RS");
+        assert!(!filter(None, None, false).is_match(&upper, false));
+        assert!(filter(None, None, true).is_match(&upper, false));
+    }
+}
@@ -0,0 +1,494 @@
+//! JSON-RPC 2.0 message envelopes as used by the base protocol of LSP.
+//!
+//! Based on <https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#abstractMessage>
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize, de::Error as _, ser::SerializeStruct};
+use serde_json::Value;
+
+use crate::{error_codes::ErrorCode, lsp::NumberOrString};
+
+/// The JSON-RPC version implemented by this module.
+pub const JSONRPC_VERSION: &str = "2.0";
+
+/// The id of a request, either a number or a string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum RequestId {
+    Number(i32),
+    String(String),
+}
+
+impl From<i32> for RequestId {
+    fn from(from: i32) -> Self {
+        Self::Number(from)
+    }
+}
+
+impl From<String> for RequestId {
+    fn from(from: String) -> Self {
+        Self::String(from)
+    }
+}
+
+impl From<&str> for RequestId {
+    fn from(from: &str) -> Self {
+        Self::String(from.to_owned())
+    }
+}
+
+impl From<NumberOrString> for RequestId {
+    fn from(from: NumberOrString) -> Self {
+        match from {
+            NumberOrString::Number(number) => Self::Number(number),
+            NumberOrString::String(string) => Self::String(string),
+        }
+    }
+}
+
+impl From<RequestId> for NumberOrString {
+    fn from(from: RequestId) -> Self {
+        match from {
+            RequestId::Number(number) => Self::Number(number),
+            RequestId::String(string) => Self::String(string),
+        }
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(number) => write!(f, "{number}"),
+            Self::String(string) => write!(f, "{string:?}"),
+        }
+    }
+}
+
+/// Any message exchanged between the client and the server.
+///
+/// Deserialization inspects the fields of the message to tell the variants apart:
+/// a message with a `method` and an `id` is a [`Request`], a message with a `method`
+/// and without an `id` is a [`Notification`] and a message with a `result` or an
+/// `error` is a [`Response`]. Anything else is rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Request(Request),
+    Response(Response),
+    Notification(Notification),
+}
+
+impl From<Request> for Message {
+    fn from(from: Request) -> Self {
+        Self::Request(from)
+    }
+}
+
+impl From<Response> for Message {
+    fn from(from: Response) -> Self {
+        Self::Response(from)
+    }
+}
+
+impl From<Notification> for Message {
+    fn from(from: Notification) -> Self {
+        Self::Notification(from)
+    }
+}
+
+impl Serialize for Message {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            Self::Request(request) => request.serialize(serializer),
+            Self::Response(response) => response.serialize(serializer),
+            Self::Notification(notification) => notification.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Message {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        RawMessage::deserialize(deserializer)?
+            .into_message()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// A request message to describe a request between the client and the server.
+/// Every processed request must send a response back to the sender of the request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    /// The request id.
+    pub id: RequestId,
+
+    /// The method to be invoked.
+    pub method: String,
+
+    /// The method's params. `Value::Null` if the request has no params.
+    pub params: Value,
+}
+
+impl Request {
+    /// Creates the request for `R` with the given id and params.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the params can not be serialized.
+    pub fn new<R>(id: RequestId, params: R::Params) -> Result<Self, serde_json::Error>
+    where
+        R: crate::request::Request,
+    {
+        Ok(Self {
+            id,
+            method: R::METHOD.to_owned(),
+            params: serde_json::to_value(params)?,
+        })
+    }
+}
+
+impl Serialize for Request {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let len = if self.params.is_null() { 3 } else { 4 };
+        let mut state = serializer.serialize_struct("Request", len)?;
+        state.serialize_field("jsonrpc", JSONRPC_VERSION)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("method", &self.method)?;
+        if !self.params.is_null() {
+            state.serialize_field("params", &self.params)?;
+        }
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Request {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match Message::deserialize(deserializer)? {
+            Message::Request(request) => Ok(request),
+            _ => Err(D::Error::custom("expected a request message")),
+        }
+    }
+}
+
+/// A notification message. A processed notification message must not send a response back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    /// The method to be invoked.
+    pub method: String,
+
+    /// The notification's params. `Value::Null` if the notification has no params.
+    pub params: Value,
+}
+
+impl Notification {
+    /// Creates the notification for `N` with the given params.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the params can not be serialized.
+    pub fn new<N>(params: N::Params) -> Result<Self, serde_json::Error>
+    where
+        N: crate::notification::Notification,
+    {
+        Ok(Self {
+            method: N::METHOD.to_owned(),
+            params: serde_json::to_value(params)?,
+        })
+    }
+}
+
+impl Serialize for Notification {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let len = if self.params.is_null() { 2 } else { 3 };
+        let mut state = serializer.serialize_struct("Notification", len)?;
+        state.serialize_field("jsonrpc", JSONRPC_VERSION)?;
+        state.serialize_field("method", &self.method)?;
+        if !self.params.is_null() {
+            state.serialize_field("params", &self.params)?;
+        }
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Notification {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match Message::deserialize(deserializer)? {
+            Message::Notification(notification) => Ok(notification),
+            _ => Err(D::Error::custom("expected a notification message")),
+        }
+    }
+}
+
+/// A response message sent as a result of a request.
+///
+/// Exactly one of `result` and `error` is sent on the wire. If `error` is `None`
+/// the response is successful and a missing `result` is sent as `null`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    /// The request id. `None` if the id of the request could not be determined,
+    /// e.g. because the request could not be parsed.
+    pub id: Option<RequestId>,
+
+    /// The result of a request.
+    pub result: Option<Value>,
+
+    /// The error object in case a request fails.
+    pub error: Option<ResponseError>,
+}
+
+impl Response {
+    /// Creates a successful response.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the result can not be serialized.
+    pub fn new_ok<T>(id: RequestId, result: T) -> Result<Self, serde_json::Error>
+    where
+        T: Serialize,
+    {
+        Ok(Self {
+            id: Some(id),
+            result: Some(serde_json::to_value(result)?),
+            error: None,
+        })
+    }
+
+    #[must_use]
+    pub const fn new_err(id: Option<RequestId>, error: ResponseError) -> Self {
+        Self {
+            id,
+            result: None,
+            error: Some(error),
+        }
+    }
+
+    /// Converts the response into the result of request `R`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the response error, or an [`ErrorCode::PARSE_ERROR`] if the result does
+    /// not match `R::Result`.
+    pub fn into_result<R>(self) -> Result<R::Result, ResponseError>
+    where
+        R: crate::request::Request,
+    {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        serde_json::from_value(self.result.unwrap_or(Value::Null))
+            .map_err(|err| ResponseError::new(ErrorCode::PARSE_ERROR, err.to_string()))
+    }
+}
+
+impl Serialize for Response {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct("Response", 3)?;
+        state.serialize_field("jsonrpc", JSONRPC_VERSION)?;
+        state.serialize_field("id", &self.id)?;
+        if let Some(error) = &self.error {
+            state.serialize_field("error", error)?;
+        } else {
+            let result = self.result.as_ref().unwrap_or(&Value::Null);
+            state.serialize_field("result", result)?;
+        }
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Response {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        match Message::deserialize(deserializer)? {
+            Message::Response(response) => Ok(response),
+            _ => Err(D::Error::custom("expected a response message")),
+        }
+    }
+}
+
+/// The error object of a failed request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ResponseError {
+    /// A number indicating the error type that occurred.
+    pub code: ErrorCode,
+
+    /// A string providing a short description of the error.
+    pub message: String,
+
+    /// A primitive or structured value that contains additional
+    /// information about the error. Can be omitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl ResponseError {
+    #[must_use]
+    pub const fn new(code: ErrorCode, message: String) -> Self {
+        Self {
+            code,
+            message,
+            data: None,
+        }
+    }
+}
+
+impl fmt::Display for ResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({:?})", self.message, self.code)
+    }
+}
+
+impl std::error::Error for ResponseError {}
+
+/// The union of the fields of all messages, used to tell the kinds of messages apart.
+#[derive(Deserialize)]
+struct RawMessage {
+    jsonrpc: String,
+    #[serde(default, deserialize_with = "deserialize_present")]
+    id: Option<Value>,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    params: Value,
+    #[serde(default, deserialize_with = "deserialize_present")]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<ResponseError>,
+}
+
+impl RawMessage {
+    fn into_message(self) -> Result<Message, String> {
+        if self.jsonrpc != JSONRPC_VERSION {
+            return Err(format!("unsupported jsonrpc version `{}`", self.jsonrpc));
+        }
+
+        let id = match self.id {
+            Some(Value::Null) => Some(None),
+            Some(id) => Some(Some(
+                RequestId::deserialize(id).map_err(|err| err.to_string())?,
+            )),
+            None => None,
+        };
+
+        match (self.method, id) {
+            (Some(_), _) if self.result.is_some() || self.error.is_some() => {
+                Err("a message can not have both a `method` and a `result` or `error`".into())
+            }
+            (Some(method), Some(Some(id))) => Ok(Message::Request(Request {
+                id,
+                method,
+                params: self.params,
+            })),
+            (Some(_), Some(None)) => Err("the id of a request must not be `null`".into()),
+            (Some(method), None) => Ok(Message::Notification(Notification {
+                method,
+                params: self.params,
+            })),
+            (None, Some(id)) => match (self.result, self.error) {
+                (Some(_), Some(_)) => {
+                    Err("a response can not have both a `result` and an `error`".into())
+                }
+                (None, None) => Err("a response must have either a `result` or an `error`".into()),
+                (result, error) => Ok(Message::Response(Response { id, result, error })),
+            },
+            (None, None) => Err("a message must have either a `method` or an `id`".into()),
+        }
+    }
+}
+
+/// Distinguishes a field set to `null` (`Some(Default)`) from a missing field (`None`).
+fn deserialize_present<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{notification::Exit, request::Shutdown, tests::test_serialization};
+
+    #[test]
+    fn request() {
+        test_serialization(
+            &Message::Request(Request {
+                id: RequestId::Number(1),
+                method: "textDocument/hover".into(),
+                params: serde_json::json!({ "position": { "line": 0, "character": 0 } }),
+            }),
+            r#"{"jsonrpc":"2.0","id":1,"method":"textDocument/hover","params":{"position":{"character":0,"line":0}}}"#,
+        );
+        test_serialization(
+            &Message::Request(Request::new::<Shutdown>("a".into(), ()).unwrap()),
+            r#"{"jsonrpc":"2.0","id":"a","method":"shutdown"}"#,
+        );
+    }
+
+    #[test]
+    fn notification() {
+        test_serialization(
+            &Message::Notification(Notification::new::<Exit>(()).unwrap()),
+            r#"{"jsonrpc":"2.0","method":"exit"}"#,
+        );
+    }
+
+    #[test]
+    fn response() {
+        test_serialization(
+            &Message::Response(Response::new_ok(RequestId::Number(1), ()).unwrap()),
+            r#"{"jsonrpc":"2.0","id":1,"result":null}"#,
+        );
+        test_serialization(
+            &Message::Response(Response::new_err(
+                None,
+                ResponseError::new(ErrorCode::PARSE_ERROR, "oops".into()),
+            )),
+            r#"{"jsonrpc":"2.0","id":null,"error":{"code":-32700,"message":"oops"}}"#,
+        );
+
+        let response: Response =
+            serde_json::from_str(r#"{"jsonrpc":"2.0","id":2,"result":null}"#).unwrap();
+        assert_eq!(response.result, Some(Value::Null));
+        response.into_result::<Shutdown>().unwrap();
+    }
+
+    #[test]
+    fn invalid_messages() {
+        for json in [
+            r#"{"id":1,"method":"exit"}"#,
+            r#"{"jsonrpc":"1.0","id":1,"method":"exit"}"#,
+            r#"{"jsonrpc":"2.0","id":null,"method":"exit"}"#,
+            r#"{"jsonrpc":"2.0","id":1,"method":"exit","result":1}"#,
+            r#"{"jsonrpc":"2.0","id":1}"#,
+            r#"{"jsonrpc":"2.0","id":1,"result":1,"error":{"code":1,"message":""}}"#,
+            r#"{"jsonrpc":"2.0","result":1}"#,
+        ] {
+            assert!(serde_json::from_str::<Message>(json).is_err(), "{json}");
+        }
+
+        assert!(serde_json::from_str::<Request>(r#"{"jsonrpc":"2.0","method":"exit"}"#).is_err());
+    }
+}
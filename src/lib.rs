@@ -4,10 +4,24 @@
 
 mod macros;
 
+pub mod base_protocol;
+pub mod capabilities;
+
+pub mod downgrade;
 pub mod error_codes;
+pub mod fuzzy;
+pub mod glob;
+pub mod jsonrpc;
 pub mod notification;
+pub mod registry;
 pub mod request;
+pub mod selector;
+pub mod semantic_tokens;
+pub mod snippet;
+pub mod strict;
+pub mod text;
 mod uri;
+pub mod vfs;
 
 pub use crate::uri::Uri;
 
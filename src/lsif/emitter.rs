@@ -0,0 +1,585 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    io::{self, Write},
+};
+
+use crate::{
+    Uri,
+    lsp::{Hover, Range},
+};
+
+use super::{
+    Document, Edge, EdgeData, EdgeDataMultiIn, Element, Entry, Event, EventKind, EventScope, Id,
+    Item, ItemKind, MetaData, Project, RangeTag, ResultSet, Vertex,
+};
+
+#[derive(Debug)]
+pub enum EmitError {
+    /// Writing the entry failed.
+    Io(io::Error),
+    /// An edge refers to a vertex that was not emitted before.
+    UnknownVertex(Id),
+    /// An `end` event does not match the last `begin` event that is not ended yet.
+    UnbalancedEvent { scope: EventScope, data: Id },
+    /// The dump was finished with `begin` events that are not ended, the scope and data of
+    /// the innermost last.
+    UnclosedEvents(Vec<(EventScope, Id)>),
+    /// The first vertex of the dump is not a `metaData` vertex, or the dump was finished
+    /// without one.
+    MissingMetaData,
+    /// A `metaData` vertex is emitted after the first entry.
+    MetaDataNotFirst,
+}
+
+impl fmt::Display for EmitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to write the entry: {error}"),
+            Self::UnknownVertex(id) => write!(f, "the vertex {id} was not emitted"),
+            Self::UnbalancedEvent { scope, data } => {
+                write!(
+                    f,
+                    "the {scope:?} event of {data} ends without a matching begin"
+                )
+            }
+            Self::UnclosedEvents(events) => {
+                write!(f, "{} events are not ended", events.len())
+            }
+            Self::MissingMetaData => f.write_str("the dump does not begin with a metaData vertex"),
+            Self::MetaDataNotFirst => f.write_str("the metaData vertex is not the first entry"),
+        }
+    }
+}
+
+impl std::error::Error for EmitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::UnknownVertex(_)
+            | Self::UnbalancedEvent { .. }
+            | Self::UnclosedEvents(_)
+            | Self::MissingMetaData
+            | Self::MetaDataNotFirst => None,
+        }
+    }
+}
+
+impl From<io::Error> for EmitError {
+    fn from(from: io::Error) -> Self {
+        Self::Io(from)
+    }
+}
+
+/// Writes an LSIF dump in the JSON lines format, allocating the ids of its entries.
+///
+/// The first vertex has to be the `metaData` vertex. Edges may only refer to vertices that were
+/// emitted before, and `$event`s have to be nested, which [`finish`](Self::finish) checks in
+/// the end.
+///
+/// # Examples
+///
+/// ```
+/// use ls_types::{
+///     lsif::{Emitter, Encoding, MetaData},
+///     lsp::{Hover, HoverContents, MarkedString, Position, Range},
+/// };
+///
+/// # fn main() -> Result<(), ls_types::lsif::EmitError> {
+/// let mut emitter = Emitter::new(vec![]);
+/// emitter.metadata(MetaData {
+///     version: "0.6.0".to_owned(),
+///     project_root: "file:///".parse().unwrap(),
+///     position_encoding: Encoding::Utf16,
+///     tool_info: None,
+/// })?;
+/// let project = emitter.project("rust")?;
+/// let document = emitter.document("file:///main.rs".parse().unwrap(), "rust")?;
+/// let range = emitter.range(Range::new(Position::new(0, 3), Position::new(0, 7)), None)?;
+/// let result_set = emitter.result_set()?;
+/// emitter.next(&range, &result_set)?;
+/// emitter.hover(
+///     &result_set,
+///     Hover {
+///         contents: HoverContents::Scalar(MarkedString::from_markdown("fn main()".to_owned())),
+///         range: None,
+///     },
+/// )?;
+/// let definition = emitter.definition(&result_set)?;
+/// emitter.item(&definition, &[range.clone()], &document, None)?;
+/// emitter.contains(&document, &[range])?;
+/// emitter.end_document(&document)?;
+/// emitter.contains(&project, &[document])?;
+/// emitter.end_project(&project)?;
+/// let dump = String::from_utf8(emitter.finish()?).unwrap();
+/// assert_eq!(dump.lines().count(), 17);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Emitter<W> {
+    writer: W,
+    next_id: i32,
+    vertices: HashSet<Id>,
+    events: Vec<(EventScope, Id)>,
+    /// Whether the version of the dump is before 0.6, which uses `document` instead of `shard`
+    /// on `item` edges.
+    pre_shard: bool,
+}
+
+impl<W: Write> Emitter<W> {
+    #[must_use]
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            next_id: 1,
+            vertices: HashSet::new(),
+            events: vec![],
+            pre_shard: false,
+        }
+    }
+
+    /// Emits a vertex and returns its id.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails, if the first vertex is not a `metaData` vertex or a
+    /// later one is, or if the vertex is an `$event` that ends a scope that was not begun last
+    /// or that refers to a vertex that was not emitted.
+    pub fn emit_vertex(&mut self, vertex: Vertex) -> Result<Id, EmitError> {
+        match (self.next_id, &vertex) {
+            (1, Vertex::MetaData(metadata)) => self.pre_shard = is_pre_shard(&metadata.version),
+            (1, _) => return Err(EmitError::MissingMetaData),
+            (_, Vertex::MetaData(_)) => return Err(EmitError::MetaDataNotFirst),
+            _ => {}
+        }
+        let event = match &vertex {
+            Vertex::Event(event) => {
+                self.check_vertex(&event.data)?;
+                if event.kind == EventKind::End
+                    && self.events.last() != Some(&(event.scope, event.data.clone()))
+                {
+                    return Err(EmitError::UnbalancedEvent {
+                        scope: event.scope,
+                        data: event.data.clone(),
+                    });
+                }
+                Some((event.kind, event.scope, event.data.clone()))
+            }
+            _ => None,
+        };
+        let id = self.write(Element::Vertex(vertex))?;
+        match event {
+            Some((EventKind::Begin, scope, data)) => self.events.push((scope, data)),
+            Some((EventKind::End, ..)) => {
+                self.events.pop();
+            }
+            None => {}
+        }
+        self.vertices.insert(id.clone());
+        Ok(id)
+    }
+
+    /// Emits an edge and returns its id.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails or if the edge refers to a vertex that was not
+    /// emitted.
+    pub fn emit_edge(&mut self, edge: Edge) -> Result<Id, EmitError> {
+        self.check_vertex(edge.out_v())?;
+        for in_v in edge.in_vs() {
+            self.check_vertex(in_v)?;
+        }
+        if let Edge::Item(item) = &edge {
+            for shard in item.document.iter().chain(&item.shard) {
+                self.check_vertex(shard)?;
+            }
+        }
+        self.write(Element::Edge(edge))
+    }
+
+    /// Checks that all `$event`s are ended, flushes the writer and returns it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if nothing was emitted, if an event is not ended or if flushing fails.
+    pub fn finish(mut self) -> Result<W, EmitError> {
+        if self.next_id == 1 {
+            return Err(EmitError::MissingMetaData);
+        }
+        if !self.events.is_empty() {
+            return Err(EmitError::UnclosedEvents(self.events));
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    /// Emits the `metaData` vertex.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub fn metadata(&mut self, metadata: MetaData) -> Result<Id, EmitError> {
+        self.emit_vertex(Vertex::MetaData(metadata))
+    }
+
+    /// Emits a `project` vertex and the `$event` that begins it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub fn project(&mut self, kind: &str) -> Result<Id, EmitError> {
+        let project = self.emit_vertex(Vertex::Project(Project {
+            resource: None,
+            content: None,
+            kind: kind.to_owned(),
+        }))?;
+        self.event(EventKind::Begin, EventScope::Project, &project)?;
+        Ok(project)
+    }
+
+    /// Emits the `$event` that ends a project.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails or if the project was not begun last.
+    pub fn end_project(&mut self, project: &Id) -> Result<Id, EmitError> {
+        self.event(EventKind::End, EventScope::Project, project)
+    }
+
+    /// Emits a `document` vertex and the `$event` that begins it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub fn document(&mut self, uri: Uri, language_id: &str) -> Result<Id, EmitError> {
+        let document = self.emit_vertex(Vertex::Document(Document {
+            uri,
+            language_id: language_id.to_owned(),
+        }))?;
+        self.event(EventKind::Begin, EventScope::Document, &document)?;
+        Ok(document)
+    }
+
+    /// Emits the `$event` that ends a document.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails or if the document was not begun last.
+    pub fn end_document(&mut self, document: &Id) -> Result<Id, EmitError> {
+        self.event(EventKind::End, EventScope::Document, document)
+    }
+
+    /// Emits a `range` vertex.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub fn range(&mut self, range: Range, tag: Option<RangeTag>) -> Result<Id, EmitError> {
+        self.emit_vertex(Vertex::Range { range, tag })
+    }
+
+    /// Emits a `resultSet` vertex.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails.
+    pub fn result_set(&mut self) -> Result<Id, EmitError> {
+        self.emit_vertex(Vertex::ResultSet(ResultSet { key: None }))
+    }
+
+    /// Emits a `next` edge.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails or if a vertex was not emitted.
+    pub fn next(&mut self, out_v: &Id, in_v: &Id) -> Result<Id, EmitError> {
+        self.emit_edge(Edge::Next(edge_data(out_v, in_v)))
+    }
+
+    /// Emits a `contains` edge.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails or if a vertex was not emitted.
+    pub fn contains(&mut self, out_v: &Id, in_vs: &[Id]) -> Result<Id, EmitError> {
+        self.emit_edge(Edge::Contains(EdgeDataMultiIn {
+            in_vs: in_vs.to_vec(),
+            out_v: out_v.clone(),
+        }))
+    }
+
+    /// Emits an `item` edge from a result to ranges or other results of the document or
+    /// project `shard`.
+    ///
+    /// The shard is written as `document` if the version of the dump is before 0.6.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails or if a vertex was not emitted.
+    pub fn item(
+        &mut self,
+        result: &Id,
+        in_vs: &[Id],
+        shard: &Id,
+        property: Option<ItemKind>,
+    ) -> Result<Id, EmitError> {
+        let (document, shard) = if self.pre_shard {
+            (Some(shard.clone()), None)
+        } else {
+            (None, Some(shard.clone()))
+        };
+        self.emit_edge(Edge::Item(Item {
+            document,
+            shard,
+            property,
+            edge_data: EdgeDataMultiIn {
+                in_vs: in_vs.to_vec(),
+                out_v: result.clone(),
+            },
+        }))
+    }
+
+    /// Emits a `hoverResult` vertex and the `textDocument/hover` edge to it from a range or
+    /// result set, and returns the id of the result.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails or if `out_v` was not emitted.
+    pub fn hover(&mut self, out_v: &Id, hover: Hover) -> Result<Id, EmitError> {
+        self.result(out_v, Vertex::HoverResult { result: hover }, Edge::Hover)
+    }
+
+    /// Emits a `definitionResult` vertex and the `textDocument/definition` edge to it, and
+    /// returns the id of the result to add [`item`](Self::item)s to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails or if `out_v` was not emitted.
+    pub fn definition(&mut self, out_v: &Id) -> Result<Id, EmitError> {
+        self.result(out_v, Vertex::DefinitionResult, Edge::Definition)
+    }
+
+    /// Emits a `declarationResult` vertex and the `textDocument/declaration` edge to it, and
+    /// returns the id of the result to add [`item`](Self::item)s to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails or if `out_v` was not emitted.
+    pub fn declaration(&mut self, out_v: &Id) -> Result<Id, EmitError> {
+        self.result(out_v, Vertex::DeclarationResult, Edge::Declaration)
+    }
+
+    /// Emits a `referenceResult` vertex and the `textDocument/references` edge to it, and
+    /// returns the id of the result to add [`item`](Self::item)s to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if writing fails or if `out_v` was not emitted.
+    pub fn references(&mut self, out_v: &Id) -> Result<Id, EmitError> {
+        self.result(out_v, Vertex::ReferenceResult, Edge::References)
+    }
+
+    fn result(
+        &mut self,
+        out_v: &Id,
+        result: Vertex,
+        edge: fn(EdgeData) -> Edge,
+    ) -> Result<Id, EmitError> {
+        self.check_vertex(out_v)?;
+        let result = self.emit_vertex(result)?;
+        self.emit_edge(edge(edge_data(out_v, &result)))?;
+        Ok(result)
+    }
+
+    fn event(&mut self, kind: EventKind, scope: EventScope, data: &Id) -> Result<Id, EmitError> {
+        self.emit_vertex(Vertex::Event(Event {
+            kind,
+            scope,
+            data: data.clone(),
+        }))
+    }
+
+    fn check_vertex(&self, id: &Id) -> Result<(), EmitError> {
+        if self.vertices.contains(id) {
+            Ok(())
+        } else {
+            Err(EmitError::UnknownVertex(id.clone()))
+        }
+    }
+
+    fn write(&mut self, data: Element) -> Result<Id, EmitError> {
+        let id = Id::Number(self.next_id);
+        let mut line = serde_json::to_vec(&Entry {
+            id: id.clone(),
+            data,
+        })
+        .map_err(io::Error::from)?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+        self.next_id += 1;
+        Ok(id)
+    }
+}
+
+/// Whether `version` is a version of LSIF before 0.6.
+fn is_pre_shard(version: &str) -> bool {
+    let mut parts = version.split('.').map(str::parse::<u32>);
+    matches!(
+        (parts.next(), parts.next()),
+        (Some(Ok(0)), Some(Ok(minor))) if minor < 6
+    )
+}
+
+fn edge_data(out_v: &Id, in_v: &Id) -> EdgeData {
+    EdgeData {
+        in_v: in_v.clone(),
+        out_v: out_v.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::slice;
+
+    use super::*;
+    use crate::{
+        lsif::{Encoding, LsifGraph, validate_reader},
+        lsp::{HoverContents, MarkedString, Position},
+    };
+
+    fn range(line: u32) -> Range {
+        Range::new(Position::new(line, 0), Position::new(line, 1))
+    }
+
+    fn metadata() -> MetaData {
+        MetaData {
+            version: "0.6.0".to_owned(),
+            project_root: "file:///".parse().unwrap(),
+            position_encoding: Encoding::Utf16,
+            tool_info: None,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut emitter = Emitter::new(vec![]);
+        emitter.metadata(metadata()).unwrap();
+        let project = emitter.project("rust").unwrap();
+        let document = emitter
+            .document("file:///lib.rs".parse().unwrap(), "rust")
+            .unwrap();
+        let definition_range = emitter.range(range(0), None).unwrap();
+        let reference_range = emitter.range(range(1), None).unwrap();
+        let result_set = emitter.result_set().unwrap();
+        emitter.next(&definition_range, &result_set).unwrap();
+        emitter.next(&reference_range, &result_set).unwrap();
+        emitter
+            .hover(
+                &result_set,
+                Hover {
+                    contents: HoverContents::Scalar(MarkedString::String("x".to_owned())),
+                    range: None,
+                },
+            )
+            .unwrap();
+        let references = emitter.references(&result_set).unwrap();
+        emitter
+            .item(
+                &references,
+                slice::from_ref(&definition_range),
+                &document,
+                Some(ItemKind::Definitions),
+            )
+            .unwrap();
+        emitter
+            .item(
+                &references,
+                slice::from_ref(&reference_range),
+                &document,
+                Some(ItemKind::References),
+            )
+            .unwrap();
+        emitter
+            .contains(&document, &[definition_range, reference_range.clone()])
+            .unwrap();
+        emitter.end_document(&document).unwrap();
+        emitter.contains(&project, &[document]).unwrap();
+        emitter.end_project(&project).unwrap();
+        let dump = emitter.finish().unwrap();
+        assert_eq!(validate_reader(dump.as_slice()).unwrap(), []);
+
+        let graph = LsifGraph::from_reader(dump.as_slice()).unwrap();
+        let reference_range = graph.find(&reference_range).unwrap();
+        assert!(graph.hover(reference_range).is_some());
+        let references = graph.reference_result(reference_range).unwrap();
+        assert_eq!(graph.items(references).count(), 2);
+    }
+
+    #[test]
+    fn item_shard() {
+        for (version, field) in [("0.6.0", "\"shard\":"), ("0.5.0", "\"document\":")] {
+            let mut emitter = Emitter::new(vec![]);
+            emitter
+                .metadata(MetaData {
+                    version: version.to_owned(),
+                    ..metadata()
+                })
+                .unwrap();
+            let document = emitter
+                .document("file:///lib.rs".parse().unwrap(), "rust")
+                .unwrap();
+            let range = emitter.range(range(0), None).unwrap();
+            let result_set = emitter.result_set().unwrap();
+            let references = emitter.references(&result_set).unwrap();
+            emitter
+                .item(&references, &[range], &document, None)
+                .unwrap();
+            emitter.end_document(&document).unwrap();
+            let dump = String::from_utf8(emitter.finish().unwrap()).unwrap();
+            let item = dump.lines().find(|line| line.contains("\"item\"")).unwrap();
+            assert!(item.contains(field), "{item}");
+        }
+        assert!(is_pre_shard("0.4.3"));
+        assert!(!is_pre_shard("0.6.0-next.7"));
+        assert!(!is_pre_shard("1.0"));
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            Emitter::new(vec![]).finish(),
+            Err(EmitError::MissingMetaData)
+        ));
+        let mut emitter = Emitter::new(vec![]);
+        assert!(matches!(
+            emitter.project("rust"),
+            Err(EmitError::MissingMetaData)
+        ));
+        emitter.metadata(metadata()).unwrap();
+        assert!(matches!(
+            emitter.metadata(metadata()),
+            Err(EmitError::MetaDataNotFirst)
+        ));
+        let project = emitter.project("rust").unwrap();
+        let document = emitter
+            .document("file:///lib.rs".parse().unwrap(), "rust")
+            .unwrap();
+        assert!(matches!(
+            emitter.next(&document, &Id::Number(100)),
+            Err(EmitError::UnknownVertex(Id::Number(100)))
+        ));
+        assert!(matches!(
+            emitter.end_project(&project),
+            Err(EmitError::UnbalancedEvent {
+                scope: EventScope::Project,
+                ..
+            })
+        ));
+        assert!(matches!(
+            emitter.finish(),
+            Err(EmitError::UnclosedEvents(events)) if events.len() == 2
+        ));
+    }
+}
@@ -0,0 +1,432 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    io::{self, BufRead},
+};
+
+use crate::{
+    Uri,
+    lsp::{Hover, Range},
+};
+
+use super::{Document, Edge, Element, Entry, Id, Item, MetaData, RangeTag, Vertex};
+
+/// The index of a vertex in an [`LsifGraph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct VertexIndex(usize);
+
+/// The index of an edge in an [`LsifGraph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct EdgeIndex(usize);
+
+#[derive(Debug)]
+pub enum LsifGraphError {
+    /// Reading the dump failed.
+    Io(io::Error),
+    /// A line of the dump is not an [`Entry`].
+    Parse {
+        /// The one-based number of the line.
+        line: usize,
+        error: serde_json::Error,
+    },
+    /// Two entries have the same id.
+    DuplicateId(Id),
+    /// An edge refers to a vertex that is not in the dump.
+    DanglingEdge { edge: Id, vertex: Id },
+}
+
+impl fmt::Display for LsifGraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "failed to read the dump: {error}"),
+            Self::Parse { line, error } => write!(f, "invalid entry on line {line}: {error}"),
+            Self::DuplicateId(id) => write!(f, "duplicate id {id}"),
+            Self::DanglingEdge { edge, vertex } => {
+                write!(f, "edge {edge} refers to the unknown vertex {vertex}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LsifGraphError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Parse { error, .. } => Some(error),
+            Self::DuplicateId(_) | Self::DanglingEdge { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for LsifGraphError {
+    fn from(from: io::Error) -> Self {
+        Self::Io(from)
+    }
+}
+
+/// An LSIF dump loaded into memory, with the ids of its edges resolved.
+///
+/// # Examples
+///
+/// ```
+/// use ls_types::lsif::LsifGraph;
+///
+/// let dump = r#"{"id":1,"type":"vertex","label":"document","uri":"file:///a.ts","languageId":"typescript"}
+/// {"id":2,"type":"vertex","label":"range","start":{"line":0,"character":6},"end":{"line":0,"character":7}}
+/// {"id":3,"type":"edge","label":"contains","outV":1,"inVs":[2]}
+/// {"id":4,"type":"vertex","label":"resultSet"}
+/// {"id":5,"type":"edge","label":"next","outV":2,"inV":4}
+/// {"id":6,"type":"vertex","label":"hoverResult","result":{"contents":"const a"}}
+/// {"id":7,"type":"edge","label":"textDocument/hover","outV":4,"inV":6}
+/// "#;
+/// let graph = LsifGraph::from_reader(dump.as_bytes()).unwrap();
+/// let document = graph.document(&"file:///a.ts".parse().unwrap()).unwrap();
+/// let range = graph.ranges(document).next().unwrap();
+/// assert!(graph.hover(range).is_some());
+/// ```
+#[derive(Debug, Default)]
+pub struct LsifGraph {
+    vertices: Vec<(Id, Vertex)>,
+    edges: Vec<(Id, Edge)>,
+    ids: HashMap<Id, VertexIndex>,
+    /// The outgoing edges of each vertex with the vertex they end at, one per end of edges with
+    /// several.
+    outgoing: Vec<Vec<(EdgeIndex, VertexIndex)>>,
+    /// The incoming edges of each vertex with the vertex they start at.
+    incoming: Vec<Vec<(EdgeIndex, VertexIndex)>>,
+    documents: HashMap<Uri, VertexIndex>,
+}
+
+impl LsifGraph {
+    /// Loads a dump from its entries.
+    ///
+    /// The entries may come in any order, so edges may precede the vertices they refer to.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if two entries have the same id or if an edge refers to a vertex that
+    /// is not in the dump.
+    pub fn from_entries<I>(entries: I) -> Result<Self, LsifGraphError>
+    where
+        I: IntoIterator<Item = Entry>,
+    {
+        let mut graph = Self::default();
+        let mut edge_ids = HashSet::new();
+        for Entry { id, data } in entries {
+            if graph.ids.contains_key(&id) || edge_ids.contains(&id) {
+                return Err(LsifGraphError::DuplicateId(id));
+            }
+            match data {
+                Element::Vertex(vertex) => {
+                    let index = VertexIndex(graph.vertices.len());
+                    if let Vertex::Document(document) = &vertex {
+                        graph.documents.insert(document.uri.clone(), index);
+                    }
+                    graph.ids.insert(id.clone(), index);
+                    graph.vertices.push((id, vertex));
+                }
+                Element::Edge(edge) => {
+                    edge_ids.insert(id.clone());
+                    graph.edges.push((id, edge));
+                }
+            }
+        }
+
+        graph.outgoing = vec![vec![]; graph.vertices.len()];
+        graph.incoming = vec![vec![]; graph.vertices.len()];
+        for (index, (id, edge)) in graph.edges.iter().enumerate() {
+            let resolve = |vertex: &Id| {
+                graph
+                    .ids
+                    .get(vertex)
+                    .copied()
+                    .ok_or_else(|| LsifGraphError::DanglingEdge {
+                        edge: id.clone(),
+                        vertex: vertex.clone(),
+                    })
+            };
+            let out_v = resolve(edge.out_v())?;
+            for in_v in edge.in_vs() {
+                let in_v = resolve(in_v)?;
+                graph.outgoing[out_v.0].push((EdgeIndex(index), in_v));
+                graph.incoming[in_v.0].push((EdgeIndex(index), out_v));
+            }
+        }
+        Ok(graph)
+    }
+
+    /// Loads a dump in the JSON lines format, skipping empty lines.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails, if a line is not an [`Entry`] or if
+    /// [`from_entries`](Self::from_entries) fails.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self, LsifGraphError> {
+        let mut entries = vec![];
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str(&line).map_err(|error| LsifGraphError::Parse {
+                line: index + 1,
+                error,
+            })?;
+            entries.push(entry);
+        }
+        Self::from_entries(entries)
+    }
+
+    /// The vertices in the order of the dump.
+    pub fn vertices(&self) -> impl Iterator<Item = VertexIndex> {
+        (0..self.vertices.len()).map(VertexIndex)
+    }
+
+    #[must_use]
+    pub fn vertex(&self, vertex: VertexIndex) -> &Vertex {
+        &self.vertices[vertex.0].1
+    }
+
+    #[must_use]
+    pub fn vertex_id(&self, vertex: VertexIndex) -> &Id {
+        &self.vertices[vertex.0].0
+    }
+
+    /// The vertex with the id `id`.
+    #[must_use]
+    pub fn find(&self, id: &Id) -> Option<VertexIndex> {
+        self.ids.get(id).copied()
+    }
+
+    /// The edges starting at `vertex`, with the vertex they end at.
+    pub fn outgoing(&self, vertex: VertexIndex) -> impl Iterator<Item = (&Edge, VertexIndex)> {
+        self.outgoing[vertex.0]
+            .iter()
+            .map(|&(edge, in_v)| (&self.edges[edge.0].1, in_v))
+    }
+
+    /// The edges ending at `vertex`, with the vertex they start at.
+    pub fn incoming(&self, vertex: VertexIndex) -> impl Iterator<Item = (&Edge, VertexIndex)> {
+        self.incoming[vertex.0]
+            .iter()
+            .map(|&(edge, out_v)| (&self.edges[edge.0].1, out_v))
+    }
+
+    /// The `metaData` vertex.
+    #[must_use]
+    pub fn metadata(&self) -> Option<&MetaData> {
+        self.vertices.iter().find_map(|(_, vertex)| match vertex {
+            Vertex::MetaData(metadata) => Some(metadata),
+            _ => None,
+        })
+    }
+
+    /// The `document` vertices.
+    pub fn documents(&self) -> impl Iterator<Item = (VertexIndex, &Document)> {
+        self.vertices()
+            .filter_map(|vertex| match self.vertex(vertex) {
+                Vertex::Document(document) => Some((vertex, document)),
+                _ => None,
+            })
+    }
+
+    /// The `document` vertex of `uri`.
+    #[must_use]
+    pub fn document(&self, uri: &Uri) -> Option<VertexIndex> {
+        self.documents.get(uri).copied()
+    }
+
+    /// The `range` vertices a document `contains`.
+    pub fn ranges(&self, document: VertexIndex) -> impl Iterator<Item = VertexIndex> {
+        self.outgoing(document)
+            .filter(move |&(edge, in_v)| {
+                matches!(edge, Edge::Contains(_)) && self.range(in_v).is_some()
+            })
+            .map(|(_, in_v)| in_v)
+    }
+
+    /// The range and tag of a `range` vertex.
+    #[must_use]
+    pub fn range(&self, vertex: VertexIndex) -> Option<(&Range, Option<&RangeTag>)> {
+        match &self.vertices[vertex.0].1 {
+            Vertex::Range { range, tag } => Some((range, tag.as_ref())),
+            _ => None,
+        }
+    }
+
+    /// The `document` vertex that `contains` a range.
+    #[must_use]
+    pub fn containing_document(&self, range: VertexIndex) -> Option<VertexIndex> {
+        self.incoming(range)
+            .find(|&(edge, out_v)| {
+                matches!(edge, Edge::Contains(_))
+                    && matches!(self.vertex(out_v), Vertex::Document(_))
+            })
+            .map(|(_, out_v)| out_v)
+    }
+
+    /// The vertex a vertex points to with a `next` edge.
+    #[must_use]
+    pub fn next(&self, vertex: VertexIndex) -> Option<VertexIndex> {
+        self.outgoing(vertex)
+            .find(|(edge, _)| matches!(edge, Edge::Next(_)))
+            .map(|(_, in_v)| in_v)
+    }
+
+    /// `vertex` and the result sets that follow it through `next` edges, ending at a cycle.
+    pub fn next_chain(&self, vertex: VertexIndex) -> impl Iterator<Item = VertexIndex> {
+        let mut visited = HashSet::new();
+        std::iter::successors(Some(vertex), |&vertex| self.next(vertex))
+            .take_while(move |&vertex| visited.insert(vertex))
+    }
+
+    /// The vertex the first edge labelled `label` points to along the
+    /// [`next_chain`](Self::next_chain) of `vertex`, like the `hoverResult` of a range.
+    #[must_use]
+    pub fn result(&self, vertex: VertexIndex, label: &str) -> Option<VertexIndex> {
+        self.next_chain(vertex).find_map(|vertex| {
+            self.outgoing(vertex)
+                .find(|(edge, _)| edge.label() == label)
+                .map(|(_, in_v)| in_v)
+        })
+    }
+
+    /// The hover of a range or result set.
+    #[must_use]
+    pub fn hover(&self, vertex: VertexIndex) -> Option<&Hover> {
+        match self.vertex(self.result(vertex, "textDocument/hover")?) {
+            Vertex::HoverResult { result } => Some(result),
+            _ => None,
+        }
+    }
+
+    /// The `definitionResult` vertex of a range or result set.
+    #[must_use]
+    pub fn definition_result(&self, vertex: VertexIndex) -> Option<VertexIndex> {
+        self.result(vertex, "textDocument/definition")
+    }
+
+    /// The `declarationResult` vertex of a range or result set.
+    #[must_use]
+    pub fn declaration_result(&self, vertex: VertexIndex) -> Option<VertexIndex> {
+        self.result(vertex, "textDocument/declaration")
+    }
+
+    /// The `referenceResult` vertex of a range or result set.
+    #[must_use]
+    pub fn reference_result(&self, vertex: VertexIndex) -> Option<VertexIndex> {
+        self.result(vertex, "textDocument/references")
+    }
+
+    /// The `implementationResult` vertex of a range or result set.
+    #[must_use]
+    pub fn implementation_result(&self, vertex: VertexIndex) -> Option<VertexIndex> {
+        self.result(vertex, "textDocument/implementation")
+    }
+
+    /// The `typeDefinitionResult` vertex of a range or result set.
+    #[must_use]
+    pub fn type_definition_result(&self, vertex: VertexIndex) -> Option<VertexIndex> {
+        self.result(vertex, "textDocument/typeDefinition")
+    }
+
+    /// The `item` edges of a result, with each vertex they end at.
+    pub fn items(&self, result: VertexIndex) -> impl Iterator<Item = (&Item, VertexIndex)> {
+        self.outgoing(result).filter_map(|(edge, in_v)| match edge {
+            Edge::Item(item) => Some((item, in_v)),
+            _ => None,
+        })
+    }
+
+    /// The `moniker` vertices of a vertex, the one of its `moniker` edge first and then the
+    /// ones linked to it by `nextMoniker` edges.
+    pub fn monikers(&self, vertex: VertexIndex) -> impl Iterator<Item = VertexIndex> {
+        let first = self
+            .outgoing(vertex)
+            .find(|(edge, _)| matches!(edge, Edge::Moniker(_)))
+            .map(|(_, in_v)| in_v);
+        let mut visited = HashSet::new();
+        std::iter::successors(first, |&moniker| {
+            self.outgoing(moniker)
+                .find(|(edge, _)| matches!(edge, Edge::NextMoniker(_)))
+                .map(|(_, in_v)| in_v)
+        })
+        .take_while(move |&moniker| visited.insert(moniker))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::{NumberOrString, Position};
+
+    fn load(dump: &str) -> Result<LsifGraph, LsifGraphError> {
+        LsifGraph::from_reader(dump.as_bytes())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn navigate() {
+        let graph = load(include_str!("../../tests/tsc-unix.lsif")).unwrap();
+        assert_eq!(graph.metadata().unwrap().version, "0.4.3");
+        assert_eq!(graph.documents().count(), 3);
+
+        let document = graph
+            .document(&"file:///media/hamid/nv1/garbage/a.ts".parse().unwrap())
+            .unwrap();
+        let a = graph
+            .ranges(document)
+            .find(|&range| graph.range(range).unwrap().0.start == Position::new(0, 13))
+            .unwrap();
+        assert_eq!(graph.containing_document(a), Some(document));
+        assert_eq!(graph.next_chain(a).count(), 2);
+        assert_eq!(
+            serde_json::to_value(&graph.hover(a).unwrap().contents).unwrap()[0]["value"],
+            "const a: (x: number, y: string) => string"
+        );
+
+        let definitions = graph.definition_result(a).unwrap();
+        assert_eq!(
+            graph
+                .items(definitions)
+                .map(|(_, range)| range)
+                .collect::<Vec<_>>(),
+            [a]
+        );
+        let references = graph.reference_result(a).unwrap();
+        assert!(graph.items(references).any(|(_, range)| range == a));
+
+        let monikers: Vec<_> = graph
+            .monikers(graph.next(a).unwrap())
+            .map(|moniker| graph.vertex_id(moniker).clone())
+            .collect();
+        assert_eq!(
+            monikers,
+            [NumberOrString::Number(16), NumberOrString::Number(18)]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let document =
+            r#"{"id":1,"type":"vertex","label":"document","uri":"file:///a","languageId":"c"}"#;
+        assert!(matches!(
+            load(&format!("{document}\n\nnot json")),
+            Err(LsifGraphError::Parse { line: 3, .. })
+        ));
+        assert!(matches!(
+            load(&format!("{document}\n{document}")),
+            Err(LsifGraphError::DuplicateId(NumberOrString::Number(1)))
+        ));
+        assert!(matches!(
+            load(&format!(
+                r#"{document}
+{{"id":2,"type":"edge","label":"contains","outV":1,"inVs":[3]}}"#
+            )),
+            Err(LsifGraphError::DanglingEdge {
+                edge: NumberOrString::Number(2),
+                vertex: NumberOrString::Number(3),
+            })
+        ));
+    }
+}
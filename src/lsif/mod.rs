@@ -4,16 +4,26 @@
 //!
 //! Based on <https://microsoft.github.io/language-server-protocol/specifications/lsif/0.6.0/specification/>
 
+mod emitter;
+mod graph;
+mod query;
+mod validate;
+
 use serde::{Deserialize, Serialize};
 
 use crate::{
     Uri,
     lsp::{
         Diagnostic, DocumentLink, DocumentSymbol, FoldingRange, Hover, Location, Moniker,
-        NumberOrString, Range, SymbolKind,
+        NumberOrString, Range, SymbolKind, SymbolTag,
     },
 };
 
+pub use emitter::*;
+pub use graph::*;
+pub use query::*;
+pub use validate::*;
+
 pub type Id = NumberOrString;
 
 #[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
@@ -76,49 +86,111 @@ pub enum DocumentSymbolOrRangeBasedVec {
 #[serde(rename_all = "camelCase")]
 pub struct DefinitionTag {
     /// The text covered by the range     
-    text: String,
+    pub text: String,
     /// The symbol kind.
-    kind: SymbolKind,
+    pub kind: SymbolKind,
     /// Indicates if this symbol is deprecated.
     #[serde(default)]
     #[serde(skip_serializing_if = "std::ops::Not::not")]
-    deprecated: bool,
+    pub deprecated: bool,
     /// The full range of the definition not including leading/trailing whitespace but everything else, e.g comments and code.
     /// The range must be included in fullRange.
-    full_range: Range,
+    pub full_range: Range,
     /// Optional detail information for the definition.
     #[serde(skip_serializing_if = "Option::is_none")]
-    detail: Option<String>,
+    pub detail: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DeclarationTag {
     /// The text covered by the range     
-    text: String,
+    pub text: String,
     /// The symbol kind.
-    kind: SymbolKind,
+    pub kind: SymbolKind,
     /// Indicates if this symbol is deprecated.
     #[serde(default)]
-    deprecated: bool,
+    pub deprecated: bool,
     /// The full range of the definition not including leading/trailing whitespace but everything else, e.g comments and code.
     /// The range must be included in fullRange.
-    full_range: Range,
+    pub full_range: Range,
     /// Optional detail information for the definition.
     #[serde(skip_serializing_if = "Option::is_none")]
-    detail: Option<String>,
+    pub detail: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ReferenceTag {
-    text: String,
+    pub text: String,
 }
 
 #[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UnknownTag {
-    text: String,
+    pub text: String,
+}
+
+impl DefinitionTag {
+    #[must_use]
+    pub const fn new(text: String, kind: SymbolKind, full_range: Range) -> Self {
+        Self {
+            text,
+            kind,
+            deprecated: false,
+            full_range,
+            detail: None,
+        }
+    }
+}
+
+/// The tag of the range of the `selection_range` of a symbol, for range based document symbols.
+impl From<&DocumentSymbol> for DefinitionTag {
+    fn from(symbol: &DocumentSymbol) -> Self {
+        #[expect(
+            deprecated,
+            reason = "the deprecated property is still part of the protocol"
+        )]
+        let deprecated = symbol.deprecated == Some(true)
+            || symbol
+                .tags
+                .as_ref()
+                .is_some_and(|tags| tags.contains(&SymbolTag::DEPRECATED));
+        Self {
+            text: symbol.name.clone(),
+            kind: symbol.kind,
+            deprecated,
+            full_range: symbol.range,
+            detail: symbol.detail.clone(),
+        }
+    }
+}
+
+impl DeclarationTag {
+    #[must_use]
+    pub const fn new(text: String, kind: SymbolKind, full_range: Range) -> Self {
+        Self {
+            text,
+            kind,
+            deprecated: false,
+            full_range,
+            detail: None,
+        }
+    }
+}
+
+impl ReferenceTag {
+    #[must_use]
+    pub const fn new(text: String) -> Self {
+        Self { text }
+    }
+}
+
+impl UnknownTag {
+    #[must_use]
+    pub const fn new(text: String) -> Self {
+        Self { text }
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
@@ -136,6 +208,9 @@ pub enum RangeTag {
 #[serde(tag = "label")]
 pub enum Vertex {
     MetaData(MetaData),
+    Source(Source),
+    Capabilities(Capabilities),
+    Group(Group),
     /// <https://github.com/Microsoft/language-server-protocol/blob/master/indexFormat/specification.md#the-project-vertex>
     Project(Project),
     Document(Document),
@@ -156,9 +231,17 @@ pub enum Vertex {
 
     DefinitionResult,
     DeclarationResult,
-    TypeDefinitionResult,
+    TypeDefinitionResult {
+        /// The result of older dumps, newer ones use `item` edges.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        result: Option<Vec<LocationOrRangeId>>,
+    },
     ReferenceResult,
-    ImplementationResult,
+    ImplementationResult {
+        /// The result of older dumps, newer ones use `item` edges.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        result: Option<Vec<LocationOrRangeId>>,
+    },
     FoldingRangeResult {
         result: Vec<FoldingRange>,
     },
@@ -176,14 +259,44 @@ pub enum Vertex {
     },
 }
 
-#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
+impl Vertex {
+    /// The `label` of the vertex in a dump.
+    #[must_use]
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Self::MetaData(_) => "metaData",
+            Self::Source(_) => "source",
+            Self::Capabilities(_) => "capabilities",
+            Self::Group(_) => "group",
+            Self::Project(_) => "project",
+            Self::Document(_) => "document",
+            Self::Range { .. } => "range",
+            Self::ResultSet(_) => "resultSet",
+            Self::Moniker(_) => "moniker",
+            Self::PackageInformation(_) => "packageInformation",
+            Self::Event(_) => "$event",
+            Self::DefinitionResult => "definitionResult",
+            Self::DeclarationResult => "declarationResult",
+            Self::TypeDefinitionResult { .. } => "typeDefinitionResult",
+            Self::ReferenceResult => "referenceResult",
+            Self::ImplementationResult { .. } => "implementationResult",
+            Self::FoldingRangeResult { .. } => "foldingRangeResult",
+            Self::HoverResult { .. } => "hoverResult",
+            Self::DocumentSymbolResult { .. } => "documentSymbolResult",
+            Self::DocumentLinkResult { .. } => "documentLinkResult",
+            Self::DiagnosticResult { .. } => "diagnosticResult",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum EventKind {
     Begin,
     End,
 }
 
-#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum EventScope {
     Document,
@@ -206,6 +319,8 @@ pub enum Edge {
     NextMoniker(EdgeData),
     Next(EdgeData),
     PackageInformation(EdgeData),
+    Attach(EdgeData),
+    BelongsTo(EdgeData),
     Item(Item),
 
     // Methods
@@ -231,6 +346,83 @@ pub enum Edge {
     Diagnostic(EdgeData),
 }
 
+impl Edge {
+    /// The `label` of the edge in a dump.
+    #[must_use]
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Self::Contains(_) => "contains",
+            Self::Moniker(_) => "moniker",
+            Self::NextMoniker(_) => "nextMoniker",
+            Self::Next(_) => "next",
+            Self::PackageInformation(_) => "packageInformation",
+            Self::Attach(_) => "attach",
+            Self::BelongsTo(_) => "belongsTo",
+            Self::Item(_) => "item",
+            Self::Definition(_) => "textDocument/definition",
+            Self::Declaration(_) => "textDocument/declaration",
+            Self::Hover(_) => "textDocument/hover",
+            Self::References(_) => "textDocument/references",
+            Self::Implementation(_) => "textDocument/implementation",
+            Self::TypeDefinition(_) => "textDocument/typeDefinition",
+            Self::FoldingRange(_) => "textDocument/foldingRange",
+            Self::DocumentLink(_) => "textDocument/documentLink",
+            Self::DocumentSymbol(_) => "textDocument/documentSymbol",
+            Self::Diagnostic(_) => "textDocument/diagnostic",
+        }
+    }
+
+    /// The id of the vertex the edge starts at.
+    #[must_use]
+    pub const fn out_v(&self) -> &Id {
+        match self {
+            Self::Contains(data) => &data.out_v,
+            Self::Item(item) => &item.edge_data.out_v,
+            Self::Moniker(data)
+            | Self::NextMoniker(data)
+            | Self::Next(data)
+            | Self::PackageInformation(data)
+            | Self::Attach(data)
+            | Self::BelongsTo(data)
+            | Self::Definition(data)
+            | Self::Declaration(data)
+            | Self::Hover(data)
+            | Self::References(data)
+            | Self::Implementation(data)
+            | Self::TypeDefinition(data)
+            | Self::FoldingRange(data)
+            | Self::DocumentLink(data)
+            | Self::DocumentSymbol(data)
+            | Self::Diagnostic(data) => &data.out_v,
+        }
+    }
+
+    /// The ids of the vertices the edge ends at.
+    #[must_use]
+    pub fn in_vs(&self) -> &[Id] {
+        match self {
+            Self::Contains(data) => &data.in_vs,
+            Self::Item(item) => &item.edge_data.in_vs,
+            Self::Moniker(data)
+            | Self::NextMoniker(data)
+            | Self::Next(data)
+            | Self::PackageInformation(data)
+            | Self::Attach(data)
+            | Self::BelongsTo(data)
+            | Self::Definition(data)
+            | Self::Declaration(data)
+            | Self::Hover(data)
+            | Self::References(data)
+            | Self::Implementation(data)
+            | Self::TypeDefinition(data)
+            | Self::FoldingRange(data)
+            | Self::DocumentLink(data)
+            | Self::DocumentSymbol(data)
+            | Self::Diagnostic(data) => std::slice::from_ref(&data.in_v),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct EdgeData {
@@ -265,13 +457,26 @@ pub enum ItemKind {
 #[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Item {
-    pub document: Id,
+    /// The document of the `inVs` in dumps before 0.6.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document: Option<Id>,
+    /// The document or project of the `inVs`, replacing `document` since 0.6.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shard: Option<Id>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub property: Option<ItemKind>,
     #[serde(flatten)]
     pub edge_data: EdgeDataMultiIn,
 }
 
+impl Item {
+    /// The `shard` of the item, or its `document` in dumps before 0.6.
+    #[must_use]
+    pub fn shard_or_document(&self) -> Option<&Id> {
+        self.shard.as_ref().or(self.document.as_ref())
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Document {
@@ -298,6 +503,62 @@ pub struct Project {
     pub kind: String,
 }
 
+/// <https://microsoft.github.io/language-server-protocol/specifications/lsif/0.6.0/specification/#source>
+#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Source {
+    /// The workspace root used when indexing.
+    pub workspace_root: Uri,
+
+    /// Optional information about the repository containing the indexed source.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repository: Option<Repository>,
+}
+
+/// The requests a dump can answer.
+///
+/// <https://microsoft.github.io/language-server-protocol/specifications/lsif/0.6.0/specification/#capabilities>
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[expect(
+    clippy::struct_excessive_bools,
+    reason = "the capabilities vertex is a set of flags"
+)]
+pub struct Capabilities {
+    pub hover_provider: bool,
+    pub declaration_provider: bool,
+    pub definition_provider: bool,
+    pub type_definition_provider: bool,
+    pub references_provider: bool,
+    pub document_symbol_provider: bool,
+    pub folding_range_provider: bool,
+    pub diagnostic_provider: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictResolution {
+    /// The information of the new dump replaces the one in the database.
+    TakeDump,
+    /// The information in the database is kept.
+    #[serde(rename = "takeDB")]
+    TakeDb,
+}
+
+/// A group of projects that a project `belongsTo`.
+#[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Group {
+    pub uri: Uri,
+    pub conflict_resolution: ConflictResolution,
+    pub name: String,
+    pub root_uri: Uri,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repository: Option<Repository>,
+}
+
 #[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MetaData {
@@ -341,3 +602,76 @@ pub struct PackageInformation {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::Position;
+
+    fn test_round_trip(json: &str) -> Entry {
+        let entry: Entry = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            serde_json::to_value(&entry).unwrap(),
+            serde_json::from_str::<serde_json::Value>(json).unwrap()
+        );
+        entry
+    }
+
+    #[test]
+    fn version_0_6() {
+        for json in [
+            r#"{"id":1,"type":"vertex","label":"source","workspaceRoot":"file:///ws","repository":{"type":"git","url":"https://example.com/repo.git"}}"#,
+            r#"{"id":2,"type":"vertex","label":"capabilities","hoverProvider":true,"declarationProvider":false,"definitionProvider":true,"typeDefinitionProvider":false,"referencesProvider":true,"documentSymbolProvider":false,"foldingRangeProvider":false,"diagnosticProvider":false}"#,
+            r#"{"id":3,"type":"vertex","label":"group","uri":"file:///ws/group","conflictResolution":"takeDB","name":"group","rootUri":"file:///ws"}"#,
+            r#"{"id":4,"type":"edge","label":"belongsTo","outV":5,"inV":3}"#,
+            r#"{"id":6,"type":"edge","label":"attach","outV":7,"inV":8}"#,
+            r#"{"id":9,"type":"vertex","label":"implementationResult","result":[10,{"uri":"file:///ws/a.rs","range":{"start":{"line":0,"character":0},"end":{"line":0,"character":1}}}]}"#,
+            r#"{"id":11,"type":"vertex","label":"typeDefinitionResult"}"#,
+        ] {
+            test_round_trip(json);
+        }
+
+        let entry = test_round_trip(
+            r#"{"id":12,"type":"edge","label":"item","outV":9,"inVs":[10],"shard":5}"#,
+        );
+        let Element::Edge(Edge::Item(item)) = entry.data else {
+            panic!("{entry:?}");
+        };
+        assert_eq!(item.document, None);
+        assert_eq!(item.shard_or_document(), Some(&Id::Number(5)));
+    }
+
+    #[test]
+    fn tags() {
+        let range = Range::new(Position::new(0, 0), Position::new(2, 1));
+        #[expect(
+            deprecated,
+            reason = "the deprecated property is still part of the protocol"
+        )]
+        let symbol = DocumentSymbol {
+            name: "main".to_owned(),
+            detail: Some("fn()".to_owned()),
+            kind: SymbolKind::FUNCTION,
+            tags: Some(vec![SymbolTag::DEPRECATED]),
+            deprecated: None,
+            range,
+            selection_range: Range::new(Position::new(0, 3), Position::new(0, 7)),
+            children: None,
+        };
+        let tag = DefinitionTag::from(&symbol);
+        assert!(tag.deprecated);
+        assert_eq!(
+            tag,
+            DefinitionTag {
+                deprecated: true,
+                detail: Some("fn()".to_owned()),
+                ..DefinitionTag::new("main".to_owned(), SymbolKind::FUNCTION, range)
+            }
+        );
+        assert_eq!(
+            serde_json::to_value(RangeTag::Reference(ReferenceTag::new("main".to_owned())))
+                .unwrap(),
+            serde_json::json!({ "type": "reference", "text": "main" })
+        );
+    }
+}
@@ -0,0 +1,547 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ptr,
+};
+
+use crate::{
+    Uri,
+    lsp::{
+        DocumentLink, DocumentSymbol, DocumentSymbolResponse, FoldingRange, GotoDefinitionResponse,
+        Hover, Location, MonikerKind, Position, UniquenessLevel,
+    },
+    request::{GotoDeclarationResponse, GotoImplementationResponse, GotoTypeDefinitionResponse},
+};
+
+use super::{
+    DocumentSymbolOrRangeBasedVec, Edge, ItemKind, LocationOrRangeId, LsifGraph,
+    RangeBasedDocumentSymbol, RangeTag, Vertex, VertexIndex,
+};
+
+/// Answers requests from LSIF dumps instead of a running language server.
+///
+/// Requests at a position look up the innermost range of the document that contains the
+/// position and follow its `next` chain to the results. If the dump has no result for a range,
+/// its monikers that are not local are looked up in the other dumps, so that for example a
+/// definition in the dump of a dependency is found.
+///
+/// # Examples
+///
+/// ```
+/// use ls_types::{
+///     lsif::{LsifGraph, LsifQuery},
+///     lsp::{GotoDefinitionResponse, Position},
+/// };
+///
+/// let dump = r#"{"id":1,"type":"vertex","label":"document","uri":"file:///a.ts","languageId":"typescript"}
+/// {"id":2,"type":"vertex","label":"range","start":{"line":0,"character":6},"end":{"line":0,"character":7}}
+/// {"id":3,"type":"vertex","label":"range","start":{"line":1,"character":0},"end":{"line":1,"character":1}}
+/// {"id":4,"type":"edge","label":"contains","outV":1,"inVs":[2,3]}
+/// {"id":5,"type":"vertex","label":"resultSet"}
+/// {"id":6,"type":"edge","label":"next","outV":2,"inV":5}
+/// {"id":7,"type":"edge","label":"next","outV":3,"inV":5}
+/// {"id":8,"type":"vertex","label":"definitionResult"}
+/// {"id":9,"type":"edge","label":"textDocument/definition","outV":5,"inV":8}
+/// {"id":10,"type":"edge","label":"item","outV":8,"inVs":[2],"document":1}
+/// "#;
+/// let graph = LsifGraph::from_reader(dump.as_bytes()).unwrap();
+/// let query = LsifQuery::new(&graph);
+/// let uri = "file:///a.ts".parse().unwrap();
+/// let Some(GotoDefinitionResponse::Array(locations)) =
+///     query.definition(&uri, Position::new(1, 1))
+/// else {
+///     panic!()
+/// };
+/// assert_eq!(locations[0].range.start, Position::new(0, 6));
+/// ```
+#[derive(Debug, Clone)]
+pub struct LsifQuery<'a> {
+    dumps: Vec<&'a LsifGraph>,
+    /// The `moniker` vertices of all dumps by their scheme and identifier.
+    monikers: HashMap<(&'a str, &'a str), Vec<(&'a LsifGraph, VertexIndex)>>,
+}
+
+impl<'a> LsifQuery<'a> {
+    #[must_use]
+    pub fn new(dump: &'a LsifGraph) -> Self {
+        Self {
+            dumps: vec![],
+            monikers: HashMap::new(),
+        }
+        .with_dump(dump)
+    }
+
+    /// Adds another dump, like the dump of a dependency, that monikers are looked up in.
+    #[must_use]
+    pub fn with_dump(mut self, dump: &'a LsifGraph) -> Self {
+        for vertex in dump.vertices() {
+            if let Vertex::Moniker(moniker) = dump.vertex(vertex) {
+                self.monikers
+                    .entry((&moniker.scheme, &moniker.identifier))
+                    .or_default()
+                    .push((dump, vertex));
+            }
+        }
+        self.dumps.push(dump);
+        self
+    }
+
+    /// Answers a `textDocument/hover` request.
+    #[must_use]
+    pub fn hover(&self, uri: &Uri, position: Position) -> Option<Hover> {
+        self.find_at(uri, position, |graph, vertex| graph.hover(vertex).cloned())
+    }
+
+    /// Answers a `textDocument/definition` request.
+    #[must_use]
+    pub fn definition(&self, uri: &Uri, position: Position) -> Option<GotoDefinitionResponse> {
+        self.goto(uri, position, LsifGraph::definition_result)
+    }
+
+    /// Answers a `textDocument/declaration` request.
+    #[must_use]
+    pub fn declaration(&self, uri: &Uri, position: Position) -> Option<GotoDeclarationResponse> {
+        self.goto(uri, position, LsifGraph::declaration_result)
+    }
+
+    /// Answers a `textDocument/typeDefinition` request.
+    #[must_use]
+    pub fn type_definition(
+        &self,
+        uri: &Uri,
+        position: Position,
+    ) -> Option<GotoTypeDefinitionResponse> {
+        self.goto(uri, position, LsifGraph::type_definition_result)
+    }
+
+    /// Answers a `textDocument/implementation` request, including the implementations of the
+    /// results that `item` edges with [`ItemKind::ImplementationResults`] point to.
+    #[must_use]
+    pub fn implementation(
+        &self,
+        uri: &Uri,
+        position: Position,
+    ) -> Option<GotoImplementationResponse> {
+        self.goto(uri, position, LsifGraph::implementation_result)
+    }
+
+    /// Answers a `textDocument/references` request, including the references of the results
+    /// that `item` edges with [`ItemKind::ReferenceResults`] point to and the references in the
+    /// other dumps linked by monikers.
+    ///
+    /// Unless `include_declaration`, the ranges of `item` edges with
+    /// [`ItemKind::Definitions`] or [`ItemKind::Declarations`] are left out.
+    #[must_use]
+    pub fn references(
+        &self,
+        uri: &Uri,
+        position: Position,
+        include_declaration: bool,
+    ) -> Option<Vec<Location>> {
+        let (graph, ranges) = self.ranges_at(uri, position)?;
+        let range = *ranges.first()?;
+        let mut locations = vec![];
+        for (graph, vertex) in std::iter::once((graph, range)).chain(self.linked(graph, range)) {
+            if let Some(result) = graph.reference_result(vertex) {
+                for location in result_locations(graph, result, include_declaration) {
+                    if !locations.contains(&location) {
+                        locations.push(location);
+                    }
+                }
+            }
+        }
+        Some(locations)
+    }
+
+    /// Answers a `textDocument/foldingRange` request.
+    #[must_use]
+    pub fn folding_ranges(&self, uri: &Uri) -> Option<Vec<FoldingRange>> {
+        let (graph, document) = self.document(uri)?;
+        match graph.vertex(graph.result(document, "textDocument/foldingRange")?) {
+            Vertex::FoldingRangeResult { result } => Some(result.clone()),
+            _ => None,
+        }
+    }
+
+    /// Answers a `textDocument/documentSymbol` request.
+    ///
+    /// Range based symbols are converted to [`DocumentSymbol`]s with the text, kind and full
+    /// range of the definition or declaration tag of their range.
+    #[must_use]
+    pub fn document_symbols(&self, uri: &Uri) -> Option<DocumentSymbolResponse> {
+        let (graph, document) = self.document(uri)?;
+        match graph.vertex(graph.result(document, "textDocument/documentSymbol")?) {
+            Vertex::DocumentSymbolResult {
+                result: DocumentSymbolOrRangeBasedVec::DocumentSymbol(symbols),
+            } => Some(DocumentSymbolResponse::Nested(symbols.clone())),
+            Vertex::DocumentSymbolResult {
+                result: DocumentSymbolOrRangeBasedVec::RangeBased(symbols),
+            } => Some(DocumentSymbolResponse::Nested(range_based_symbols(
+                graph, symbols,
+            ))),
+            _ => None,
+        }
+    }
+
+    /// Answers a `textDocument/documentLink` request.
+    #[must_use]
+    pub fn document_links(&self, uri: &Uri) -> Option<Vec<DocumentLink>> {
+        let (graph, document) = self.document(uri)?;
+        match graph.vertex(graph.result(document, "textDocument/documentLink")?) {
+            Vertex::DocumentLinkResult { result } => Some(result.clone()),
+            _ => None,
+        }
+    }
+
+    /// The dump and `document` vertex of `uri`.
+    fn document(&self, uri: &Uri) -> Option<(&'a LsifGraph, VertexIndex)> {
+        self.dumps
+            .iter()
+            .find_map(|&graph| Some((graph, graph.document(uri)?)))
+    }
+
+    /// The ranges of `uri` that contain `position`, the innermost first.
+    fn ranges_at(
+        &self,
+        uri: &Uri,
+        position: Position,
+    ) -> Option<(&'a LsifGraph, Vec<VertexIndex>)> {
+        let (graph, document) = self.document(uri)?;
+        let mut ranges: Vec<_> = graph
+            .ranges(document)
+            .filter_map(|vertex| {
+                let (range, _) = graph.range(vertex)?;
+                (range.start <= position && position <= range.end).then_some((vertex, range))
+            })
+            .collect();
+        ranges.sort_by(|(_, a), (_, b)| b.start.cmp(&a.start).then(a.end.cmp(&b.end)));
+        Some((
+            graph,
+            ranges.into_iter().map(|(vertex, _)| vertex).collect(),
+        ))
+    }
+
+    /// Calls `find` with the ranges at `position` from the innermost one, and then with the
+    /// vertices of the other dumps they are linked to, until it finds something.
+    fn find_at<T>(
+        &self,
+        uri: &Uri,
+        position: Position,
+        find: impl Fn(&'a LsifGraph, VertexIndex) -> Option<T>,
+    ) -> Option<T> {
+        let (graph, ranges) = self.ranges_at(uri, position)?;
+        ranges.iter().find_map(|&range| {
+            find(graph, range).or_else(|| {
+                self.linked(graph, range)
+                    .into_iter()
+                    .find_map(|(graph, vertex)| find(graph, vertex))
+            })
+        })
+    }
+
+    fn goto(
+        &self,
+        uri: &Uri,
+        position: Position,
+        result: impl Fn(&LsifGraph, VertexIndex) -> Option<VertexIndex>,
+    ) -> Option<GotoDefinitionResponse> {
+        self.find_at(uri, position, |graph, vertex| {
+            let locations = result_locations(graph, result(graph, vertex)?, true);
+            (!locations.is_empty()).then_some(GotoDefinitionResponse::Array(locations))
+        })
+    }
+
+    /// The vertices of the other dumps that have a moniker of `vertex` or of its result sets,
+    /// unless the moniker is local or only unique in its document.
+    fn linked(&self, graph: &LsifGraph, vertex: VertexIndex) -> Vec<(&'a LsifGraph, VertexIndex)> {
+        let mut keys = vec![];
+        for moniker in graph
+            .next_chain(vertex)
+            .flat_map(|vertex| graph.monikers(vertex))
+        {
+            if let Vertex::Moniker(moniker) = graph.vertex(moniker)
+                && moniker.kind != Some(MonikerKind::Local)
+                && moniker.unique != UniquenessLevel::Document
+                && !keys.contains(&(moniker.scheme.as_str(), moniker.identifier.as_str()))
+            {
+                keys.push((&moniker.scheme, &moniker.identifier));
+            }
+        }
+        let mut linked = vec![];
+        for key in keys {
+            for &(other, candidate) in self.monikers.get(&key).into_iter().flatten() {
+                if !ptr::eq(other, graph) {
+                    linked.extend(moniker_owners(other, candidate).map(|owner| (other, owner)));
+                }
+            }
+        }
+        linked
+    }
+}
+
+/// The vertices with a `moniker` edge to `moniker` or to a moniker linked to it by
+/// `nextMoniker` edges.
+fn moniker_owners(graph: &LsifGraph, moniker: VertexIndex) -> impl Iterator<Item = VertexIndex> {
+    let mut monikers = vec![moniker];
+    let mut index = 0;
+    while let Some(&moniker) = monikers.get(index) {
+        for (edge, out_v) in graph.incoming(moniker) {
+            if matches!(edge, Edge::NextMoniker(_)) && !monikers.contains(&out_v) {
+                monikers.push(out_v);
+            }
+        }
+        index += 1;
+    }
+    monikers.into_iter().flat_map(|moniker| {
+        graph
+            .incoming(moniker)
+            .filter(|(edge, _)| matches!(edge, Edge::Moniker(_)))
+            .map(|(_, out_v)| out_v)
+    })
+}
+
+/// The locations of the ranges a result points to with `item` edges, following `item` edges
+/// to other results, and the locations in the `result` of results of older dumps.
+fn result_locations(
+    graph: &LsifGraph,
+    result: VertexIndex,
+    include_declaration: bool,
+) -> Vec<Location> {
+    let mut locations = vec![];
+    let mut visited = HashSet::new();
+    let mut results = vec![result];
+    while let Some(result) = results.pop() {
+        if !visited.insert(result) {
+            continue;
+        }
+        if let Vertex::ImplementationResult {
+            result: Some(payload),
+        }
+        | Vertex::TypeDefinitionResult {
+            result: Some(payload),
+        } = graph.vertex(result)
+        {
+            for item in payload {
+                let item_location = match item {
+                    LocationOrRangeId::Location(location) => Some(location.clone()),
+                    LocationOrRangeId::RangeId(id) => graph
+                        .find(id)
+                        .and_then(|range| location(graph, graph.containing_document(range), range)),
+                };
+                if let Some(item_location) = item_location
+                    && !locations.contains(&item_location)
+                {
+                    locations.push(item_location);
+                }
+            }
+        }
+        for (item, vertex) in graph.items(result) {
+            match item.property {
+                Some(ItemKind::ReferenceResults | ItemKind::ImplementationResults) => {
+                    results.push(vertex);
+                }
+                Some(ItemKind::Definitions | ItemKind::Declarations) if !include_declaration => {}
+                _ => {
+                    let document = item
+                        .shard_or_document()
+                        .and_then(|shard| graph.find(shard))
+                        .filter(|&shard| matches!(graph.vertex(shard), Vertex::Document(_)))
+                        .or_else(|| graph.containing_document(vertex));
+                    if let Some(location) = location(graph, document, vertex)
+                        && !locations.contains(&location)
+                    {
+                        locations.push(location);
+                    }
+                }
+            }
+        }
+    }
+    locations
+}
+
+fn location(
+    graph: &LsifGraph,
+    document: Option<VertexIndex>,
+    range: VertexIndex,
+) -> Option<Location> {
+    let Vertex::Document(document) = graph.vertex(document?) else {
+        return None;
+    };
+    let (range, _) = graph.range(range)?;
+    Some(Location::new(document.uri.clone(), *range))
+}
+
+/// Converts range based symbols, putting the children of symbols whose range has no definition
+/// or declaration tag in their place.
+fn range_based_symbols(
+    graph: &LsifGraph,
+    symbols: &[RangeBasedDocumentSymbol],
+) -> Vec<DocumentSymbol> {
+    let mut converted = vec![];
+    for symbol in symbols {
+        let children = range_based_symbols(graph, &symbol.children);
+        let range = graph
+            .find(&symbol.id)
+            .and_then(|vertex| graph.range(vertex));
+        let (name, kind, deprecated, range, detail, selection_range) = match range {
+            Some((selection_range, Some(RangeTag::Definition(tag)))) => (
+                &tag.text,
+                tag.kind,
+                tag.deprecated,
+                tag.full_range,
+                &tag.detail,
+                *selection_range,
+            ),
+            Some((selection_range, Some(RangeTag::Declaration(tag)))) => (
+                &tag.text,
+                tag.kind,
+                tag.deprecated,
+                tag.full_range,
+                &tag.detail,
+                *selection_range,
+            ),
+            _ => {
+                converted.extend(children);
+                continue;
+            }
+        };
+        #[expect(
+            deprecated,
+            reason = "the deprecated property is still part of the protocol"
+        )]
+        converted.push(DocumentSymbol {
+            name: name.clone(),
+            detail: detail.clone(),
+            kind,
+            tags: None,
+            deprecated: deprecated.then_some(true),
+            range,
+            selection_range,
+            children: (!children.is_empty()).then_some(children),
+        });
+    }
+    converted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::{HoverContents, MarkedString, Range, SymbolKind};
+
+    fn load(dump: &str) -> LsifGraph {
+        LsifGraph::from_reader(dump.as_bytes()).unwrap()
+    }
+
+    fn location(uri: &str, start: (u32, u32), end: (u32, u32)) -> Location {
+        Location::new(
+            uri.parse().unwrap(),
+            Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1)),
+        )
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn tsc() {
+        let graph = load(include_str!("../../tests/tsc-unix.lsif"));
+        let query = LsifQuery::new(&graph);
+        let a = "file:///media/hamid/nv1/garbage/a.ts";
+        let b: Uri = "file:///media/hamid/nv1/garbage/b.ts".parse().unwrap();
+
+        let Some(Hover {
+            contents: HoverContents::Array(contents),
+            ..
+        }) = query.hover(&b, Position::new(2, 1))
+        else {
+            panic!();
+        };
+        assert!(matches!(
+            &contents[0],
+            MarkedString::LanguageString(string) if string.value.starts_with("(alias)")
+        ));
+
+        assert_eq!(
+            query.definition(&b, Position::new(2, 0)),
+            Some(GotoDefinitionResponse::Array(vec![location(
+                a,
+                (0, 13),
+                (0, 14)
+            )]))
+        );
+        assert_eq!(
+            query.references(&b, Position::new(2, 0), true),
+            Some(vec![
+                location(a, (0, 13), (0, 14)),
+                location(b.as_str(), (0, 9), (0, 10)),
+                location(b.as_str(), (2, 0), (2, 1)),
+            ])
+        );
+        assert_eq!(
+            query
+                .references(&b, Position::new(2, 0), false)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(query.definition(&b, Position::new(5, 0)), None);
+        assert_eq!(query.folding_ranges(&b), None);
+    }
+
+    #[test]
+    fn monikers() {
+        let app = load(
+            r#"{"id":1,"type":"vertex","label":"document","uri":"file:///app/main.ts","languageId":"typescript"}
+{"id":2,"type":"vertex","label":"range","start":{"line":3,"character":0},"end":{"line":3,"character":3}}
+{"id":3,"type":"edge","label":"contains","outV":1,"inVs":[2]}
+{"id":4,"type":"vertex","label":"resultSet"}
+{"id":5,"type":"edge","label":"next","outV":2,"inV":4}
+{"id":6,"type":"vertex","label":"moniker","kind":"import","unique":"scheme","scheme":"npm","identifier":"lib:foo"}
+{"id":7,"type":"edge","label":"moniker","outV":4,"inV":6}"#,
+        );
+        let lib = load(
+            r#"{"id":1,"type":"vertex","label":"document","uri":"file:///lib/index.ts","languageId":"typescript"}
+{"id":2,"type":"vertex","label":"range","start":{"line":0,"character":16},"end":{"line":0,"character":19},"tag":{"type":"definition","text":"foo","kind":12,"fullRange":{"start":{"line":0,"character":0},"end":{"line":2,"character":1}}}}
+{"id":3,"type":"edge","label":"contains","outV":1,"inVs":[2]}
+{"id":4,"type":"vertex","label":"resultSet"}
+{"id":5,"type":"edge","label":"next","outV":2,"inV":4}
+{"id":6,"type":"vertex","label":"moniker","kind":"export","unique":"document","scheme":"tsc","identifier":"index:foo"}
+{"id":7,"type":"edge","label":"moniker","outV":4,"inV":6}
+{"id":8,"type":"vertex","label":"moniker","kind":"export","unique":"scheme","scheme":"npm","identifier":"lib:foo"}
+{"id":9,"type":"edge","label":"nextMoniker","outV":6,"inV":8}
+{"id":10,"type":"vertex","label":"definitionResult"}
+{"id":11,"type":"edge","label":"textDocument/definition","outV":4,"inV":10}
+{"id":12,"type":"edge","label":"item","outV":10,"inVs":[2],"document":1}
+{"id":13,"type":"vertex","label":"documentSymbolResult","result":[{"id":2}]}
+{"id":14,"type":"edge","label":"textDocument/documentSymbol","outV":1,"inV":13}"#,
+        );
+        let main = "file:///app/main.ts".parse().unwrap();
+        assert_eq!(
+            LsifQuery::new(&app).definition(&main, Position::new(3, 1)),
+            None
+        );
+
+        let query = LsifQuery::new(&app).with_dump(&lib);
+        assert_eq!(
+            query.definition(&main, Position::new(3, 1)),
+            Some(GotoDefinitionResponse::Array(vec![location(
+                "file:///lib/index.ts",
+                (0, 16),
+                (0, 19)
+            )]))
+        );
+        assert_eq!(
+            LsifQuery::new(&lib)
+                .with_dump(&app)
+                .definition(&main, Position::new(3, 1)),
+            query.definition(&main, Position::new(3, 1))
+        );
+
+        let Some(DocumentSymbolResponse::Nested(symbols)) =
+            query.document_symbols(&"file:///lib/index.ts".parse().unwrap())
+        else {
+            panic!();
+        };
+        assert_eq!(symbols[0].name, "foo");
+        assert_eq!(symbols[0].kind, SymbolKind::FUNCTION);
+        assert_eq!(symbols[0].range.end, Position::new(2, 1));
+        assert_eq!(symbols[0].selection_range.start, Position::new(0, 16));
+    }
+}
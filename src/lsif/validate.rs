@@ -0,0 +1,402 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    io::{self, BufRead},
+};
+
+use super::{Edge, Element, Entry, EventKind, EventScope, Id, RangeTag, Vertex};
+
+/// A violation of the structural rules of LSIF found by [`validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LsifDiagnostic {
+    /// The one-based number of the line of the entry.
+    pub line: usize,
+    pub kind: LsifDiagnosticKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LsifDiagnosticKind {
+    /// The line is not an [`Entry`].
+    InvalidEntry(String),
+    /// The id was used by an earlier entry.
+    DuplicateId(Id),
+    /// The dump has no `metaData` vertex.
+    MissingMetaData,
+    /// The `metaData` vertex is not the first entry.
+    MetaDataNotFirst,
+    /// The edge refers to a vertex that was not emitted before it.
+    DanglingEdge { vertex: Id },
+    /// The edge points to a vertex with another label than its label requires, like a
+    /// `textDocument/hover` edge to a `definitionResult`.
+    WrongTarget {
+        edge: &'static str,
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The range is not contained in a document by a `contains` edge.
+    RangeNotContained(Id),
+    /// The range is not included in the `fullRange` of its tag.
+    RangeOutsideFullRange(Id),
+    /// The `$event` ends a scope that was not begun, or begins one that was already begun.
+    UnbalancedEvent { scope: EventScope, data: Id },
+    /// The `$event` begins a scope that is never ended.
+    UnclosedEvent { scope: EventScope, data: Id },
+}
+
+impl fmt::Display for LsifDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl fmt::Display for LsifDiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidEntry(error) => write!(f, "invalid entry: {error}"),
+            Self::DuplicateId(id) => write!(f, "duplicate id {id}"),
+            Self::MissingMetaData => f.write_str("the dump has no metaData vertex"),
+            Self::MetaDataNotFirst => f.write_str("the metaData vertex is not the first entry"),
+            Self::DanglingEdge { vertex } => {
+                write!(
+                    f,
+                    "the edge refers to the vertex {vertex} before it is emitted"
+                )
+            }
+            Self::WrongTarget {
+                edge,
+                expected,
+                found,
+            } => write!(
+                f,
+                "the {edge} edge points to a {found} instead of a {expected}"
+            ),
+            Self::RangeNotContained(id) => {
+                write!(f, "the range {id} is not contained in a document")
+            }
+            Self::RangeOutsideFullRange(id) => {
+                write!(f, "the range {id} is not included in its full range")
+            }
+            Self::UnbalancedEvent { scope, data } => {
+                write!(f, "unbalanced {scope:?} event of {data}")
+            }
+            Self::UnclosedEvent { scope, data } => {
+                write!(f, "the {scope:?} event of {data} is never ended")
+            }
+        }
+    }
+}
+
+/// Checks the structural rules of LSIF on a dump, taking the index of each entry plus one as
+/// its line number.
+///
+/// The diagnostics are sorted by line.
+///
+/// # Examples
+///
+/// ```
+/// use ls_types::lsif::validate_reader;
+///
+/// let dump = r#"{"id":1,"type":"vertex","label":"metaData","version":"0.6.0","projectRoot":"file:///","positionEncoding":"utf-16"}
+/// {"id":2,"type":"vertex","label":"range","start":{"line":0,"character":0},"end":{"line":0,"character":1}}
+/// {"id":3,"type":"edge","label":"contains","outV":4,"inVs":[2]}
+/// "#;
+/// let diagnostics = validate_reader(dump.as_bytes()).unwrap();
+/// assert_eq!(diagnostics.len(), 2);
+/// assert_eq!(diagnostics[0].line, 2);
+/// assert_eq!(
+///     diagnostics[1].to_string(),
+///     "line 3: the edge refers to the vertex 4 before it is emitted"
+/// );
+/// ```
+#[must_use]
+pub fn validate<I>(entries: I) -> Vec<LsifDiagnostic>
+where
+    I: IntoIterator<Item = Entry>,
+{
+    let mut validator = Validator::default();
+    for (index, entry) in entries.into_iter().enumerate() {
+        validator.entry(index + 1, entry);
+    }
+    validator.finish()
+}
+
+/// Checks the structural rules of LSIF on a dump in the JSON lines format, skipping empty
+/// lines and reporting lines that are not an [`Entry`].
+///
+/// # Errors
+///
+/// Returns an error if reading fails.
+pub fn validate_reader<R: BufRead>(reader: R) -> io::Result<Vec<LsifDiagnostic>> {
+    let mut validator = Validator::default();
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(entry) => validator.entry(index + 1, entry),
+            Err(error) => validator.invalid_entry(index + 1, &error),
+        }
+    }
+    Ok(validator.finish())
+}
+
+/// The label of the vertex an edge has to point to.
+const fn target_label(edge: &Edge) -> Option<&'static str> {
+    match edge {
+        Edge::Contains(_) | Edge::Item(_) => None,
+        Edge::Next(_) => Some("resultSet"),
+        Edge::Moniker(_) | Edge::NextMoniker(_) | Edge::Attach(_) => Some("moniker"),
+        Edge::BelongsTo(_) => Some("group"),
+        Edge::PackageInformation(_) => Some("packageInformation"),
+        Edge::Definition(_) => Some("definitionResult"),
+        Edge::Declaration(_) => Some("declarationResult"),
+        Edge::Hover(_) => Some("hoverResult"),
+        Edge::References(_) => Some("referenceResult"),
+        Edge::Implementation(_) => Some("implementationResult"),
+        Edge::TypeDefinition(_) => Some("typeDefinitionResult"),
+        Edge::FoldingRange(_) => Some("foldingRangeResult"),
+        Edge::DocumentLink(_) => Some("documentLinkResult"),
+        Edge::DocumentSymbol(_) => Some("documentSymbolResult"),
+        Edge::Diagnostic(_) => Some("diagnosticResult"),
+    }
+}
+
+#[derive(Debug, Default)]
+struct Validator {
+    diagnostics: Vec<LsifDiagnostic>,
+    entries: usize,
+    metadata: bool,
+    ids: HashSet<Id>,
+    /// The labels of the vertices.
+    vertices: HashMap<Id, &'static str>,
+    /// The ranges with the lines they are on.
+    ranges: Vec<(usize, Id)>,
+    /// The ranges that a document contains.
+    contained: HashSet<Id>,
+    /// The scopes that are begun but not ended, with the lines of their `begin` event.
+    events: Vec<(usize, EventScope, Id)>,
+}
+
+impl Validator {
+    fn report(&mut self, line: usize, kind: LsifDiagnosticKind) {
+        self.diagnostics.push(LsifDiagnostic { line, kind });
+    }
+
+    /// Reports a line that is not an entry, which still counts as an entry.
+    fn invalid_entry(&mut self, line: usize, error: &serde_json::Error) {
+        self.entries += 1;
+        self.report(line, LsifDiagnosticKind::InvalidEntry(error.to_string()));
+    }
+
+    fn entry(&mut self, line: usize, Entry { id, data }: Entry) {
+        self.entries += 1;
+        if !self.ids.insert(id.clone()) {
+            self.report(line, LsifDiagnosticKind::DuplicateId(id.clone()));
+        }
+        match data {
+            Element::Vertex(vertex) => self.vertex(line, id, &vertex),
+            Element::Edge(edge) => self.edge(line, &edge),
+        }
+    }
+
+    fn vertex(&mut self, line: usize, id: Id, vertex: &Vertex) {
+        match vertex {
+            Vertex::MetaData(_) => {
+                if self.entries != 1 {
+                    self.report(line, LsifDiagnosticKind::MetaDataNotFirst);
+                }
+                self.metadata = true;
+            }
+            Vertex::Range { range, tag } => {
+                let full_range = match tag {
+                    Some(RangeTag::Definition(tag)) => Some(tag.full_range),
+                    Some(RangeTag::Declaration(tag)) => Some(tag.full_range),
+                    _ => None,
+                };
+                if let Some(full_range) = full_range
+                    && (range.start < full_range.start || full_range.end < range.end)
+                {
+                    self.report(line, LsifDiagnosticKind::RangeOutsideFullRange(id.clone()));
+                }
+                self.ranges.push((line, id.clone()));
+            }
+            Vertex::Event(event) => {
+                let open = self
+                    .events
+                    .iter()
+                    .position(|(_, scope, data)| *scope == event.scope && *data == event.data);
+                match (event.kind, open) {
+                    (EventKind::Begin, None) => {
+                        self.events.push((line, event.scope, event.data.clone()));
+                    }
+                    (EventKind::End, Some(open)) => {
+                        self.events.remove(open);
+                    }
+                    _ => self.report(
+                        line,
+                        LsifDiagnosticKind::UnbalancedEvent {
+                            scope: event.scope,
+                            data: event.data.clone(),
+                        },
+                    ),
+                }
+            }
+            _ => {}
+        }
+        self.vertices.insert(id, vertex.label());
+    }
+
+    fn edge(&mut self, line: usize, edge: &Edge) {
+        let shards: Vec<&Id> = match edge {
+            Edge::Item(item) => item.document.iter().chain(&item.shard).collect(),
+            _ => vec![],
+        };
+        let dangling: Vec<Id> = std::iter::once(edge.out_v())
+            .chain(edge.in_vs())
+            .chain(shards)
+            .filter(|vertex| !self.vertices.contains_key(*vertex))
+            .cloned()
+            .collect();
+        for vertex in dangling {
+            self.report(line, LsifDiagnosticKind::DanglingEdge { vertex });
+        }
+
+        if let Some(expected) = target_label(edge) {
+            for in_v in edge.in_vs() {
+                if let Some(&found) = self.vertices.get(in_v)
+                    && found != expected
+                {
+                    self.report(
+                        line,
+                        LsifDiagnosticKind::WrongTarget {
+                            edge: edge.label(),
+                            expected,
+                            found,
+                        },
+                    );
+                }
+            }
+        }
+        if let Edge::Contains(data) = edge
+            && self.vertices.get(&data.out_v) == Some(&"document")
+        {
+            self.contained.extend(data.in_vs.iter().cloned());
+        }
+    }
+
+    fn finish(mut self) -> Vec<LsifDiagnostic> {
+        if !self.metadata {
+            self.report(1, LsifDiagnosticKind::MissingMetaData);
+        }
+        for (line, range) in std::mem::take(&mut self.ranges) {
+            if !self.contained.contains(&range) {
+                self.report(line, LsifDiagnosticKind::RangeNotContained(range));
+            }
+        }
+        for (line, scope, data) in std::mem::take(&mut self.events) {
+            self.report(line, LsifDiagnosticKind::UnclosedEvent { scope, data });
+        }
+        self.diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+        self.diagnostics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(unix)]
+    fn valid() {
+        let dump = include_str!("../../tests/tsc-unix.lsif");
+        assert_eq!(validate_reader(dump.as_bytes()).unwrap(), []);
+    }
+
+    #[test]
+    fn invalid() {
+        let dump = r#"{"id":1,"type":"vertex","label":"document","uri":"file:///a","languageId":"c"}
+{"id":2,"type":"vertex","label":"$event","kind":"begin","scope":"document","data":1}
+{"id":3,"type":"vertex","label":"metaData","version":"0.6.0","projectRoot":"file:///","positionEncoding":"utf-16"}
+{"id":3,"type":"vertex","label":"range","start":{"line":1,"character":0},"end":{"line":1,"character":3},"tag":{"type":"definition","text":"foo","kind":12,"fullRange":{"start":{"line":1,"character":1},"end":{"line":2,"character":0}}}}
+{"id":5,"type":"vertex","label":"range","start":{"line":3,"character":0},"end":{"line":3,"character":3}}
+{"id":6,"type":"edge","label":"contains","outV":1,"inVs":[3]}
+{"id":7,"type":"vertex","label":"definitionResult"}
+{"id":8,"type":"edge","label":"textDocument/hover","outV":3,"inV":7}
+{"id":9,"type":"edge","label":"next","outV":3,"inV":10}
+{"id":11,"type":"vertex","label":"$event","kind":"end","scope":"project","data":1}
+invalid
+"#;
+        let diagnostics: Vec<_> = validate_reader(dump.as_bytes())
+            .unwrap()
+            .into_iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.kind))
+            .collect();
+        assert_eq!(
+            diagnostics[..8],
+            [
+                (
+                    2,
+                    LsifDiagnosticKind::UnclosedEvent {
+                        scope: EventScope::Document,
+                        data: Id::Number(1),
+                    }
+                ),
+                (3, LsifDiagnosticKind::MetaDataNotFirst),
+                (4, LsifDiagnosticKind::DuplicateId(Id::Number(3))),
+                (4, LsifDiagnosticKind::RangeOutsideFullRange(Id::Number(3))),
+                (5, LsifDiagnosticKind::RangeNotContained(Id::Number(5))),
+                (
+                    8,
+                    LsifDiagnosticKind::WrongTarget {
+                        edge: "textDocument/hover",
+                        expected: "hoverResult",
+                        found: "definitionResult",
+                    }
+                ),
+                (
+                    9,
+                    LsifDiagnosticKind::DanglingEdge {
+                        vertex: Id::Number(10),
+                    }
+                ),
+                (
+                    10,
+                    LsifDiagnosticKind::UnbalancedEvent {
+                        scope: EventScope::Project,
+                        data: Id::Number(1),
+                    }
+                ),
+            ]
+        );
+        assert!(matches!(
+            &diagnostics[8..],
+            [(11, LsifDiagnosticKind::InvalidEntry(_))]
+        ));
+
+        let dump = r#"invalid
+{"id":1,"type":"vertex","label":"metaData","version":"0.6.0","projectRoot":"file:///","positionEncoding":"utf-16"}
+"#;
+        let diagnostics = validate_reader(dump.as_bytes()).unwrap();
+        assert!(matches!(
+            diagnostics.as_slice(),
+            [
+                LsifDiagnostic {
+                    line: 1,
+                    kind: LsifDiagnosticKind::InvalidEntry(_),
+                },
+                LsifDiagnostic {
+                    line: 2,
+                    kind: LsifDiagnosticKind::MetaDataNotFirst,
+                },
+            ]
+        ));
+        assert_eq!(
+            validate(vec![]),
+            [LsifDiagnostic {
+                line: 1,
+                kind: LsifDiagnosticKind::MissingMetaData,
+            }]
+        );
+    }
+}
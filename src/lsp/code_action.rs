@@ -6,7 +6,8 @@ use serde_json::Value;
 use crate::{
     lsp::{
         Command, Diagnostic, PartialResultParams, Range, TextDocumentIdentifier,
-        WorkDoneProgressOptions, WorkDoneProgressParams, WorkspaceEdit,
+        TextDocumentRegistrationOptions, WorkDoneProgressOptions, WorkDoneProgressParams,
+        WorkspaceEdit,
     },
     macros::lsp_enum,
 };
@@ -80,6 +81,21 @@ pub struct CodeActionClientCapabilities {
     /// @since 3.16.0
     #[serde(skip_serializing_if = "Option::is_none")]
     pub honors_change_annotations: Option<bool>,
+
+    /// Client supports the tag property on a code action. Clients
+    /// supporting tags have to handle unknown tags gracefully.
+    ///
+    /// @since 3.18.0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag_support: Option<CodeActionTagSupport>,
+}
+
+/// @since 3.18.0
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeActionTagSupport {
+    /// The tags supported by the client.
+    pub value_set: Vec<CodeActionTag>,
 }
 
 /// Whether the client supports resolving additional code action
@@ -110,6 +126,25 @@ pub struct CodeActionKindLiteralSupport {
     pub value_set: Vec<String>,
 }
 
+impl CodeActionKindLiteralSupport {
+    /// Whether the client supports `kind`, that is a kind of the value set contains it.
+    #[must_use]
+    pub fn supports(&self, kind: &CodeActionKind) -> bool {
+        self.effective_kind(kind).is_some()
+    }
+
+    /// The kind the client treats `kind` as: the most specific kind of the value set that
+    /// contains it, or `None` if the client does not support `kind`.
+    #[must_use]
+    pub fn effective_kind(&self, kind: &CodeActionKind) -> Option<CodeActionKind> {
+        self.value_set
+            .iter()
+            .filter(|value| contains(value, kind.as_str()))
+            .max_by_key(|value| value.len())
+            .map(|value| CodeActionKind::from(value.clone()))
+    }
+}
+
 /// Params for the `CodeActionRequest`
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -224,6 +259,51 @@ impl CodeActionKind {
     pub fn as_str(&self) -> &str {
         &self.0
     }
+
+    /// Whether `other` is this kind or one of its subkinds.
+    ///
+    /// For example `refactor` contains `refactor` and `refactor.extract.function`, but not
+    /// `refactoring`. The empty kind contains every kind.
+    #[must_use]
+    pub fn contains(&self, other: &Self) -> bool {
+        contains(self.as_str(), other.as_str())
+    }
+
+    /// Whether this kind is `other` or one of its subkinds.
+    #[must_use]
+    pub fn is_subkind_of(&self, other: &Self) -> bool {
+        other.contains(self)
+    }
+
+    /// The kind this kind is a subkind of, for example `refactor` for `refactor.extract`.
+    ///
+    /// Returns `None` for a top-level kind like `refactor` and for the empty kind.
+    #[must_use]
+    pub fn parent(&self) -> Option<Self> {
+        let end = self.0.rfind('.')?;
+        Some(match &self.0 {
+            Cow::Borrowed(kind) => Self(Cow::Borrowed(&kind[..end])),
+            Cow::Owned(kind) => Self(Cow::Owned(kind[..end].to_owned())),
+        })
+    }
+
+    /// Creates a subkind of this kind, for example `refactor.extract` by appending `extract`
+    /// to `refactor`.
+    #[must_use]
+    pub fn append(&self, part: &str) -> Self {
+        if self.0.is_empty() {
+            Self::from(part.to_owned())
+        } else {
+            Self::from(format!("{}.{part}", self.0))
+        }
+    }
+}
+
+fn contains(kind: &str, other: &str) -> bool {
+    kind.is_empty()
+        || other
+            .strip_prefix(kind)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
 }
 
 impl From<String> for CodeActionKind {
@@ -297,6 +377,26 @@ pub struct CodeAction {
     /// @since 3.16.0
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<Value>,
+
+    /// Tags for this code action.
+    ///
+    /// @since 3.18.0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<CodeActionTag>>,
+}
+
+/// Code action tags are extra annotations that tweak the behavior of a code action.
+///
+/// @since 3.18.0
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct CodeActionTag(i32);
+
+lsp_enum! {
+    impl CodeActionTag {
+        /// Marks the code action as LLM-generated.
+        const LLM_GENERATED = 1;
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -350,6 +450,33 @@ pub struct CodeActionContext {
     pub trigger_kind: Option<CodeActionTriggerKind>,
 }
 
+impl CodeActionContext {
+    /// Whether code actions of `kind` are requested, that is `only` is omitted or one of its
+    /// kinds contains `kind`.
+    #[must_use]
+    pub fn allows(&self, kind: &CodeActionKind) -> bool {
+        self.only
+            .as_ref()
+            .is_none_or(|only| only.iter().any(|only| only.contains(kind)))
+    }
+
+    /// Removes the code actions that are not requested from `actions`.
+    ///
+    /// If `only` is given, commands and code actions without a kind are removed as well,
+    /// since they can not be of a requested kind.
+    pub fn retain_allowed(&self, actions: &mut CodeActionResponse) {
+        if self.only.is_none() {
+            return;
+        }
+        actions.retain(|action| match action {
+            CodeActionOrCommand::Command(_) => false,
+            CodeActionOrCommand::CodeAction(action) => {
+                action.kind.as_ref().is_some_and(|kind| self.allows(kind))
+            }
+        });
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CodeActionOptions {
@@ -371,6 +498,16 @@ pub struct CodeActionOptions {
     pub resolve_provider: Option<bool>,
 }
 
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeActionRegistrationOptions {
+    #[serde(flatten)]
+    pub text_document_registration_options: TextDocumentRegistrationOptions,
+
+    #[serde(flatten)]
+    pub code_action_options: CodeActionOptions,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -398,4 +535,95 @@ mod tests {
             r#"[{"title":"title","command":"command"},{"title":"title","kind":"quickfix"}]"#,
         );
     }
+
+    #[test]
+    fn code_action_tags() {
+        test_serialization(
+            &CodeAction {
+                title: "title".to_string(),
+                tags: Some(vec![CodeActionTag::LLM_GENERATED]),
+                ..CodeAction::default()
+            },
+            r#"{"title":"title","tags":[1]}"#,
+        );
+    }
+
+    #[test]
+    fn code_action_kind_hierarchy() {
+        let function = CodeActionKind::REFACTOR_EXTRACT.append("function");
+        assert_eq!(function.as_str(), "refactor.extract.function");
+        assert_eq!(CodeActionKind::EMPTY.append("custom").as_str(), "custom");
+
+        assert!(CodeActionKind::REFACTOR.contains(&function));
+        assert!(CodeActionKind::REFACTOR.contains(&CodeActionKind::REFACTOR));
+        assert!(CodeActionKind::EMPTY.contains(&function));
+        assert!(!function.contains(&CodeActionKind::REFACTOR));
+        assert!(!CodeActionKind::REFACTOR.contains(&CodeActionKind::from("refactoring")));
+        assert!(function.is_subkind_of(&CodeActionKind::REFACTOR_EXTRACT));
+        assert!(!CodeActionKind::SOURCE.is_subkind_of(&CodeActionKind::REFACTOR));
+
+        assert_eq!(function.parent(), Some(CodeActionKind::REFACTOR_EXTRACT));
+        assert_eq!(
+            CodeActionKind::REFACTOR_EXTRACT.parent(),
+            Some(CodeActionKind::REFACTOR)
+        );
+        assert_eq!(CodeActionKind::REFACTOR.parent(), None);
+        assert_eq!(CodeActionKind::EMPTY.parent(), None);
+    }
+
+    #[test]
+    fn code_action_context_only() {
+        let action = |kind: Option<CodeActionKind>| {
+            CodeActionOrCommand::from(CodeAction {
+                title: "title".to_string(),
+                kind,
+                ..CodeAction::default()
+            })
+        };
+        let command = CodeActionOrCommand::Command(Command::new(
+            "title".to_string(),
+            "command".to_string(),
+            None,
+        ));
+        let actions = vec![
+            action(Some(CodeActionKind::QUICKFIX)),
+            action(Some(CodeActionKind::REFACTOR_EXTRACT.append("function"))),
+            action(Some(CodeActionKind::SOURCE_FIX_ALL)),
+            action(None),
+            command,
+        ];
+
+        let mut context = CodeActionContext::default();
+        let mut all = actions.clone();
+        context.retain_allowed(&mut all);
+        assert_eq!(all, actions);
+
+        context.only = Some(vec![CodeActionKind::REFACTOR, CodeActionKind::SOURCE]);
+        assert!(context.allows(&CodeActionKind::SOURCE_ORGANIZE_IMPORTS));
+        assert!(!context.allows(&CodeActionKind::QUICKFIX));
+        let mut allowed = actions.clone();
+        context.retain_allowed(&mut allowed);
+        assert_eq!(allowed, actions[1..3]);
+    }
+
+    #[test]
+    fn code_action_kind_literal_support() {
+        let support = CodeActionKindLiteralSupport {
+            value_set: vec![
+                "quickfix".to_owned(),
+                "refactor".to_owned(),
+                "refactor.extract".to_owned(),
+            ],
+        };
+        assert_eq!(
+            support.effective_kind(&CodeActionKind::REFACTOR_EXTRACT.append("function")),
+            Some(CodeActionKind::REFACTOR_EXTRACT)
+        );
+        assert_eq!(
+            support.effective_kind(&CodeActionKind::REFACTOR_INLINE),
+            Some(CodeActionKind::REFACTOR)
+        );
+        assert!(support.supports(&CodeActionKind::QUICKFIX));
+        assert!(!support.supports(&CodeActionKind::SOURCE));
+    }
 }
@@ -209,6 +209,14 @@ pub struct CompletionListCapability {
     /// @since 3.17.0
     #[serde(skip_serializing_if = "Option::is_none")]
     pub item_defaults: Option<Vec<String>>,
+
+    /// Specifies whether the client supports `CompletionList.applyKind` to
+    /// indicate how supported values from `completionList.itemDefaults`
+    /// and `completion` will be combined.
+    ///
+    /// @since 3.18.0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apply_kind_support: Option<bool>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -419,6 +427,148 @@ pub struct CompletionList {
 
     /// The completion items.
     pub items: Vec<CompletionItem>,
+
+    /// In many cases the items of an actual completion result share the same
+    /// value for properties like `commitCharacters` or the range of a text
+    /// edit. A completion list can therefore define item defaults which will
+    /// be used if a completion item itself doesn't specify the value.
+    ///
+    /// If a completion list specifies a default value and a completion item
+    /// also specifies a corresponding value the one from the item is used.
+    ///
+    /// Servers are only allowed to return default values if the client
+    /// signals support for this via the `completionList.itemDefaults`
+    /// capability.
+    ///
+    /// @since 3.17.0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_defaults: Option<CompletionListItemDefaults>,
+
+    /// Specifies how fields from a completion item should be combined with those
+    /// from `completionList.itemDefaults`.
+    ///
+    /// If unspecified, all fields will be treated as `ApplyKind::REPLACE`.
+    ///
+    /// If a field's value is `ApplyKind::REPLACE`, the value from a completion item
+    /// (if provided and not `null`) will always be used instead of the value
+    /// from `completionItem.itemDefaults`.
+    ///
+    /// If a field's value is `ApplyKind::MERGE`, the values will be merged using
+    /// the rules defined against each field below.
+    ///
+    /// Servers are only allowed to return `applyKind` if the client
+    /// signals support for this via the `completionList.applyKindSupport`
+    /// capability.
+    ///
+    /// @since 3.18.0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apply_kind: Option<CompletionItemApplyKinds>,
+}
+
+/// The default values of the items of a [`CompletionList`].
+///
+/// @since 3.17.0
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionListItemDefaults {
+    /// A default commit character set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_characters: Option<Vec<String>>,
+
+    /// A default edit range.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edit_range: Option<CompletionListItemDefaultsEditRange>,
+
+    /// A default insert text format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insert_text_format: Option<InsertTextFormat>,
+
+    /// A default insert text mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub insert_text_mode: Option<InsertTextMode>,
+
+    /// A default data value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+/// The default edit range of the items of a [`CompletionList`].
+///
+/// @since 3.17.0
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum CompletionListItemDefaultsEditRange {
+    Range(Range),
+    InsertAndReplace { insert: Range, replace: Range },
+}
+
+/// Specifies how fields from a completion item should be combined with those
+/// from `completionList.itemDefaults`.
+///
+/// @since 3.18.0
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionItemApplyKinds {
+    /// Specifies whether commitCharacters on a completion will replace or be
+    /// merged with those in `completionList.itemDefaults.commitCharacters`.
+    ///
+    /// If `ApplyKind::REPLACE`, the commit characters from the completion item
+    /// will always be used unless not provided, in which case those from
+    /// `completionList.itemDefaults.commitCharacters` will be used. An
+    /// empty list can be used if a completion item does not have any commit
+    /// characters and also should not use those from
+    /// `completionList.itemDefaults.commitCharacters`.
+    ///
+    /// If `ApplyKind::MERGE` the commitCharacters for the completion will be
+    /// the union of all values in both
+    /// `completionList.itemDefaults.commitCharacters` and the completion's
+    /// own `commitCharacters`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_characters: Option<ApplyKind>,
+
+    /// Specifies whether the `data` field on a completion will replace or
+    /// be merged with data from `completionList.itemDefaults.data`.
+    ///
+    /// If `ApplyKind::REPLACE`, the data from the completion item will be used
+    /// if provided (and not `null`), otherwise
+    /// `completionList.itemDefaults.data` will be used. An empty object can
+    /// be used if a completion item does not have any data but also should
+    /// not use the value from `completionList.itemDefaults.data`.
+    ///
+    /// If `ApplyKind::MERGE`, a shallow merge will be performed between
+    /// `completionList.itemDefaults.data` and the completion's own data
+    /// using the following rules:
+    ///
+    /// - If a completion's `data` field is not provided (or `null`), the
+    ///   entire `data` field from `completionList.itemDefaults.data` will be
+    ///   used as-is.
+    /// - If a completion's `data` field is provided, each field will
+    ///   overwrite the field of the same name in
+    ///   `completionList.itemDefaults.data` but no merging of nested fields
+    ///   within that value will occur.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<ApplyKind>,
+}
+
+/// Defines how values from a set of defaults and an individual item will be
+/// merged.
+///
+/// @since 3.18.0
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct ApplyKind(i32);
+
+lsp_enum! {
+    impl ApplyKind {
+        /// The value from the individual item (if provided and not `null`) will be
+        /// used instead of the default.
+        const REPLACE = 1;
+        /// The value from the item will be merged with the default.
+        ///
+        /// The specific rules for merging values are defined against each field
+        /// that supports merging.
+        const MERGE = 2;
+    }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -608,6 +758,32 @@ mod tests {
         test_deserialization(r#"{"tagSupport": {"valueSet": [1]}}"#, &t);
     }
 
+    #[test]
+    fn test_item_defaults_deserialization() {
+        let range = Range::default();
+        test_deserialization(
+            r#"{"isIncomplete": false, "items": [], "itemDefaults": {"editRange": {"insert": {"start": {"line": 0, "character": 0}, "end": {"line": 0, "character": 0}}, "replace": {"start": {"line": 0, "character": 0}, "end": {"line": 0, "character": 0}}}, "insertTextFormat": 2}}"#,
+            &CompletionList {
+                item_defaults: Some(CompletionListItemDefaults {
+                    edit_range: Some(CompletionListItemDefaultsEditRange::InsertAndReplace {
+                        insert: range,
+                        replace: range,
+                    }),
+                    insert_text_format: Some(InsertTextFormat::SNIPPET),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        );
+        test_deserialization(
+            r#"{"editRange": {"start": {"line": 0, "character": 0}, "end": {"line": 0, "character": 0}}}"#,
+            &CompletionListItemDefaults {
+                edit_range: Some(CompletionListItemDefaultsEditRange::Range(range)),
+                ..Default::default()
+            },
+        );
+    }
+
     #[test]
     fn test_debug_enum() {
         assert_eq!(format!("{:?}", CompletionItemKind::TEXT), "Text");
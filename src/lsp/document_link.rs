@@ -2,8 +2,8 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::lsp::{
-    PartialResultParams, Range, TextDocumentIdentifier, Uri, WorkDoneProgressOptions,
-    WorkDoneProgressParams,
+    PartialResultParams, Range, TextDocumentIdentifier, TextDocumentRegistrationOptions, Uri,
+    WorkDoneProgressOptions, WorkDoneProgressParams,
 };
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -29,6 +29,16 @@ pub struct DocumentLinkOptions {
     pub work_done_progress_options: WorkDoneProgressOptions,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentLinkRegistrationOptions {
+    #[serde(flatten)]
+    pub text_document_registration_options: TextDocumentRegistrationOptions,
+
+    #[serde(flatten)]
+    pub document_link_options: DocumentLinkOptions,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DocumentLinkParams {
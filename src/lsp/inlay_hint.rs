@@ -83,7 +83,7 @@ pub struct InlayHintParams {
 /// Inlay hint information.
 ///
 /// @since 3.17.0
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InlayHint {
     /// The position of this hint.
@@ -140,7 +140,7 @@ pub struct InlayHint {
     pub data: Option<LSPAny>,
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum InlayHintLabel {
     String(String),
@@ -161,7 +161,7 @@ impl From<Vec<InlayHintLabelPart>> for InlayHintLabel {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum InlayHintTooltip {
     String(String),
@@ -184,7 +184,7 @@ impl From<MarkupContent> for InlayHintTooltip {
 
 /// An inlay hint label part allows for interactive and composite labels
 /// of inlay hints.
-#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InlayHintLabelPart {
     /// The value of this label part.
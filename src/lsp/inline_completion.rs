@@ -2,8 +2,9 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     lsp::{
-        Command, InsertTextFormat, Range, StaticRegistrationOptions, TextDocumentPositionParams,
-        TextDocumentRegistrationOptions, WorkDoneProgressOptions, WorkDoneProgressParams,
+        Command, InsertTextFormat, OneOf, Range, StaticRegistrationOptions, StringValue,
+        TextDocumentPositionParams, TextDocumentRegistrationOptions, WorkDoneProgressOptions,
+        WorkDoneProgressParams,
     },
     macros::lsp_enum,
 };
@@ -140,7 +141,7 @@ pub struct InlineCompletionList {
 pub struct InlineCompletionItem {
     /// The text to replace the range with. Must be set.
     /// Is used both for the preview and the accept operation.
-    pub insert_text: String,
+    pub insert_text: OneOf<String, StringValue>,
     /// A text that is used to decide if this inline completion should be
     /// shown. When `falsy` the [`InlineCompletionItem::insertText`] is
     /// used.
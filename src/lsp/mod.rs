@@ -24,6 +24,7 @@ mod rename;
 mod selection_range;
 mod semantic_tokens;
 mod signature_help;
+mod text_document_content;
 mod trace;
 mod type_hierarchy;
 mod window;
@@ -57,6 +58,7 @@ pub use rename::*;
 pub use selection_range::*;
 pub use semantic_tokens::*;
 pub use signature_help::*;
+pub use text_document_content::*;
 pub use trace::*;
 pub use type_hierarchy::*;
 pub use window::*;
@@ -376,6 +378,77 @@ pub struct AnnotatedTextEdit {
     pub annotation_id: ChangeAnnotationIdentifier,
 }
 
+/// A string value used as a snippet is a template which allows to insert text
+/// and to control the editor cursor when insertion happens.
+///
+/// A snippet can define tab stops and placeholders with `$1`, `$2`
+/// and `${3:foo}`. `$0` defines the final tab stop, it defaults to
+/// the end of the snippet. Variables are defined with `$name` and
+/// `${name:default value}`.
+///
+/// @since 3.18.0
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename = "snippet")]
+pub struct StringValue {
+    /// The snippet string.
+    pub value: String,
+}
+
+impl StringValue {
+    #[must_use]
+    pub const fn new(value: String) -> Self {
+        Self { value }
+    }
+}
+
+/// An interactive text edit.
+///
+/// @since 3.18.0
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetTextEdit {
+    /// The range of the text document to be manipulated.
+    pub range: Range,
+
+    /// The snippet to be inserted.
+    pub snippet: StringValue,
+
+    /// The actual identifier of the snippet edit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub annotation_id: Option<ChangeAnnotationIdentifier>,
+}
+
+/// An edit of a [`TextDocumentEdit`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum TextDocumentEditItem {
+    // The variants with more required properties come first, so that they are not
+    // deserialized as a plain `TextEdit`.
+    /// @since 3.16.0
+    Annotated(AnnotatedTextEdit),
+    /// @since 3.18.0
+    Snippet(SnippetTextEdit),
+    Text(TextEdit),
+}
+
+impl From<TextEdit> for TextDocumentEditItem {
+    fn from(from: TextEdit) -> Self {
+        Self::Text(from)
+    }
+}
+
+impl From<AnnotatedTextEdit> for TextDocumentEditItem {
+    fn from(from: AnnotatedTextEdit) -> Self {
+        Self::Annotated(from)
+    }
+}
+
+impl From<SnippetTextEdit> for TextDocumentEditItem {
+    fn from(from: SnippetTextEdit) -> Self {
+        Self::Snippet(from)
+    }
+}
+
 /// Describes textual changes on a single text document.
 ///
 /// The text document is referred to as a
@@ -395,7 +468,10 @@ pub struct TextDocumentEdit {
     ///
     /// @since 3.16.0 - support for `AnnotatedTextEdit`. This is guarded by the
     /// client capability `workspace.workspaceEdit.changeAnnotationSupport`
-    pub edits: Vec<OneOf<TextEdit, AnnotatedTextEdit>>,
+    ///
+    /// @since 3.18.0 - support for `SnippetTextEdit`. This is guarded by the
+    /// client capability `workspace.workspaceEdit.snippetEditSupport`
+    pub edits: Vec<TextDocumentEditItem>,
 }
 
 /// Additional information that describes document changes.
@@ -928,6 +1004,12 @@ pub struct WorkspaceEditClientCapabilities {
     /// @since 3.16.0
     #[serde(skip_serializing_if = "Option::is_none")]
     pub change_annotation_support: Option<ChangeAnnotationWorkspaceEditClientCapabilities>,
+
+    /// Whether the client supports snippets as text edits.
+    ///
+    /// @since 3.18.0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet_edit_support: Option<bool>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
@@ -1037,6 +1119,18 @@ pub struct WorkspaceClientCapabilities {
     /// since 3.17.0
     #[serde(skip_serializing_if = "Option::is_none")]
     pub diagnostics: Option<DiagnosticWorkspaceClientCapabilities>,
+
+    /// Client workspace capabilities specific to folding ranges.
+    ///
+    /// @since 3.18.0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub folding_range: Option<FoldingRangeWorkspaceClientCapabilities>,
+
+    /// Capabilities specific to the `workspace/textDocumentContent` request.
+    ///
+    /// @since 3.18.0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_document_content: Option<TextDocumentContentClientCapabilities>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -1762,6 +1856,12 @@ pub struct WorkspaceServerCapabilities {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file_operations: Option<WorkspaceFileOperationsServerCapabilities>,
+
+    /// The server supports the `workspace/textDocumentContent` request.
+    ///
+    /// @since 3.18.0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_document_content: Option<TextDocumentContentServerCapabilities>,
 }
 
 /// General parameters to to register for a capability.
@@ -1842,6 +1942,22 @@ pub struct DocumentFormattingOptions {
 pub struct DocumentRangeFormattingOptions {
     #[serde(flatten)]
     pub work_done_progress_options: WorkDoneProgressOptions,
+
+    /// Whether the server supports formatting multiple ranges at once.
+    ///
+    /// @since 3.18.0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ranges_support: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentRangeFormattingRegistrationOptions {
+    #[serde(flatten)]
+    pub text_document_registration_options: TextDocumentRegistrationOptions,
+
+    #[serde(flatten)]
+    pub document_range_formatting_options: DocumentRangeFormattingOptions,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -1869,10 +1985,10 @@ pub struct DocumentSymbolOptions {
 #[serde(rename_all = "camelCase")]
 pub struct DocumentSymbolRegistrationOptions {
     #[serde(flatten)]
-    text_document_registration_options: TextDocumentRegistrationOptions,
+    pub text_document_registration_options: TextDocumentRegistrationOptions,
 
     #[serde(flatten)]
-    document_symbol_options: DocumentSymbolOptions,
+    pub document_symbol_options: DocumentSymbolOptions,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -2451,6 +2567,38 @@ mod tests {
         );
     }
 
+    #[test]
+    #[expect(
+        clippy::literal_string_with_formatting_args,
+        reason = "the literal is a snippet, not a format string"
+    )]
+    fn text_document_edit() {
+        let range = Range::new(Position::new(0, 0), Position::new(0, 1));
+        test_serialization(
+            &TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier {
+                    uri: "file:///a.rs".parse().unwrap(),
+                    version: Some(1),
+                },
+                edits: vec![
+                    TextEdit::new(range, "a".into()).into(),
+                    AnnotatedTextEdit {
+                        text_edit: TextEdit::new(range, "b".into()),
+                        annotation_id: "id".into(),
+                    }
+                    .into(),
+                    SnippetTextEdit {
+                        range,
+                        snippet: StringValue::new("${1:c}".into()),
+                        annotation_id: None,
+                    }
+                    .into(),
+                ],
+            },
+            r#"{"textDocument":{"uri":"file:///a.rs","version":1},"edits":[{"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":1}},"newText":"a"},{"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":1}},"newText":"b","annotationId":"id"},{"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":1}},"snippet":{"kind":"snippet","value":"${1:c}"}}]}"#,
+        );
+    }
+
     #[test]
     fn number_or_string() {
         test_serialization(&NumberOrString::Number(123), r"123");
@@ -2549,6 +2697,15 @@ pub enum NumberOrString {
     String(String),
 }
 
+impl std::fmt::Display for NumberOrString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(number) => write!(f, "{number}"),
+            Self::String(string) => write!(f, "{string:?}"),
+        }
+    }
+}
+
 /// Position in a text document expressed as zero-based line and character offset.
 /// A position is between two characters like an `insert` cursor in a editor.
 #[derive(
@@ -2603,43 +2760,6 @@ impl Location {
     }
 }
 
-/// Represents programming constructs like variables, classes, interfaces etc.
-///
-/// that appear in a document. Document symbols can be hierarchical and they have two ranges:
-/// one that encloses its definition and one that points to its most interesting range,
-/// e.g. the range of an identifier.
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub struct DocumentSymbol {
-    /// The name of this symbol.
-    pub name: String,
-    /// More detail for this symbol, e.g the signature of a function. If not provided the
-    /// name is used.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub detail: Option<String>,
-    /// The kind of this symbol.
-    pub kind: SymbolKind,
-    /// Tags for this completion item.
-    ///
-    /// @since 3.15.0
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub tags: Option<Vec<SymbolTag>>,
-    /// Indicates if this symbol is deprecated.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    #[deprecated(note = "Use tags instead")]
-    pub deprecated: Option<bool>,
-    /// The range enclosing this symbol not including leading/trailing whitespace but everything else
-    /// like comments. This information is typically used to determine if the the clients cursor is
-    /// inside the symbol to reveal in the symbol in the UI.
-    pub range: Range,
-    /// The range that should be selected and revealed when this symbol is being picked, e.g the name of a function.
-    /// Must be contained by the the `range`.
-    pub selection_range: Range,
-    /// Children of this symbol, e.g. properties of a class.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub children: Option<Vec<DocumentSymbol>>,
-}
-
 /// A symbol kind.
 #[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(transparent)]
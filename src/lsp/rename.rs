@@ -1,5 +1,8 @@
 use crate::{
-    lsp::{Range, TextDocumentPositionParams, WorkDoneProgressOptions, WorkDoneProgressParams},
+    lsp::{
+        Range, TextDocumentPositionParams, TextDocumentRegistrationOptions,
+        WorkDoneProgressOptions, WorkDoneProgressParams,
+    },
     macros::lsp_enum,
 };
 use serde::{Deserialize, Serialize};
@@ -31,6 +34,16 @@ pub struct RenameOptions {
     pub work_done_progress_options: WorkDoneProgressOptions,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenameRegistrationOptions {
+    #[serde(flatten)]
+    pub text_document_registration_options: TextDocumentRegistrationOptions,
+
+    #[serde(flatten)]
+    pub rename_options: RenameOptions,
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RenameClientCapabilities {
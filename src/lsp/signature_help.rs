@@ -25,6 +25,14 @@ pub struct SignatureInformationSettings {
     /// @since 3.16.0
     #[serde(skip_serializing_if = "Option::is_none")]
     pub active_parameter_support: Option<bool>,
+
+    /// The client supports the `activeParameter` property on
+    /// `SignatureHelp`/`SignatureInformation` being set to `null` to
+    /// indicate that no parameter should be active.
+    ///
+    /// @since 3.18.0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_active_parameter_support: Option<bool>,
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
@@ -153,8 +161,22 @@ pub struct SignatureHelp {
     pub active_signature: Option<u32>,
 
     /// The active parameter of the active signature.
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub active_parameter: Option<u32>,
+    ///
+    /// If omitted or the value lies outside the range of
+    /// `signatures[activeSignature].parameters` defaults to 0 if the active
+    /// signature has parameters.
+    ///
+    /// `Some(None)` is serialized as `null` and indicates that no parameter is
+    /// active, which requires the client capability
+    /// `signatureHelp.signatureInformation.noActiveParameterSupport`.
+    ///
+    /// @since 3.18.0 - support for `null`
+    #[serde(
+        default,
+        deserialize_with = "deserialize_nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub active_parameter: Option<Option<u32>>,
 }
 
 /// Represents the signature of something callable. A signature
@@ -178,11 +200,35 @@ pub struct SignatureInformation {
 
     /// The index of the active parameter.
     ///
-    /// If provided, this is used in place of `SignatureHelp.activeParameter`.
+    /// If `null`, no parameter of the signature is active (for example a named
+    /// argument that does not match any declared parameters). This is only valid
+    /// if the client specifies the client capability
+    /// `signatureHelp.signatureInformation.noActiveParameterSupport === true`.
+    ///
+    /// If provided (or `null`), this is used in place of
+    /// `SignatureHelp.activeParameter`.
     ///
     /// @since 3.16.0
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub active_parameter: Option<u32>,
+    /// @since 3.18.0 - support for `null`
+    #[serde(
+        default,
+        deserialize_with = "deserialize_nullable",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub active_parameter: Option<Option<u32>>,
+}
+
+/// Deserializes a property that is present, but possibly `null`, into `Some`.
+/// Omitted properties default to `None`.
+#[expect(
+    clippy::option_option,
+    reason = "the property can be omitted or `null`"
+)]
+fn deserialize_nullable<'de, D>(deserializer: D) -> Result<Option<Option<u32>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Option::<u32>::deserialize(deserializer).map(Some)
 }
 
 /// Represents a parameter of a callable-signature. A parameter can
@@ -209,3 +255,28 @@ pub enum ParameterLabel {
     Simple(String),
     LabelOffsets([u32; 2]),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{test_deserialization, test_serialization};
+
+    #[test]
+    fn active_parameter() {
+        let help = |active_parameter| SignatureHelp {
+            signatures: vec![],
+            active_signature: None,
+            active_parameter,
+        };
+        test_serialization(&help(None), r#"{"signatures":[]}"#);
+        test_serialization(
+            &help(Some(None)),
+            r#"{"signatures":[],"activeParameter":null}"#,
+        );
+        test_serialization(
+            &help(Some(Some(1))),
+            r#"{"signatures":[],"activeParameter":1}"#,
+        );
+        test_deserialization(r#"{"signatures":[]}"#, &help(None));
+    }
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Uri,
+    lsp::{DynamicRegistrationClientCapabilities, StaticRegistrationOptions},
+};
+
+/// Client capabilities for a text document content provider.
+///
+/// @since 3.18.0
+pub type TextDocumentContentClientCapabilities = DynamicRegistrationClientCapabilities;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum TextDocumentContentServerCapabilities {
+    Options(TextDocumentContentOptions),
+    RegistrationOptions(TextDocumentContentRegistrationOptions),
+}
+
+/// Text document content provider options.
+///
+/// @since 3.18.0
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextDocumentContentOptions {
+    /// The schemes for which the server provides content.
+    pub schemes: Vec<String>,
+}
+
+/// Text document content provider registration options.
+///
+/// @since 3.18.0
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextDocumentContentRegistrationOptions {
+    #[serde(flatten)]
+    pub text_document_content_options: TextDocumentContentOptions,
+
+    #[serde(flatten)]
+    pub static_registration_options: StaticRegistrationOptions,
+}
+
+/// Parameters for the `workspace/textDocumentContent` request.
+///
+/// @since 3.18.0
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextDocumentContentParams {
+    /// The uri of the text document.
+    pub uri: Uri,
+}
+
+/// Result of the `workspace/textDocumentContent` request.
+///
+/// @since 3.18.0
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextDocumentContentResult {
+    /// The text content of the text document. Please note, that the content of
+    /// any subsequent open notifications for the text document might differ
+    /// from the returned content due to whitespace and line ending
+    /// normalizations done on the client
+    pub text: String,
+}
+
+/// Parameters for the `workspace/textDocumentContent/refresh` request.
+///
+/// @since 3.18.0
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextDocumentContentRefreshParams {
+    /// The uri of the text document to refresh.
+    pub uri: Uri,
+}
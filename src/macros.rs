@@ -97,3 +97,133 @@ macro_rules! lsp_enum {
 }
 
 pub(crate) use lsp_enum;
+
+// Generates an enum with one variant per method of a `lsp_request!`/`lsp_notification!` table
+// that deserializes a `{ "method": .., "params": .. }` pair into the matching variant.
+//
+// ```
+// lsp_dispatch! {
+//   pub enum ClientRequest: lsp_request as Request {
+//     Initialize = "initialize",
+//   }
+// }
+// ```
+macro_rules! lsp_dispatch {
+    (
+        $(#[$meta:meta])*
+        pub enum $typ:ident: $table:ident as $trait:ident {
+            $(
+                $(#[$attr:meta])*
+                $variant:ident = $method:tt,
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq)]
+        #[allow(
+            clippy::large_enum_variant,
+            clippy::derive_partial_eq_without_eq,
+            reason = "the variants depend on the params of each method"
+        )]
+        pub enum $typ {
+            $(
+                $(#[$attr])*
+                $variant(<crate::$table!($method) as $trait>::Params),
+            )*
+            /// A method that is not known to this crate.
+            Unknown {
+                method: String,
+                params: serde_json::Value,
+            },
+        }
+
+        impl $typ {
+            /// The methods of all variants except [`Self::Unknown`].
+            pub const METHODS: &'static [&'static str] = &[$($method,)*];
+
+            /// Parses the params of `method` into the matching variant.
+            ///
+            /// Methods that are not known to this crate are returned as [`Self::Unknown`].
+            ///
+            /// # Errors
+            ///
+            /// Returns an error if the params do not match the params of a known method.
+            pub fn parse(method: &str, params: serde_json::Value) -> Result<Self, serde_json::Error> {
+                match method {
+                    $(
+                        $(#[$attr])*
+                        $method => serde_json::from_value(params).map(Self::$variant),
+                    )*
+                    _ => Ok(Self::Unknown {
+                        method: method.to_owned(),
+                        params,
+                    }),
+                }
+            }
+
+            /// The method of this message.
+            #[must_use]
+            pub fn method(&self) -> &str {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        Self::$variant(_) => $method,
+                    )*
+                    Self::Unknown { method, .. } => method,
+                }
+            }
+
+            /// Serializes the params of this message.
+            ///
+            /// # Errors
+            ///
+            /// Returns an error if the params can not be serialized.
+            pub fn params(&self) -> Result<serde_json::Value, serde_json::Error> {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        Self::$variant(params) => serde_json::to_value(params),
+                    )*
+                    Self::Unknown { params, .. } => Ok(params.clone()),
+                }
+            }
+        }
+
+        impl serde::Serialize for $typ {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: serde::Serializer,
+            {
+                use serde::ser::{Error, SerializeStruct};
+
+                let params = self.params().map_err(S::Error::custom)?;
+                let len = if params.is_null() { 1 } else { 2 };
+                let mut state = serializer.serialize_struct(stringify!($typ), len)?;
+                state.serialize_field("method", self.method())?;
+                if !params.is_null() {
+                    state.serialize_field("params", &params)?;
+                }
+                state.end()
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $typ {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                #[derive(serde::Deserialize)]
+                struct Raw {
+                    method: String,
+                    #[serde(default)]
+                    params: serde_json::Value,
+                }
+
+                let raw = Raw::deserialize(deserializer)?;
+                Self::parse(&raw.method, raw.params).map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+pub(crate) use lsp_dispatch;
@@ -1,6 +1,6 @@
 use serde::{Serialize, de::DeserializeOwned};
 
-use super::lsp;
+use super::{lsp, macros::lsp_dispatch};
 
 pub trait Notification {
     type Params: DeserializeOwned + Serialize + Send + Sync + 'static;
@@ -354,6 +354,52 @@ impl Notification for DidDeleteFiles {
     const METHOD: &'static str = "workspace/didDeleteFiles";
 }
 
+lsp_dispatch! {
+    /// A notification sent from the client to the server, with typed params.
+    pub enum ClientNotification: lsp_notification as Notification {
+        Cancel = "$/cancelRequest",
+        SetTrace = "$/setTrace",
+        Progress = "$/progress",
+        Initialized = "initialized",
+        Exit = "exit",
+
+        WorkDoneProgressCancel = "window/workDoneProgress/cancel",
+
+        DidOpenTextDocument = "textDocument/didOpen",
+        DidChangeTextDocument = "textDocument/didChange",
+        WillSaveTextDocument = "textDocument/willSave",
+        DidSaveTextDocument = "textDocument/didSave",
+        DidCloseTextDocument = "textDocument/didClose",
+
+        DidOpenNotebookDocument = "notebookDocument/didOpen",
+        DidChangeNotebookDocument = "notebookDocument/didChange",
+        DidSaveNotebookDocument = "notebookDocument/didSave",
+        DidCloseNotebookDocument = "notebookDocument/didClose",
+
+        DidChangeConfiguration = "workspace/didChangeConfiguration",
+        DidChangeWatchedFiles = "workspace/didChangeWatchedFiles",
+        DidChangeWorkspaceFolders = "workspace/didChangeWorkspaceFolders",
+        DidCreateFiles = "workspace/didCreateFiles",
+        DidRenameFiles = "workspace/didRenameFiles",
+        DidDeleteFiles = "workspace/didDeleteFiles",
+    }
+}
+
+lsp_dispatch! {
+    /// A notification sent from the server to the client, with typed params.
+    pub enum ServerNotification: lsp_notification as Notification {
+        Cancel = "$/cancelRequest",
+        Progress = "$/progress",
+        LogTrace = "$/logTrace",
+
+        ShowMessage = "window/showMessage",
+        LogMessage = "window/logMessage",
+        TelemetryEvent = "telemetry/event",
+
+        PublishDiagnostics = "textDocument/publishDiagnostics",
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -371,6 +417,13 @@ mod test {
             assert_eq!(<lsp_notification!($name) as Notification>::METHOD, $name);
             // test whether type checking passes for each component
             fake_call::<lsp_notification!($name)>();
+            // check whether the method is dispatched in at least one direction
+            assert!(
+                ClientNotification::METHODS.contains(&$name)
+                    || ServerNotification::METHODS.contains(&$name),
+                "{} is not dispatched",
+                $name,
+            );
         };
     }
 
@@ -403,4 +456,27 @@ mod test {
     #[test]
     #[cfg(feature = "proposed")]
     fn check_proposed_macro_definitions() {}
+
+    #[test]
+    fn dispatch() {
+        assert_eq!(
+            serde_json::from_str::<ClientNotification>(r#"{"jsonrpc":"2.0","method":"exit"}"#)
+                .unwrap(),
+            ClientNotification::Exit(())
+        );
+        assert_eq!(
+            serde_json::from_str::<ServerNotification>(
+                r#"{"method":"window/logMessage","params":{"type":3,"message":"hi"}}"#
+            )
+            .unwrap(),
+            ServerNotification::LogMessage(lsp::LogMessageParams {
+                typ: lsp::MessageType::INFO,
+                message: "hi".into(),
+            })
+        );
+        assert!(
+            serde_json::from_str::<ServerNotification>(r#"{"method":"window/logMessage"}"#)
+                .is_err()
+        );
+    }
 }
@@ -0,0 +1,608 @@
+//! Bookkeeping of the capabilities a server registers dynamically.
+//!
+//! The `registerOptions` of a [`Registration`] are untyped JSON whose shape depends on the
+//! `method`. [`RegistrationOptions`] parses them into the options type of the method, and a
+//! [`Registry`] tracks the active registrations of a session by their id so that the client can
+//! process `client/unregisterCapability` requests and look up what is registered for a method.
+
+use std::fmt;
+
+use serde_json::Value;
+
+#[cfg(feature = "proposed")]
+use crate::lsp::InlineCompletionRegistrationOptions;
+use crate::lsp::{
+    CodeActionRegistrationOptions, CodeLensRegistrationOptions, CompletionRegistrationOptions,
+    DeclarationRegistrationOptions, DiagnosticRegistrationOptions,
+    DidChangeWatchedFilesRegistrationOptions, DocumentLinkRegistrationOptions,
+    DocumentOnTypeFormattingRegistrationOptions, DocumentRangeFormattingRegistrationOptions,
+    DocumentSelector, DocumentSymbolRegistrationOptions, ExecuteCommandRegistrationOptions,
+    FileOperationRegistrationOptions, GenericRegistrationOptions, HoverRegistrationOptions,
+    InlayHintRegistrationOptions, InlineValueRegistrationOptions,
+    LinkedEditingRangeRegistrationOptions, MonikerRegistrationOptions,
+    NotebookDocumentSyncRegistrationOptions, Registration, RegistrationParams,
+    RenameRegistrationOptions, SelectionRangeRegistrationOptions,
+    SemanticTokensRegistrationOptions, SignatureHelpRegistrationOptions,
+    TextDocumentChangeRegistrationOptions, TextDocumentContentRegistrationOptions,
+    TextDocumentRegistrationOptions, TextDocumentSaveRegistrationOptions,
+    TypeHierarchyRegistrationOptions, Unregistration, UnregistrationParams,
+};
+
+macro_rules! registration_options {
+    (
+        $(#[$meta:meta])*
+        pub enum $typ:ident {
+            $(
+                $(#[$attr:meta])*
+                $variant:ident($options:ty) = $method:literal,
+            )*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq)]
+        #[allow(
+            clippy::large_enum_variant,
+            clippy::derive_partial_eq_without_eq,
+            reason = "the variants depend on the options of each method"
+        )]
+        pub enum $typ {
+            $(
+                $(#[$attr])*
+                $variant($options),
+            )*
+            /// A method that is not known to this crate, with its untyped options.
+            Unknown {
+                method: String,
+                options: Option<Value>,
+                /// The `documentSelector` of the options, if they have one that parses.
+                document_selector: Option<DocumentSelector>,
+            },
+        }
+
+        impl $typ {
+            /// The methods of all variants except [`Self::Unknown`].
+            pub const METHODS: &'static [&'static str] = &[$($(#[$attr])* $method,)*];
+
+            /// Parses the registration options of `method` into the matching variant.
+            ///
+            /// Omitted options are parsed like an empty object. Methods that are not known to
+            /// this crate are returned as [`Self::Unknown`].
+            ///
+            /// # Errors
+            ///
+            /// Returns an error if the options do not match the options of a known method.
+            pub fn parse(method: &str, options: Option<Value>) -> Result<Self, serde_json::Error> {
+                match method {
+                    $(
+                        $(#[$attr])*
+                        $method => serde_json::from_value(
+                            options.unwrap_or_else(|| Value::Object(serde_json::Map::new())),
+                        )
+                        .map(Self::$variant),
+                    )*
+                    _ => Ok(Self::Unknown {
+                        method: method.to_owned(),
+                        document_selector: options
+                            .as_ref()
+                            .and_then(|options| options.get("documentSelector"))
+                            .and_then(|selector| {
+                                serde_json::from_value(selector.clone()).ok()
+                            }),
+                        options,
+                    }),
+                }
+            }
+
+            /// The method the options are registered for.
+            #[must_use]
+            pub fn method(&self) -> &str {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        Self::$variant(_) => $method,
+                    )*
+                    Self::Unknown { method, .. } => method,
+                }
+            }
+
+            /// Serializes the options as the `registerOptions` of a [`Registration`].
+            ///
+            /// # Errors
+            ///
+            /// Returns an error if the options can not be serialized.
+            pub fn to_value(&self) -> Result<Option<Value>, serde_json::Error> {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        Self::$variant(options) => serde_json::to_value(options).map(Some),
+                    )*
+                    Self::Unknown { options, .. } => Ok(options.clone()),
+                }
+            }
+        }
+    };
+}
+
+registration_options! {
+    /// The typed `registerOptions` of a [`Registration`].
+    pub enum RegistrationOptions {
+        DidOpenTextDocument(TextDocumentRegistrationOptions) = "textDocument/didOpen",
+        DidChangeTextDocument(TextDocumentChangeRegistrationOptions) = "textDocument/didChange",
+        WillSaveTextDocument(TextDocumentRegistrationOptions) = "textDocument/willSave",
+        WillSaveWaitUntil(TextDocumentRegistrationOptions) = "textDocument/willSaveWaitUntil",
+        DidSaveTextDocument(TextDocumentSaveRegistrationOptions) = "textDocument/didSave",
+        DidCloseTextDocument(TextDocumentRegistrationOptions) = "textDocument/didClose",
+        NotebookDocumentSync(NotebookDocumentSyncRegistrationOptions) = "notebookDocument/sync",
+
+        DidChangeWatchedFiles(DidChangeWatchedFilesRegistrationOptions) = "workspace/didChangeWatchedFiles",
+        ExecuteCommand(ExecuteCommandRegistrationOptions) = "workspace/executeCommand",
+        WillCreateFiles(FileOperationRegistrationOptions) = "workspace/willCreateFiles",
+        DidCreateFiles(FileOperationRegistrationOptions) = "workspace/didCreateFiles",
+        WillRenameFiles(FileOperationRegistrationOptions) = "workspace/willRenameFiles",
+        DidRenameFiles(FileOperationRegistrationOptions) = "workspace/didRenameFiles",
+        WillDeleteFiles(FileOperationRegistrationOptions) = "workspace/willDeleteFiles",
+        DidDeleteFiles(FileOperationRegistrationOptions) = "workspace/didDeleteFiles",
+        TextDocumentContent(TextDocumentContentRegistrationOptions) = "workspace/textDocumentContent",
+
+        Completion(CompletionRegistrationOptions) = "textDocument/completion",
+        Hover(HoverRegistrationOptions) = "textDocument/hover",
+        SignatureHelp(SignatureHelpRegistrationOptions) = "textDocument/signatureHelp",
+        Declaration(DeclarationRegistrationOptions) = "textDocument/declaration",
+        Definition(GenericRegistrationOptions) = "textDocument/definition",
+        TypeDefinition(GenericRegistrationOptions) = "textDocument/typeDefinition",
+        Implementation(GenericRegistrationOptions) = "textDocument/implementation",
+        References(GenericRegistrationOptions) = "textDocument/references",
+        DocumentHighlight(GenericRegistrationOptions) = "textDocument/documentHighlight",
+        DocumentSymbol(DocumentSymbolRegistrationOptions) = "textDocument/documentSymbol",
+        CodeAction(CodeActionRegistrationOptions) = "textDocument/codeAction",
+        CodeLens(CodeLensRegistrationOptions) = "textDocument/codeLens",
+        DocumentLink(DocumentLinkRegistrationOptions) = "textDocument/documentLink",
+        DocumentColor(GenericRegistrationOptions) = "textDocument/documentColor",
+        Formatting(GenericRegistrationOptions) = "textDocument/formatting",
+        RangeFormatting(DocumentRangeFormattingRegistrationOptions) = "textDocument/rangeFormatting",
+        RangesFormatting(DocumentRangeFormattingRegistrationOptions) = "textDocument/rangesFormatting",
+        OnTypeFormatting(DocumentOnTypeFormattingRegistrationOptions) = "textDocument/onTypeFormatting",
+        Rename(RenameRegistrationOptions) = "textDocument/rename",
+        FoldingRange(GenericRegistrationOptions) = "textDocument/foldingRange",
+        SelectionRange(SelectionRangeRegistrationOptions) = "textDocument/selectionRange",
+        LinkedEditingRange(LinkedEditingRangeRegistrationOptions) = "textDocument/linkedEditingRange",
+        CallHierarchy(GenericRegistrationOptions) = "textDocument/prepareCallHierarchy",
+        SemanticTokens(SemanticTokensRegistrationOptions) = "textDocument/semanticTokens",
+        Moniker(MonikerRegistrationOptions) = "textDocument/moniker",
+        TypeHierarchy(TypeHierarchyRegistrationOptions) = "textDocument/prepareTypeHierarchy",
+        InlineValue(InlineValueRegistrationOptions) = "textDocument/inlineValue",
+        InlayHint(InlayHintRegistrationOptions) = "textDocument/inlayHint",
+        Diagnostic(DiagnosticRegistrationOptions) = "textDocument/diagnostic",
+        #[cfg(feature = "proposed")]
+        InlineCompletion(InlineCompletionRegistrationOptions) = "textDocument/inlineCompletion",
+    }
+}
+
+impl RegistrationOptions {
+    /// The document selector of options that are registered for text documents.
+    ///
+    /// Returns `None` if the options are not registered for text documents or if the selector
+    /// is `null`, in which case the document selector of the client applies.
+    #[must_use]
+    pub const fn document_selector(&self) -> Option<&DocumentSelector> {
+        let selector = match self {
+            Self::DidOpenTextDocument(options)
+            | Self::WillSaveTextDocument(options)
+            | Self::WillSaveWaitUntil(options)
+            | Self::DidCloseTextDocument(options) => &options.document_selector,
+            Self::DidChangeTextDocument(options) => &options.document_selector,
+            Self::DidSaveTextDocument(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            Self::Completion(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            Self::Hover(options) => &options.text_document_registration_options.document_selector,
+            Self::SignatureHelp(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            Self::Declaration(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            Self::Definition(options)
+            | Self::TypeDefinition(options)
+            | Self::Implementation(options)
+            | Self::References(options)
+            | Self::DocumentHighlight(options)
+            | Self::DocumentColor(options)
+            | Self::Formatting(options)
+            | Self::FoldingRange(options)
+            | Self::CallHierarchy(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            Self::DocumentSymbol(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            Self::CodeAction(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            Self::CodeLens(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            Self::DocumentLink(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            Self::RangeFormatting(options) | Self::RangesFormatting(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            Self::OnTypeFormatting(options) => &options.document_selector,
+            Self::Rename(options) => &options.text_document_registration_options.document_selector,
+            Self::SelectionRange(options) => &options.registration_options.document_selector,
+            Self::LinkedEditingRange(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            Self::SemanticTokens(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            Self::Moniker(options) => &options.text_document_registration_options.document_selector,
+            Self::TypeHierarchy(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            Self::InlineValue(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            Self::InlayHint(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            Self::Diagnostic(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            #[cfg(feature = "proposed")]
+            Self::InlineCompletion(options) => {
+                &options.text_document_registration_options.document_selector
+            }
+            Self::NotebookDocumentSync(_)
+            | Self::DidChangeWatchedFiles(_)
+            | Self::ExecuteCommand(_)
+            | Self::WillCreateFiles(_)
+            | Self::DidCreateFiles(_)
+            | Self::WillRenameFiles(_)
+            | Self::DidRenameFiles(_)
+            | Self::WillDeleteFiles(_)
+            | Self::DidDeleteFiles(_)
+            | Self::TextDocumentContent(_) => &None,
+            Self::Unknown {
+                document_selector, ..
+            } => document_selector,
+        };
+        selector.as_ref()
+    }
+}
+
+/// An error returned by [`Registry::register`].
+#[derive(Debug)]
+pub enum RegistryError {
+    /// A registration with the same id is already active.
+    DuplicateId(String),
+    /// The options do not match the options of the method.
+    InvalidOptions {
+        method: String,
+        error: serde_json::Error,
+    },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateId(id) => write!(f, "a registration with id `{id}` already exists"),
+            Self::InvalidOptions { method, error } => {
+                write!(f, "invalid registration options for `{method}`: {error}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::DuplicateId(_) => None,
+            Self::InvalidOptions { error, .. } => Some(error),
+        }
+    }
+}
+
+/// The active dynamic registrations of a session, in the order they were registered.
+#[derive(Debug, Default, Clone)]
+pub struct Registry {
+    registrations: Vec<(String, RegistrationOptions)>,
+}
+
+impl Registry {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            registrations: Vec::new(),
+        }
+    }
+
+    /// Adds a registration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a registration with the same id is already active or if the options
+    /// do not match the method. The registry is unchanged in that case.
+    pub fn register(&mut self, registration: Registration) -> Result<(), RegistryError> {
+        if self.get(&registration.id).is_some() {
+            return Err(RegistryError::DuplicateId(registration.id));
+        }
+        let options =
+            RegistrationOptions::parse(&registration.method, registration.register_options)
+                .map_err(|error| RegistryError::InvalidOptions {
+                    method: registration.method,
+                    error,
+                })?;
+        self.registrations.push((registration.id, options));
+        Ok(())
+    }
+
+    /// Adds the registrations of a `client/registerCapability` request.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of [`Self::register`]. The registrations before it are kept.
+    pub fn register_all(&mut self, params: RegistrationParams) -> Result<(), RegistryError> {
+        params
+            .registrations
+            .into_iter()
+            .try_for_each(|registration| self.register(registration))
+    }
+
+    /// Removes a registration and returns its options, or `None` if no registration with the
+    /// id and method is active.
+    pub fn unregister(&mut self, unregistration: &Unregistration) -> Option<RegistrationOptions> {
+        let index = self.registrations.iter().position(|(id, options)| {
+            *id == unregistration.id && options.method() == unregistration.method
+        })?;
+        Some(self.registrations.remove(index).1)
+    }
+
+    /// Removes the registrations of a `client/unregisterCapability` request.
+    pub fn unregister_all(&mut self, params: &UnregistrationParams) {
+        for unregistration in &params.unregisterations {
+            self.unregister(unregistration);
+        }
+    }
+
+    /// The options of the registration with the id.
+    #[must_use]
+    pub fn get(&self, id: &str) -> Option<&RegistrationOptions> {
+        self.registrations
+            .iter()
+            .find(|(registration, _)| registration == id)
+            .map(|(_, options)| options)
+    }
+
+    /// The ids and options of the registrations of `method`.
+    pub fn registrations<'a>(
+        &'a self,
+        method: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a RegistrationOptions)> {
+        self.registrations
+            .iter()
+            .filter(move |(_, options)| options.method() == method)
+            .map(|(id, options)| (id.as_str(), options))
+    }
+
+    /// Whether `method` has an active registration.
+    #[must_use]
+    pub fn is_registered(&self, method: &str) -> bool {
+        self.registrations(method).next().is_some()
+    }
+
+    /// The document selectors `method` is registered for.
+    ///
+    /// A `None` item is a registration without a selector, which applies to the documents of
+    /// the document selector of the client.
+    pub fn document_selectors<'a>(
+        &'a self,
+        method: &'a str,
+    ) -> impl Iterator<Item = Option<&'a DocumentSelector>> {
+        self.registrations(method)
+            .map(|(_, options)| options.document_selector())
+    }
+
+    /// Iterates over the ids and options of all active registrations.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &RegistrationOptions)> {
+        self.registrations
+            .iter()
+            .map(|(id, options)| (id.as_str(), options))
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.registrations.len()
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.registrations.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::lsp::{CodeActionKind, DocumentFilter, TextDocumentSyncKind};
+
+    fn registration(id: &str, method: &str, options: Option<Value>) -> Registration {
+        Registration {
+            id: id.to_owned(),
+            method: method.to_owned(),
+            register_options: options,
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let options = RegistrationOptions::parse(
+            "textDocument/didChange",
+            Some(json!({ "documentSelector": null, "syncKind": 2 })),
+        )
+        .unwrap();
+        assert_eq!(
+            options,
+            RegistrationOptions::DidChangeTextDocument(TextDocumentChangeRegistrationOptions {
+                document_selector: None,
+                sync_kind: TextDocumentSyncKind::INCREMENTAL,
+            })
+        );
+        assert_eq!(options.method(), "textDocument/didChange");
+        assert_eq!(
+            options.to_value().unwrap(),
+            Some(json!({ "documentSelector": null, "syncKind": 2 }))
+        );
+
+        let watched = RegistrationOptions::parse(
+            "workspace/didChangeWatchedFiles",
+            Some(json!({ "watchers": [{ "globPattern": "**/*.rs" }] })),
+        )
+        .unwrap();
+        assert!(matches!(
+            watched,
+            RegistrationOptions::DidChangeWatchedFiles(options) if options.watchers.len() == 1
+        ));
+
+        let options = RegistrationOptions::parse("textDocument/hover", None).unwrap();
+        assert!(matches!(options, RegistrationOptions::Hover(_)));
+        assert!(RegistrationOptions::parse("textDocument/definition", None).is_ok());
+        assert_eq!(options.document_selector(), None);
+
+        let unknown = RegistrationOptions::parse("custom/method", Some(json!(1))).unwrap();
+        assert_eq!(unknown.method(), "custom/method");
+        assert_eq!(unknown.to_value().unwrap(), Some(json!(1)));
+        assert_eq!(unknown.document_selector(), None);
+
+        let selector = json!({ "documentSelector": [{ "language": "rust" }] });
+        let rust = vec![DocumentFilter {
+            language: Some("rust".to_owned()),
+            scheme: None,
+            pattern: None,
+        }];
+        let unknown = RegistrationOptions::parse("custom/method", Some(selector.clone())).unwrap();
+        assert_eq!(unknown.document_selector(), Some(&rust));
+        assert_eq!(unknown.to_value().unwrap(), Some(selector));
+
+        let code_action = RegistrationOptions::parse(
+            "textDocument/codeAction",
+            Some(json!({
+                "documentSelector": [{ "language": "rust" }],
+                "codeActionKinds": ["quickfix"]
+            })),
+        )
+        .unwrap();
+        assert!(matches!(
+            &code_action,
+            RegistrationOptions::CodeAction(options)
+                if options.code_action_options.code_action_kinds
+                    == Some(vec![CodeActionKind::QUICKFIX])
+        ));
+        assert_eq!(code_action.document_selector(), Some(&rust));
+        for method in [
+            "textDocument/rename",
+            "textDocument/documentLink",
+            "textDocument/rangeFormatting",
+            "textDocument/rangesFormatting",
+        ] {
+            let options = RegistrationOptions::parse(
+                method,
+                Some(json!({ "documentSelector": [{ "language": "rust" }] })),
+            )
+            .unwrap();
+            assert!(!matches!(options, RegistrationOptions::Unknown { .. }));
+            assert_eq!(options.document_selector(), Some(&rust));
+        }
+
+        assert!(RegistrationOptions::parse("textDocument/didChange", None).is_err());
+        assert!(
+            RegistrationOptions::METHODS
+                .iter()
+                .all(|method| !method.is_empty())
+        );
+    }
+
+    #[test]
+    fn registry() {
+        let mut registry = Registry::new();
+        registry
+            .register_all(RegistrationParams {
+                registrations: vec![
+                    registration(
+                        "1",
+                        "textDocument/hover",
+                        Some(json!({ "documentSelector": [{ "language": "rust" }] })),
+                    ),
+                    registration(
+                        "2",
+                        "textDocument/hover",
+                        Some(json!({ "documentSelector": null })),
+                    ),
+                    registration(
+                        "3",
+                        "textDocument/completion",
+                        Some(json!({
+                            "documentSelector": [{ "scheme": "file" }],
+                            "triggerCharacters": ["."]
+                        })),
+                    ),
+                ],
+            })
+            .unwrap();
+        assert_eq!(registry.len(), 3);
+        assert!(matches!(
+            registry.register(registration("1", "textDocument/hover", None)),
+            Err(RegistryError::DuplicateId(id)) if id == "1"
+        ));
+        assert!(matches!(
+            registry.register(registration("4", "textDocument/didChange", None)),
+            Err(RegistryError::InvalidOptions { .. })
+        ));
+        assert_eq!(registry.len(), 3);
+
+        let rust = vec![DocumentFilter {
+            language: Some("rust".to_owned()),
+            scheme: None,
+            pattern: None,
+        }];
+        assert_eq!(
+            registry
+                .document_selectors("textDocument/hover")
+                .collect::<Vec<_>>(),
+            vec![Some(&rust), None]
+        );
+        assert!(registry.is_registered("textDocument/completion"));
+        assert!(!registry.is_registered("textDocument/definition"));
+
+        // The method has to match the method of the registration.
+        let unregistration = Unregistration {
+            id: "1".to_owned(),
+            method: "textDocument/completion".to_owned(),
+        };
+        assert_eq!(registry.unregister(&unregistration), None);
+        registry.unregister_all(&UnregistrationParams {
+            unregisterations: vec![
+                Unregistration {
+                    id: "1".to_owned(),
+                    method: "textDocument/hover".to_owned(),
+                },
+                Unregistration {
+                    id: "3".to_owned(),
+                    method: "textDocument/completion".to_owned(),
+                },
+            ],
+        });
+        assert_eq!(registry.iter().map(|(id, _)| id).collect::<Vec<_>>(), ["2"]);
+        assert_eq!(
+            registry
+                .document_selectors("textDocument/hover")
+                .collect::<Vec<_>>(),
+            vec![None]
+        );
+    }
+}
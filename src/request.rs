@@ -1,6 +1,6 @@
 use serde::{Serialize, de::DeserializeOwned};
 
-use crate::lsp;
+use crate::{lsp, macros::lsp_dispatch};
 
 pub trait Request {
     type Params: DeserializeOwned + Serialize + Send + Sync + 'static;
@@ -90,6 +90,9 @@ macro_rules! lsp_request {
     ("textDocument/rangeFormatting") => {
         $crate::request::RangeFormatting
     };
+    ("textDocument/rangesFormatting") => {
+        $crate::request::RangesFormatting
+    };
     ("textDocument/onTypeFormatting") => {
         $crate::request::OnTypeFormatting
     };
@@ -198,6 +201,15 @@ macro_rules! lsp_request {
     ("workspace/inlineValue/refresh") => {
         $crate::request::InlineValueRefreshRequest
     };
+    ("workspace/foldingRange/refresh") => {
+        $crate::request::FoldingRangeRefresh
+    };
+    ("workspace/textDocumentContent") => {
+        $crate::request::TextDocumentContentRequest
+    };
+    ("workspace/textDocumentContent/refresh") => {
+        $crate::request::TextDocumentContentRefresh
+    };
     ("codeAction/resolve") => {
         $crate::request::CodeActionResolveRequest
     };
@@ -585,6 +597,19 @@ impl Request for RangeFormatting {
     const METHOD: &'static str = "textDocument/rangeFormatting";
 }
 
+/// The document ranges formatting request is sent from the client to the server to format
+/// multiple ranges in a document at once.
+///
+/// @since 3.18.0
+#[derive(Debug)]
+pub enum RangesFormatting {}
+
+impl Request for RangesFormatting {
+    type Params = lsp::DocumentRangesFormattingParams;
+    type Result = Option<Vec<lsp::TextEdit>>;
+    const METHOD: &'static str = "textDocument/rangesFormatting";
+}
+
 /// The document on type formatting request is sent from the client to the server to format parts of
 /// the document during typing.
 #[derive(Debug)]
@@ -652,6 +677,21 @@ impl Request for FoldingRangeRequest {
     const METHOD: &'static str = "textDocument/foldingRange";
 }
 
+/// The `workspace/foldingRange/refresh` request is sent from the server to the client.
+///
+/// Servers can use it to ask clients to refresh the folding ranges currently shown in editors. As
+/// a result the client should ask the server to recompute the folding ranges for these editors.
+///
+/// @since 3.18.0
+#[derive(Debug)]
+pub enum FoldingRangeRefresh {}
+
+impl Request for FoldingRangeRefresh {
+    type Params = ();
+    type Result = ();
+    const METHOD: &'static str = "workspace/foldingRange/refresh";
+}
+
 /// The prepare rename request is sent from the client to the server to setup and test the validity of a rename operation
 /// at a given location.
 #[derive(Debug)]
@@ -939,6 +979,32 @@ impl Request for WorkspaceDiagnosticRefresh {
     const METHOD: &'static str = "workspace/diagnostic/refresh";
 }
 
+/// The `workspace/textDocumentContent` request is sent from the client to the server to request
+/// the content of a text document whose uri uses one of the schemes registered by the server.
+///
+/// @since 3.18.0
+#[derive(Debug)]
+pub enum TextDocumentContentRequest {}
+
+impl Request for TextDocumentContentRequest {
+    type Params = lsp::TextDocumentContentParams;
+    type Result = lsp::TextDocumentContentResult;
+    const METHOD: &'static str = "workspace/textDocumentContent";
+}
+
+/// The `workspace/textDocumentContent/refresh` request is sent from the server to the client to
+/// refresh the content of a specific text document.
+///
+/// @since 3.18.0
+#[derive(Debug)]
+pub enum TextDocumentContentRefresh {}
+
+impl Request for TextDocumentContentRefresh {
+    type Params = lsp::TextDocumentContentRefreshParams;
+    type Result = ();
+    const METHOD: &'static str = "workspace/textDocumentContent/refresh";
+}
+
 /// The type hierarchy request is sent from the client to the server to return a type hierarchy for
 /// the language element of given text document positions. Will return null if the server couldn’t
 /// infer a valid type from the position. The type hierarchy requests are executed in two steps:
@@ -978,6 +1044,90 @@ impl Request for TypeHierarchySubtypes {
     const METHOD: &'static str = "typeHierarchy/subtypes";
 }
 
+lsp_dispatch! {
+    /// A request sent from the client to the server, with typed params.
+    pub enum ClientRequest: lsp_request as Request {
+        Initialize = "initialize",
+        Shutdown = "shutdown",
+
+        WorkspaceSymbolRequest = "workspace/symbol",
+        WorkspaceSymbolResolve = "workspaceSymbol/resolve",
+        ExecuteCommand = "workspace/executeCommand",
+        WillCreateFiles = "workspace/willCreateFiles",
+        WillRenameFiles = "workspace/willRenameFiles",
+        WillDeleteFiles = "workspace/willDeleteFiles",
+        WorkspaceDiagnosticRequest = "workspace/diagnostic",
+        TextDocumentContentRequest = "workspace/textDocumentContent",
+
+        WillSaveWaitUntil = "textDocument/willSaveWaitUntil",
+        Completion = "textDocument/completion",
+        ResolveCompletionItem = "completionItem/resolve",
+        HoverRequest = "textDocument/hover",
+        SignatureHelpRequest = "textDocument/signatureHelp",
+        GotoDeclaration = "textDocument/declaration",
+        GotoDefinition = "textDocument/definition",
+        References = "textDocument/references",
+        DocumentHighlightRequest = "textDocument/documentHighlight",
+        DocumentSymbolRequest = "textDocument/documentSymbol",
+        CodeActionRequest = "textDocument/codeAction",
+        CodeActionResolveRequest = "codeAction/resolve",
+        CodeLensRequest = "textDocument/codeLens",
+        CodeLensResolve = "codeLens/resolve",
+        DocumentLinkRequest = "textDocument/documentLink",
+        DocumentLinkResolve = "documentLink/resolve",
+        RangeFormatting = "textDocument/rangeFormatting",
+        RangesFormatting = "textDocument/rangesFormatting",
+        OnTypeFormatting = "textDocument/onTypeFormatting",
+        Formatting = "textDocument/formatting",
+        Rename = "textDocument/rename",
+        PrepareRenameRequest = "textDocument/prepareRename",
+        DocumentColor = "textDocument/documentColor",
+        ColorPresentationRequest = "textDocument/colorPresentation",
+        FoldingRangeRequest = "textDocument/foldingRange",
+        GotoImplementation = "textDocument/implementation",
+        GotoTypeDefinition = "textDocument/typeDefinition",
+        SelectionRangeRequest = "textDocument/selectionRange",
+        CallHierarchyPrepare = "textDocument/prepareCallHierarchy",
+        CallHierarchyIncomingCalls = "callHierarchy/incomingCalls",
+        CallHierarchyOutgoingCalls = "callHierarchy/outgoingCalls",
+        TypeHierarchyPrepare = "textDocument/prepareTypeHierarchy",
+        TypeHierarchySupertypes = "typeHierarchy/supertypes",
+        TypeHierarchySubtypes = "typeHierarchy/subtypes",
+        MonikerRequest = "textDocument/moniker",
+        LinkedEditingRange = "textDocument/linkedEditingRange",
+        SemanticTokensFullRequest = "textDocument/semanticTokens/full",
+        SemanticTokensFullDeltaRequest = "textDocument/semanticTokens/full/delta",
+        SemanticTokensRangeRequest = "textDocument/semanticTokens/range",
+        InlayHintRequest = "textDocument/inlayHint",
+        InlayHintResolveRequest = "inlayHint/resolve",
+        InlineValueRequest = "textDocument/inlineValue",
+        DocumentDiagnosticRequest = "textDocument/diagnostic",
+    }
+}
+
+lsp_dispatch! {
+    /// A request sent from the server to the client, with typed params.
+    pub enum ServerRequest: lsp_request as Request {
+        ShowMessageRequest = "window/showMessageRequest",
+        ShowDocument = "window/showDocument",
+        WorkDoneProgressCreate = "window/workDoneProgress/create",
+
+        RegisterCapability = "client/registerCapability",
+        UnregisterCapability = "client/unregisterCapability",
+
+        ApplyWorkspaceEdit = "workspace/applyEdit",
+        WorkspaceFoldersRequest = "workspace/workspaceFolders",
+        WorkspaceConfiguration = "workspace/configuration",
+        SemanticTokensRefresh = "workspace/semanticTokens/refresh",
+        CodeLensRefresh = "workspace/codeLens/refresh",
+        InlayHintRefreshRequest = "workspace/inlayHint/refresh",
+        InlineValueRefreshRequest = "workspace/inlineValue/refresh",
+        WorkspaceDiagnosticRefresh = "workspace/diagnostic/refresh",
+        FoldingRangeRefresh = "workspace/foldingRange/refresh",
+        TextDocumentContentRefresh = "workspace/textDocumentContent/refresh",
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -996,6 +1146,12 @@ mod test {
             assert_eq!(<lsp_request!($name) as Request>::METHOD, $name);
             // test whether type checking passes for each component
             fake_call::<lsp_request!($name)>();
+            // check whether the method is dispatched in exactly one direction
+            assert!(
+                ClientRequest::METHODS.contains(&$name) != ServerRequest::METHODS.contains(&$name),
+                "{} is not dispatched in exactly one direction",
+                $name,
+            );
         };
     }
 
@@ -1024,6 +1180,7 @@ mod test {
         check_macro!("textDocument/codeLens");
         check_macro!("textDocument/documentLink");
         check_macro!("textDocument/rangeFormatting");
+        check_macro!("textDocument/rangesFormatting");
         check_macro!("textDocument/onTypeFormatting");
         check_macro!("textDocument/formatting");
         check_macro!("textDocument/rename");
@@ -1059,6 +1216,9 @@ mod test {
         check_macro!("workspace/codeLens/refresh");
         check_macro!("workspace/inlayHint/refresh");
         check_macro!("workspace/inlineValue/refresh");
+        check_macro!("workspace/foldingRange/refresh");
+        check_macro!("workspace/textDocumentContent");
+        check_macro!("workspace/textDocumentContent/refresh");
 
         check_macro!("callHierarchy/incomingCalls");
         check_macro!("callHierarchy/outgoingCalls");
@@ -1075,4 +1235,42 @@ mod test {
     #[test]
     #[cfg(feature = "proposed")]
     fn check_proposed_macro_definitions() {}
+
+    #[test]
+    fn dispatch() {
+        let request: ClientRequest = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","id":1,"method":"textDocument/hover","params":{"textDocument":{"uri":"file:///a.rs"},"position":{"line":1,"character":2}}}"#,
+        )
+        .unwrap();
+        let ClientRequest::HoverRequest(params) = &request else {
+            panic!("unexpected request {request:?}");
+        };
+        assert_eq!(
+            params.text_document_position_params.position,
+            lsp::Position::new(1, 2)
+        );
+        assert_eq!(request.method(), "textDocument/hover");
+
+        assert_eq!(
+            ClientRequest::parse("shutdown", serde_json::Value::Null).unwrap(),
+            ClientRequest::Shutdown(())
+        );
+        assert_eq!(
+            serde_json::to_string(&ClientRequest::Shutdown(())).unwrap(),
+            r#"{"method":"shutdown"}"#
+        );
+
+        assert_eq!(
+            ClientRequest::parse("custom/method", serde_json::json!([1])).unwrap(),
+            ClientRequest::Unknown {
+                method: "custom/method".into(),
+                params: serde_json::json!([1]),
+            }
+        );
+        assert!(matches!(
+            ClientRequest::parse("workspace/applyEdit", serde_json::json!({})).unwrap(),
+            ClientRequest::Unknown { .. }
+        ));
+        assert!(ServerRequest::parse("workspace/applyEdit", serde_json::json!({})).is_err());
+    }
 }
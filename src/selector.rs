@@ -0,0 +1,331 @@
+//! Matching of documents against [`DocumentSelector`]s and of notebooks and their cells
+//! against notebook filters and selectors.
+//!
+//! Like `languages.match` of VS Code, matching returns a score rather than a boolean so that
+//! the best of several selectors can be chosen:
+//! - `10` if a property of the filter matches exactly, for a pattern if it is the path of the
+//!   document,
+//! - `5` if the properties of the filter only match because of a `*` wildcard or a glob
+//!   pattern,
+//! - `0` if a property of the filter does not match or the filter has no properties at all.
+//!
+//! A selector scores the maximum of its filters.
+//!
+//! Glob patterns are compiled on every call, an invalid pattern matches nothing.
+//!
+//! [`DocumentSelector`]: crate::lsp::DocumentSelector
+
+use crate::{
+    Uri,
+    glob::Glob,
+    lsp::{
+        DocumentFilter, Notebook, NotebookCellTextDocumentFilter, NotebookDocumentFilter,
+        NotebookSelector,
+    },
+};
+
+/// The score of an exact match.
+pub const EXACT_SCORE: u8 = 10;
+
+/// The score of a match of a `*` wildcard or a glob pattern.
+pub const WILDCARD_SCORE: u8 = 5;
+
+/// Scores a document against the filters of a [`DocumentSelector`].
+///
+/// [`DocumentSelector`]: crate::lsp::DocumentSelector
+#[must_use]
+pub fn score(selector: &[DocumentFilter], uri: &Uri, language_id: &str) -> u8 {
+    selector
+        .iter()
+        .map(|filter| filter.score(uri, language_id))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Whether a document matches any filter of a [`DocumentSelector`].
+///
+/// [`DocumentSelector`]: crate::lsp::DocumentSelector
+#[must_use]
+pub fn matches(selector: &[DocumentFilter], uri: &Uri, language_id: &str) -> bool {
+    score(selector, uri, language_id) > 0
+}
+
+impl DocumentFilter {
+    /// Scores a document against the filter.
+    #[must_use]
+    pub fn score(&self, uri: &Uri, language_id: &str) -> u8 {
+        combine([
+            self.language
+                .as_deref()
+                .map(|language| score_name(language, language_id)),
+            self.scheme
+                .as_deref()
+                .map(|scheme| score_scheme(scheme, uri)),
+            self.pattern
+                .as_deref()
+                .map(|pattern| score_pattern(pattern, uri)),
+        ])
+    }
+
+    /// Whether a document matches the filter.
+    #[must_use]
+    pub fn matches(&self, uri: &Uri, language_id: &str) -> bool {
+        self.score(uri, language_id) > 0
+    }
+}
+
+impl NotebookDocumentFilter {
+    /// Scores a notebook document against the filter.
+    #[must_use]
+    pub fn score(&self, notebook_uri: &Uri, notebook_type: &str) -> u8 {
+        let (filter_type, scheme, pattern) = match self {
+            Self::ByType {
+                notebook_type,
+                scheme,
+                pattern,
+            } => (Some(notebook_type), scheme.as_ref(), pattern.as_ref()),
+            Self::ByScheme {
+                notebook_type,
+                scheme,
+                pattern,
+            } => (notebook_type.as_ref(), Some(scheme), pattern.as_ref()),
+            Self::ByPattern {
+                notebook_type,
+                scheme,
+                pattern,
+            } => (notebook_type.as_ref(), scheme.as_ref(), Some(pattern)),
+        };
+        combine([
+            filter_type.map(|filter_type| score_name(filter_type, notebook_type)),
+            scheme.map(|scheme| score_scheme(scheme, notebook_uri)),
+            pattern.map(|pattern| score_pattern(pattern, notebook_uri)),
+        ])
+    }
+
+    /// Whether a notebook document matches the filter.
+    #[must_use]
+    pub fn matches(&self, notebook_uri: &Uri, notebook_type: &str) -> bool {
+        self.score(notebook_uri, notebook_type) > 0
+    }
+}
+
+impl Notebook {
+    /// Scores a notebook document against the notebook type or filter.
+    #[must_use]
+    pub fn score(&self, notebook_uri: &Uri, notebook_type: &str) -> u8 {
+        match self {
+            Self::String(filter_type) => score_name(filter_type, notebook_type),
+            Self::NotebookDocumentFilter(filter) => filter.score(notebook_uri, notebook_type),
+        }
+    }
+}
+
+impl NotebookCellTextDocumentFilter {
+    /// Scores a cell with the language `language_id` of the notebook document `notebook_uri`
+    /// against the filter.
+    #[must_use]
+    pub fn score(&self, notebook_uri: &Uri, notebook_type: &str, language_id: &str) -> u8 {
+        combine([
+            Some(self.notebook.score(notebook_uri, notebook_type)),
+            self.language
+                .as_deref()
+                .map(|language| score_name(language, language_id)),
+        ])
+    }
+
+    /// Whether a cell with the language `language_id` of the notebook document `notebook_uri`
+    /// matches the filter.
+    #[must_use]
+    pub fn matches(&self, notebook_uri: &Uri, notebook_type: &str, language_id: &str) -> bool {
+        self.score(notebook_uri, notebook_type, language_id) > 0
+    }
+}
+
+impl NotebookSelector {
+    /// Scores a cell with the language `language_id` of the notebook document `notebook_uri`
+    /// against the selector.
+    #[must_use]
+    pub fn score(&self, notebook_uri: &Uri, notebook_type: &str, language_id: &str) -> u8 {
+        let (notebook, cells) = match self {
+            Self::ByNotebook { notebook, cells } => (Some(notebook), cells.as_deref()),
+            Self::ByCells { notebook, cells } => (notebook.as_ref(), Some(cells.as_slice())),
+        };
+        combine([
+            notebook.map(|notebook| notebook.score(notebook_uri, notebook_type)),
+            cells.map(|cells| {
+                cells
+                    .iter()
+                    .map(|cell| score_name(&cell.language, language_id))
+                    .max()
+                    .unwrap_or(0)
+            }),
+        ])
+    }
+
+    /// Whether a cell with the language `language_id` of the notebook document `notebook_uri`
+    /// matches the selector.
+    #[must_use]
+    pub fn matches(&self, notebook_uri: &Uri, notebook_type: &str, language_id: &str) -> bool {
+        self.score(notebook_uri, notebook_type, language_id) > 0
+    }
+}
+
+/// Combines the scores of the properties a filter specifies.
+fn combine<const N: usize>(scores: [Option<u8>; N]) -> u8 {
+    let mut combined = 0;
+    for score in scores.into_iter().flatten() {
+        if score == 0 {
+            return 0;
+        }
+        combined = combined.max(score);
+    }
+    combined
+}
+
+fn score_name(filter: &str, name: &str) -> u8 {
+    if filter == name {
+        EXACT_SCORE
+    } else if filter == "*" {
+        WILDCARD_SCORE
+    } else {
+        0
+    }
+}
+
+fn score_scheme(filter: &str, uri: &Uri) -> u8 {
+    if filter.eq_ignore_ascii_case(uri.scheme().as_str()) {
+        EXACT_SCORE
+    } else if filter == "*" {
+        WILDCARD_SCORE
+    } else {
+        0
+    }
+}
+
+fn score_pattern(pattern: &str, uri: &Uri) -> u8 {
+    if uri.path().decode().into_string_lossy() == pattern {
+        EXACT_SCORE
+    } else if Glob::new(pattern).is_ok_and(|glob| glob.is_match_uri(uri)) {
+        WILDCARD_SCORE
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::NotebookCellSelector;
+
+    fn uri(uri: &str) -> Uri {
+        uri.parse().unwrap()
+    }
+
+    fn filter(
+        language: Option<&str>,
+        scheme: Option<&str>,
+        pattern: Option<&str>,
+    ) -> DocumentFilter {
+        DocumentFilter {
+            language: language.map(ToOwned::to_owned),
+            scheme: scheme.map(ToOwned::to_owned),
+            pattern: pattern.map(ToOwned::to_owned),
+        }
+    }
+
+    #[test]
+    fn document_selector() {
+        let file = uri("file:///project/package.json");
+        let untitled = uri("untitled:Untitled-1");
+
+        assert_eq!(filter(Some("json"), None, None).score(&file, "json"), 10);
+        assert_eq!(filter(Some("*"), None, None).score(&file, "json"), 5);
+        assert_eq!(filter(Some("rust"), None, None).score(&file, "json"), 0);
+        assert_eq!(filter(None, None, None).score(&file, "json"), 0);
+
+        assert_eq!(
+            filter(Some("*"), Some("file"), None).score(&file, "json"),
+            10
+        );
+        assert_eq!(
+            filter(Some("json"), Some("file"), None).score(&untitled, "json"),
+            0
+        );
+        assert_eq!(filter(None, Some("*"), None).score(&untitled, "json"), 5);
+
+        let package_json = filter(Some("json"), None, Some("**/package.json"));
+        assert!(package_json.matches(&file, "json"));
+        assert!(!package_json.matches(&uri("file:///project/tsconfig.json"), "json"));
+        assert!(!filter(None, None, Some("{")).matches(&file, "json"));
+
+        let rust = uri("file:///project/src/main.rs");
+        let glob = filter(None, None, Some("**/*.rs"));
+        let exact = filter(None, None, Some("/project/src/main.rs"));
+        assert_eq!(glob.score(&rust, "rust"), 5);
+        assert_eq!(exact.score(&rust, "rust"), 10);
+        assert_eq!(score(&[glob, exact], &rust, "rust"), 10);
+
+        let selector = vec![
+            filter(Some("*"), None, None),
+            filter(Some("json"), Some("file"), None),
+        ];
+        assert_eq!(score(&selector, &file, "json"), 10);
+        assert_eq!(score(&selector, &untitled, "json"), 5);
+        assert!(matches(&selector, &untitled, "rust"));
+        assert!(!matches(&[], &file, "json"));
+    }
+
+    #[test]
+    fn notebook_selector() {
+        let notebook = uri("file:///project/analysis.ipynb");
+
+        let by_type = NotebookDocumentFilter::ByType {
+            notebook_type: "jupyter-notebook".to_owned(),
+            scheme: Some("file".to_owned()),
+            pattern: None,
+        };
+        assert_eq!(by_type.score(&notebook, "jupyter-notebook"), 10);
+        assert_eq!(by_type.score(&notebook, "interactive"), 0);
+        let by_pattern = NotebookDocumentFilter::ByPattern {
+            notebook_type: Some("*".to_owned()),
+            scheme: None,
+            pattern: "**/*.ipynb".to_owned(),
+        };
+        assert_eq!(by_pattern.score(&notebook, "interactive"), 5);
+        assert!(!by_pattern.matches(&uri("file:///project/a.py"), "interactive"));
+
+        let cell_filter = NotebookCellTextDocumentFilter {
+            notebook: Notebook::String("*".to_owned()),
+            language: Some("python".to_owned()),
+        };
+        assert_eq!(
+            cell_filter.score(&notebook, "jupyter-notebook", "python"),
+            10
+        );
+        assert_eq!(
+            cell_filter.score(&notebook, "jupyter-notebook", "markdown"),
+            0
+        );
+        let any_cell = NotebookCellTextDocumentFilter {
+            notebook: Notebook::String("*".to_owned()),
+            language: None,
+        };
+        assert_eq!(any_cell.score(&notebook, "jupyter-notebook", "markdown"), 5);
+
+        let selector = NotebookSelector::ByNotebook {
+            notebook: Notebook::NotebookDocumentFilter(by_type),
+            cells: Some(vec![NotebookCellSelector {
+                language: "python".to_owned(),
+            }]),
+        };
+        assert!(selector.matches(&notebook, "jupyter-notebook", "python"));
+        assert!(!selector.matches(&notebook, "jupyter-notebook", "markdown"));
+        let selector = NotebookSelector::ByCells {
+            notebook: None,
+            cells: vec![NotebookCellSelector {
+                language: "*".to_owned(),
+            }],
+        };
+        assert_eq!(selector.score(&notebook, "interactive", "markdown"), 5);
+    }
+}
@@ -0,0 +1,721 @@
+//! Encoding and decoding of [`SemanticTokens`].
+//!
+//! The tokens of a `textDocument/semanticTokens` response are relative to the previous token
+//! and refer to their type and modifiers by their index in the [`SemanticTokensLegend`].
+//! [`SemanticTokensBuilder`] takes tokens with absolute ranges and typed values and encodes
+//! them, [`decode`] turns encoded tokens back into [`AbsoluteSemanticToken`]s.
+//!
+//! For `textDocument/semanticTokens/full/delta`, [`delta`] computes the edits from the tokens
+//! of a previous result to new tokens and [`apply_delta`] applies them on the client.
+
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    lsp::{
+        PositionEncodingKind, Range, SemanticToken, SemanticTokenModifier, SemanticTokenType,
+        SemanticTokens, SemanticTokensDelta, SemanticTokensEdit, SemanticTokensFullDeltaResult,
+        SemanticTokensLegend,
+    },
+    text::{LineIndex, diff_slices},
+};
+
+/// The maximum number of token insertions and deletions [`delta`] searches for before it falls
+/// back to replacing all changed tokens at once.
+const MAX_TOKEN_EDITS: usize = 1000;
+
+/// The number of integers a token is encoded with.
+const TOKEN_LEN: u32 = 5;
+
+/// An error returned if tokens can not be encoded or decoded with a legend, or if edits can not
+/// be applied to them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SemanticTokensError {
+    /// The token type is not part of the legend.
+    UnknownTokenType(SemanticTokenType),
+    /// The token modifier is not part of the legend or beyond the 32 modifiers a bitset can
+    /// represent.
+    UnknownTokenModifier(SemanticTokenModifier),
+    /// The index of an encoded token type is out of the bounds of the legend.
+    TokenTypeOutOfBounds(u32),
+    /// An encoded token has a modifier bit set that is out of the bounds of the legend.
+    TokenModifierOutOfBounds(u32),
+    /// An edit deletes integers beyond the end of the tokens.
+    EditOutOfBounds { start: u32, delete_count: u32 },
+    /// An edit starts within the integers another edit deletes.
+    OverlappingEdits { start: u32 },
+    /// The edited tokens are not a multiple of five integers.
+    InvalidLength(usize),
+}
+
+impl fmt::Display for SemanticTokensError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownTokenType(token_type) => {
+                write!(
+                    f,
+                    "token type `{}` is not in the legend",
+                    token_type.as_str()
+                )
+            }
+            Self::UnknownTokenModifier(modifier) => {
+                write!(
+                    f,
+                    "token modifier `{}` is not in the legend",
+                    modifier.as_str()
+                )
+            }
+            Self::TokenTypeOutOfBounds(index) => {
+                write!(f, "token type index {index} is out of bounds")
+            }
+            Self::TokenModifierOutOfBounds(bit) => {
+                write!(f, "token modifier bit {bit} is out of bounds")
+            }
+            Self::EditOutOfBounds {
+                start,
+                delete_count,
+            } => write!(
+                f,
+                "edit deleting {delete_count} integers at {start} is out of bounds"
+            ),
+            Self::OverlappingEdits { start } => {
+                write!(f, "edit at {start} overlaps another edit")
+            }
+            Self::InvalidLength(len) => {
+                write!(f, "{len} integers are not a multiple of five")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SemanticTokensError {}
+
+/// A semantic token with an absolute position and its type and modifiers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbsoluteSemanticToken {
+    /// The line of the start of the token.
+    pub line: u32,
+    /// The character of the start of the token.
+    pub start: u32,
+    /// The length of the token in code units of the position encoding.
+    pub length: u32,
+    pub token_type: SemanticTokenType,
+    pub token_modifiers: Vec<SemanticTokenModifier>,
+}
+
+/// Encodes tokens with absolute ranges into [`SemanticTokens`].
+///
+/// The tokens can be pushed in any order, they are sorted when the result is built. Tokens
+/// that span multiple lines are split into a token per line unless the client announced
+/// `multilineTokenSupport`, see [`Self::multiline_token_support`].
+///
+/// # Examples
+///
+/// ```
+/// use ls_types::{
+///     lsp::{
+///         Position, PositionEncodingKind, Range, SemanticTokenModifier, SemanticTokenType,
+///         SemanticTokensLegend,
+///     },
+///     semantic_tokens::SemanticTokensBuilder,
+///     text::LineIndex,
+/// };
+///
+/// let legend = SemanticTokensLegend {
+///     token_types: vec![SemanticTokenType::KEYWORD, SemanticTokenType::FUNCTION],
+///     token_modifiers: vec![SemanticTokenModifier::DECLARATION],
+/// };
+/// let line_index = LineIndex::new("fn main() {}");
+/// let mut builder = SemanticTokensBuilder::new(&legend, &line_index, PositionEncodingKind::UTF16);
+/// let range = |start, end| Range::new(Position::new(0, start), Position::new(0, end));
+/// builder
+///     .push(range(3, 7), &SemanticTokenType::FUNCTION, &[SemanticTokenModifier::DECLARATION])
+///     .unwrap();
+/// builder.push(range(0, 2), &SemanticTokenType::KEYWORD, &[]).unwrap();
+///
+/// let tokens = builder.build();
+/// assert_eq!(
+///     serde_json::to_value(&tokens).unwrap()["data"],
+///     serde_json::json!([0, 0, 2, 0, 0, 0, 3, 4, 1, 1])
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct SemanticTokensBuilder<'a> {
+    token_types: HashMap<&'a SemanticTokenType, u32>,
+    token_modifiers: HashMap<&'a SemanticTokenModifier, u32>,
+    line_index: &'a LineIndex,
+    encoding: PositionEncodingKind,
+    multiline_token_support: bool,
+    /// The tokens as `(line, start, length, token type, token modifiers)`.
+    tokens: Vec<(u32, u32, u32, u32, u32)>,
+}
+
+impl<'a> SemanticTokensBuilder<'a> {
+    /// Creates a builder for tokens of the text of `line_index`, whose ranges are in the
+    /// position `encoding`.
+    #[must_use]
+    pub fn new(
+        legend: &'a SemanticTokensLegend,
+        line_index: &'a LineIndex,
+        encoding: PositionEncodingKind,
+    ) -> Self {
+        let mut token_types = HashMap::new();
+        for (index, token_type) in (0..).zip(&legend.token_types) {
+            token_types.entry(token_type).or_insert(index);
+        }
+        let mut token_modifiers = HashMap::new();
+        for (bit, modifier) in (0..u32::BITS).zip(&legend.token_modifiers) {
+            token_modifiers.entry(modifier).or_insert(bit);
+        }
+        Self {
+            token_types,
+            token_modifiers,
+            line_index,
+            encoding,
+            multiline_token_support: false,
+            tokens: Vec::new(),
+        }
+    }
+
+    /// Whether the client supports tokens that span multiple lines, see
+    /// `SemanticTokensClientCapabilities::multiline_token_support`.
+    #[must_use]
+    pub const fn multiline_token_support(mut self, yes: bool) -> Self {
+        self.multiline_token_support = yes;
+        self
+    }
+
+    /// Adds a token.
+    ///
+    /// Empty tokens are ignored and the range is clamped to the lines of the text.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the type or a modifier is not part of the legend.
+    pub fn push(
+        &mut self,
+        range: Range,
+        token_type: &SemanticTokenType,
+        token_modifiers: &[SemanticTokenModifier],
+    ) -> Result<(), SemanticTokensError> {
+        let token_type = *self
+            .token_types
+            .get(token_type)
+            .ok_or_else(|| SemanticTokensError::UnknownTokenType(token_type.clone()))?;
+        let mut bitset = 0;
+        for modifier in token_modifiers {
+            let bit = self
+                .token_modifiers
+                .get(modifier)
+                .ok_or_else(|| SemanticTokensError::UnknownTokenModifier(modifier.clone()))?;
+            bitset |= 1 << bit;
+        }
+
+        let (start, end) = (range.start, range.end);
+        if start.line == end.line || self.multiline_token_support {
+            let length = self.length(range);
+            if length > 0 {
+                let start = start.character.min(self.line_len(start.line));
+                self.tokens
+                    .push((range.start.line, start, length, token_type, bitset));
+            }
+        } else {
+            for line in start.line..=end.line {
+                let line_start = if line == start.line {
+                    start.character.min(self.line_len(line))
+                } else {
+                    0
+                };
+                let line_end = if line == end.line {
+                    end.character.min(self.line_len(line))
+                } else {
+                    self.line_len(line)
+                };
+                if line_end > line_start {
+                    self.tokens
+                        .push((line, line_start, line_end - line_start, token_type, bitset));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sorts and encodes the tokens.
+    #[must_use]
+    pub fn build(mut self) -> SemanticTokens {
+        self.tokens.sort_by_key(|&(line, start, ..)| (line, start));
+        let mut data = Vec::with_capacity(self.tokens.len());
+        let (mut previous_line, mut previous_start) = (0, 0);
+        for (line, start, length, token_type, token_modifiers_bitset) in self.tokens {
+            let delta_line = line - previous_line;
+            let delta_start = if delta_line == 0 {
+                start - previous_start
+            } else {
+                start
+            };
+            data.push(SemanticToken {
+                delta_line,
+                delta_start,
+                length,
+                token_type,
+                token_modifiers_bitset,
+            });
+            (previous_line, previous_start) = (line, start);
+        }
+        SemanticTokens {
+            result_id: None,
+            data,
+        }
+    }
+
+    /// The length of `range` in code units, including the line terminators it spans.
+    fn length(&self, range: Range) -> u32 {
+        let (start, end) = (range.start, range.end);
+        if end.line < start.line {
+            return 0;
+        }
+        let start_character = start.character.min(self.line_len(start.line));
+        let end_character = end.character.min(self.line_len(end.line));
+        if start.line == end.line {
+            return end_character.saturating_sub(start_character);
+        }
+        let mut length = 0;
+        for line in start.line..end.line {
+            length += self.line_len(line) + self.terminator_len(line);
+        }
+        length + end_character - start_character
+    }
+
+    fn line_len(&self, line: u32) -> u32 {
+        self.line_index.line_len(line, &self.encoding).unwrap_or(0)
+    }
+
+    /// The length of the terminator of `line`, which is ASCII in every encoding.
+    fn terminator_len(&self, line: u32) -> u32 {
+        match (
+            self.line_index.line_range(line),
+            self.line_index.line_range(line + 1),
+        ) {
+            (Some(line), Some(next)) => u32::try_from(next.start - line.end).unwrap_or(0),
+            _ => 0,
+        }
+    }
+}
+
+/// Decodes [`SemanticTokens`] into tokens with absolute positions, for example to check the
+/// tokens of a server in tests.
+///
+/// # Errors
+///
+/// Returns an error if a token refers to a type or modifier that is not part of the legend.
+pub fn decode(
+    tokens: &SemanticTokens,
+    legend: &SemanticTokensLegend,
+) -> Result<Vec<AbsoluteSemanticToken>, SemanticTokensError> {
+    let (mut line, mut start) = (0, 0);
+    tokens
+        .data
+        .iter()
+        .map(|token| {
+            if token.delta_line == 0 {
+                start += token.delta_start;
+            } else {
+                line += token.delta_line;
+                start = token.delta_start;
+            }
+            let token_type = usize::try_from(token.token_type)
+                .ok()
+                .and_then(|index| legend.token_types.get(index))
+                .ok_or(SemanticTokensError::TokenTypeOutOfBounds(token.token_type))?;
+            let token_modifiers = (0..u32::BITS)
+                .filter(|bit| token.token_modifiers_bitset & (1 << bit) != 0)
+                .map(|bit| {
+                    usize::try_from(bit)
+                        .ok()
+                        .and_then(|index| legend.token_modifiers.get(index))
+                        .cloned()
+                        .ok_or(SemanticTokensError::TokenModifierOutOfBounds(bit))
+                })
+                .collect::<Result<_, _>>()?;
+            Ok(AbsoluteSemanticToken {
+                line,
+                start,
+                length: token.length,
+                token_type: token_type.clone(),
+                token_modifiers,
+            })
+        })
+        .collect()
+}
+
+/// Computes the delta from the `previous` tokens a client has to the `current` tokens.
+///
+/// The tokens are compared token by token, so every edit starts at and deletes whole tokens.
+/// The result id of the delta is the result id of `current`.
+#[must_use]
+pub fn delta(previous: &SemanticTokens, current: &SemanticTokens) -> SemanticTokensDelta {
+    let edits = diff_slices(&previous.data, &current.data, MAX_TOKEN_EDITS)
+        .into_iter()
+        .map(|(old_range, new_range)| SemanticTokensEdit {
+            start: flat_len(old_range.start),
+            delete_count: flat_len(old_range.len()),
+            data: (!new_range.is_empty()).then(|| current.data[new_range].to_vec()),
+        })
+        .collect();
+    SemanticTokensDelta {
+        result_id: current.result_id.clone(),
+        edits,
+    }
+}
+
+/// Applies the result of a `textDocument/semanticTokens/full/delta` request to the `previous`
+/// tokens of the client.
+///
+/// Full tokens replace the previous tokens. The edits of a delta refer to the integers of the
+/// previous tokens and may be given in any order, but must not overlap.
+///
+/// # Errors
+///
+/// Returns an error if an edit is out of bounds, edits overlap or the edited tokens are not a
+/// multiple of five integers.
+pub fn apply_delta(
+    previous: &SemanticTokens,
+    result: SemanticTokensFullDeltaResult,
+) -> Result<SemanticTokens, SemanticTokensError> {
+    let (result_id, mut edits) = match result {
+        SemanticTokensFullDeltaResult::Tokens(tokens) => return Ok(tokens),
+        SemanticTokensFullDeltaResult::TokensDelta(delta) => (delta.result_id, delta.edits),
+        SemanticTokensFullDeltaResult::PartialTokensDelta { edits } => (None, edits),
+    };
+    edits.sort_by_key(|edit| edit.start);
+
+    let old = flatten(&previous.data);
+    let mut new = Vec::with_capacity(old.len());
+    let mut copied = 0;
+    for edit in edits {
+        let start = edit.start as usize;
+        let end = start.saturating_add(edit.delete_count as usize);
+        if end > old.len() {
+            return Err(SemanticTokensError::EditOutOfBounds {
+                start: edit.start,
+                delete_count: edit.delete_count,
+            });
+        }
+        if start < copied {
+            return Err(SemanticTokensError::OverlappingEdits { start: edit.start });
+        }
+        new.extend_from_slice(&old[copied..start]);
+        new.extend(flatten(edit.data.as_deref().unwrap_or_default()));
+        copied = end;
+    }
+    new.extend_from_slice(&old[copied..]);
+
+    let chunks = new.chunks_exact(TOKEN_LEN as usize);
+    if !chunks.remainder().is_empty() {
+        return Err(SemanticTokensError::InvalidLength(new.len()));
+    }
+    Ok(SemanticTokens {
+        result_id,
+        data: chunks
+            .map(|chunk| SemanticToken {
+                delta_line: chunk[0],
+                delta_start: chunk[1],
+                length: chunk[2],
+                token_type: chunk[3],
+                token_modifiers_bitset: chunk[4],
+            })
+            .collect(),
+    })
+}
+
+/// The number of integers `tokens` tokens are encoded with.
+fn flat_len(tokens: usize) -> u32 {
+    u32::try_from(tokens).map_or(u32::MAX, |tokens| tokens.saturating_mul(TOKEN_LEN))
+}
+
+fn flatten(tokens: &[SemanticToken]) -> Vec<u32> {
+    tokens
+        .iter()
+        .flat_map(|token| {
+            [
+                token.delta_line,
+                token.delta_start,
+                token.length,
+                token.token_type,
+                token.token_modifiers_bitset,
+            ]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::Position;
+
+    fn legend() -> SemanticTokensLegend {
+        SemanticTokensLegend {
+            token_types: vec![
+                SemanticTokenType::KEYWORD,
+                SemanticTokenType::STRING,
+                SemanticTokenType::COMMENT,
+            ],
+            token_modifiers: vec![
+                SemanticTokenModifier::DECLARATION,
+                SemanticTokenModifier::DOCUMENTATION,
+            ],
+        }
+    }
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+        Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+    }
+
+    fn token(
+        line: u32,
+        start: u32,
+        length: u32,
+        token_type: SemanticTokenType,
+        token_modifiers: Vec<SemanticTokenModifier>,
+    ) -> AbsoluteSemanticToken {
+        AbsoluteSemanticToken {
+            line,
+            start,
+            length,
+            token_type,
+            token_modifiers,
+        }
+    }
+
+    #[test]
+    fn build_and_decode() {
+        let legend = legend();
+        let text = "let s = \"a\r\nb\";\n/// doc\n";
+        let line_index = LineIndex::new(text);
+
+        let mut builder =
+            SemanticTokensBuilder::new(&legend, &line_index, PositionEncodingKind::UTF16);
+        builder
+            .push(
+                range((2, 0), (2, 7)),
+                &SemanticTokenType::COMMENT,
+                &[SemanticTokenModifier::DOCUMENTATION],
+            )
+            .unwrap();
+        builder
+            .push(range((0, 8), (1, 2)), &SemanticTokenType::STRING, &[])
+            .unwrap();
+        builder
+            .push(range((0, 0), (0, 3)), &SemanticTokenType::KEYWORD, &[])
+            .unwrap();
+        builder
+            .push(range((1, 3), (1, 3)), &SemanticTokenType::KEYWORD, &[])
+            .unwrap();
+        let tokens = builder.build();
+        assert_eq!(
+            tokens.data,
+            [
+                (0, 0, 3, 0, 0),
+                (0, 8, 2, 1, 0),
+                (1, 0, 2, 1, 0),
+                (1, 0, 7, 2, 2),
+            ]
+            .map(|(delta_line, delta_start, length, token_type, bitset)| {
+                SemanticToken {
+                    delta_line,
+                    delta_start,
+                    length,
+                    token_type,
+                    token_modifiers_bitset: bitset,
+                }
+            })
+        );
+        assert_eq!(
+            decode(&tokens, &legend).unwrap(),
+            [
+                token(0, 0, 3, SemanticTokenType::KEYWORD, vec![]),
+                token(0, 8, 2, SemanticTokenType::STRING, vec![]),
+                token(1, 0, 2, SemanticTokenType::STRING, vec![]),
+                token(
+                    2,
+                    0,
+                    7,
+                    SemanticTokenType::COMMENT,
+                    vec![SemanticTokenModifier::DOCUMENTATION]
+                ),
+            ]
+        );
+
+        // With multiline support the string keeps its `\r\n` terminator.
+        let mut builder =
+            SemanticTokensBuilder::new(&legend, &line_index, PositionEncodingKind::UTF16)
+                .multiline_token_support(true);
+        builder
+            .push(range((0, 8), (1, 2)), &SemanticTokenType::STRING, &[])
+            .unwrap();
+        assert_eq!(
+            decode(&builder.build(), &legend).unwrap(),
+            [token(0, 8, 6, SemanticTokenType::STRING, vec![])]
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let legend = legend();
+        let line_index = LineIndex::new("x");
+        let mut builder =
+            SemanticTokensBuilder::new(&legend, &line_index, PositionEncodingKind::UTF16);
+        assert_eq!(
+            builder.push(range((0, 0), (0, 1)), &SemanticTokenType::MACRO, &[]),
+            Err(SemanticTokensError::UnknownTokenType(
+                SemanticTokenType::MACRO
+            ))
+        );
+        assert_eq!(
+            builder.push(
+                range((0, 0), (0, 1)),
+                &SemanticTokenType::KEYWORD,
+                &[SemanticTokenModifier::STATIC]
+            ),
+            Err(SemanticTokensError::UnknownTokenModifier(
+                SemanticTokenModifier::STATIC
+            ))
+        );
+
+        let tokens = |token_type, token_modifiers_bitset| SemanticTokens {
+            result_id: None,
+            data: vec![SemanticToken {
+                delta_line: 0,
+                delta_start: 0,
+                length: 1,
+                token_type,
+                token_modifiers_bitset,
+            }],
+        };
+        assert_eq!(
+            decode(&tokens(3, 0), &legend),
+            Err(SemanticTokensError::TokenTypeOutOfBounds(3))
+        );
+        assert_eq!(
+            decode(&tokens(0, 0b100), &legend),
+            Err(SemanticTokensError::TokenModifierOutOfBounds(2))
+        );
+    }
+
+    fn tokens(result_id: &str, data: &[u32]) -> SemanticTokens {
+        SemanticTokens {
+            result_id: Some(result_id.to_owned()),
+            data: data
+                .chunks_exact(5)
+                .map(|chunk| SemanticToken {
+                    delta_line: chunk[0],
+                    delta_start: chunk[1],
+                    length: chunk[2],
+                    token_type: chunk[3],
+                    token_modifiers_bitset: chunk[4],
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn delta_and_apply() {
+        let previous = tokens(
+            "1",
+            &[0, 0, 3, 0, 0, 0, 4, 2, 1, 0, 2, 0, 7, 2, 2, 1, 2, 3, 0, 0],
+        );
+        let current = tokens(
+            "2",
+            &[0, 0, 3, 0, 0, 1, 0, 7, 2, 2, 1, 2, 3, 0, 0, 0, 5, 1, 1, 0],
+        );
+        let delta = delta(&previous, &current);
+        assert_eq!(delta.result_id.as_deref(), Some("2"));
+        assert_eq!(
+            serde_json::to_value(&delta.edits).unwrap(),
+            serde_json::json!([
+                { "start": 5, "deleteCount": 10, "data": [1, 0, 7, 2, 2] },
+                { "start": 20, "deleteCount": 0, "data": [0, 5, 1, 1, 0] },
+            ])
+        );
+        let applied =
+            apply_delta(&previous, SemanticTokensFullDeltaResult::TokensDelta(delta)).unwrap();
+        assert_eq!(applied, current);
+
+        let unchanged = super::delta(&previous, &previous);
+        assert!(unchanged.edits.is_empty());
+
+        // Edits of other servers may be given in any order and need not be token aligned.
+        let edits = vec![
+            SemanticTokensEdit {
+                start: 17,
+                delete_count: 3,
+                data: Some(vec![SemanticToken {
+                    delta_line: 9,
+                    delta_start: 9,
+                    length: 9,
+                    token_type: 9,
+                    token_modifiers_bitset: 9,
+                }]),
+            },
+            SemanticTokensEdit {
+                start: 2,
+                delete_count: 2,
+                data: None,
+            },
+        ];
+        let applied = apply_delta(
+            &previous,
+            SemanticTokensFullDeltaResult::PartialTokensDelta { edits },
+        );
+        let mut expected = tokens(
+            "",
+            &[0, 0, 0, 0, 4, 2, 1, 0, 2, 0, 7, 2, 2, 1, 2, 9, 9, 9, 9, 9],
+        );
+        expected.result_id = None;
+        assert_eq!(applied, Ok(expected));
+        assert_eq!(
+            apply_delta(
+                &previous,
+                SemanticTokensFullDeltaResult::PartialTokensDelta {
+                    edits: vec![SemanticTokensEdit {
+                        start: 0,
+                        delete_count: 3,
+                        data: None,
+                    }]
+                }
+            ),
+            Err(SemanticTokensError::InvalidLength(17))
+        );
+
+        let out_of_bounds = SemanticTokensEdit {
+            start: 20,
+            delete_count: 1,
+            data: None,
+        };
+        assert_eq!(
+            apply_delta(
+                &previous,
+                SemanticTokensFullDeltaResult::PartialTokensDelta {
+                    edits: vec![out_of_bounds]
+                }
+            ),
+            Err(SemanticTokensError::EditOutOfBounds {
+                start: 20,
+                delete_count: 1
+            })
+        );
+        let overlapping = |start| SemanticTokensEdit {
+            start,
+            delete_count: 5,
+            data: None,
+        };
+        assert_eq!(
+            apply_delta(
+                &previous,
+                SemanticTokensFullDeltaResult::PartialTokensDelta {
+                    edits: vec![overlapping(3), overlapping(0)]
+                }
+            ),
+            Err(SemanticTokensError::OverlappingEdits { start: 3 })
+        );
+    }
+}
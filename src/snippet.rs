@@ -0,0 +1,1150 @@
+//! The snippet syntax of [`InsertTextFormat::SNIPPET`] completion items, snippet text edits and
+//! inline completions.
+//!
+//! [`Snippet::parse`] parses a snippet string into its tabstops, placeholders, choices,
+//! variables and transforms, and the [`Display`](fmt::Display) implementation prints it again
+//! with everything escaped that has to be. [`SnippetBuilder`] builds snippets from plain text,
+//! so that `$`, `}` and `\` in the text do not have to be escaped by hand, and
+//! [`Snippet::render`] expands a snippet to plain text and the ranges of its tabstops, like a
+//! client does when it inserts it.
+//!
+//! [`InsertTextFormat::SNIPPET`]: crate::lsp::InsertTextFormat::SNIPPET
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, iter, ops,
+};
+
+/// The characters that are escaped in text.
+const TEXT_ESCAPES: &[char] = &['$', '}', '\\'];
+
+/// The characters that are escaped in the options of a choice.
+const CHOICE_ESCAPES: &[char] = &['$', '}', '\\', ',', '|'];
+
+/// The characters that are escaped in the format string of a transform.
+const FORMAT_ESCAPES: &[char] = &['$', '/', '\\'];
+
+/// The characters that are escaped in the texts of a conditional format.
+const CONDITIONAL_ESCAPES: &[char] = &['}', ':', '\\'];
+
+/// A parsed snippet.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Snippet {
+    pub elements: Vec<SnippetElement>,
+}
+
+/// An element of a [`Snippet`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnippetElement {
+    /// Plain text, unescaped.
+    Text(String),
+    /// A tabstop like `$1` or `${1}`, optionally with a transform of the text of the tabstop.
+    ///
+    /// The tabstop `0` is the final cursor position.
+    Tabstop {
+        index: u32,
+        transform: Option<Transform>,
+    },
+    /// A tabstop with a default value like `${1:foo}`.
+    Placeholder { index: u32, value: Vec<Self> },
+    /// A tabstop with a list of values like `${1|one,two,three|}`.
+    Choice { index: u32, options: Vec<String> },
+    /// A variable like `$TM_FILENAME` or `${TM_FILENAME:default}`, optionally with a transform
+    /// of its value.
+    Variable {
+        name: String,
+        default: Option<Vec<Self>>,
+        transform: Option<Transform>,
+    },
+}
+
+/// A transform like `/(.*)/${1:/upcase}/g` that replaces the matches of a regular expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transform {
+    /// The source of the JavaScript regular expression, with `\/` unescaped.
+    pub regex: String,
+    /// The replacement of the matches.
+    pub format: Vec<FormatItem>,
+    /// The JavaScript regular expression flags, like `g` or `i`.
+    pub options: String,
+}
+
+/// An item of the format string of a [`Transform`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatItem {
+    /// Plain text, unescaped.
+    Text(String),
+    /// The capture group like `$1` or `${1}`.
+    Group(u32),
+    /// The capture group with a case modification like `${1:/upcase}`.
+    Case { group: u32, modifier: CaseModifier },
+    /// Text depending on whether the capture group matched, like `${1:+if}`, `${1:-else}` or
+    /// `${1:?if:else}`.
+    Conditional {
+        group: u32,
+        if_text: Option<String>,
+        else_text: Option<String>,
+    },
+}
+
+/// A case modification of a [`FormatItem::Case`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseModifier {
+    Upcase,
+    Downcase,
+    Capitalize,
+    Camelcase,
+    Pascalcase,
+}
+
+impl CaseModifier {
+    const ALL: [Self; 5] = [
+        Self::Upcase,
+        Self::Downcase,
+        Self::Capitalize,
+        Self::Camelcase,
+        Self::Pascalcase,
+    ];
+
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Upcase => "upcase",
+            Self::Downcase => "downcase",
+            Self::Capitalize => "capitalize",
+            Self::Camelcase => "camelcase",
+            Self::Pascalcase => "pascalcase",
+        }
+    }
+}
+
+/// A snippet expanded to plain text, see [`Snippet::render`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedSnippet {
+    pub text: String,
+    /// The tabstops in the order they are visited, with the final tabstop `0` last.
+    pub tabstops: Vec<RenderedTabstop>,
+}
+
+/// The ranges of a tabstop in a [`RenderedSnippet`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedTabstop {
+    pub index: u32,
+    /// The byte ranges of the tabstop and its mirrors in the text.
+    pub ranges: Vec<ops::Range<usize>>,
+}
+
+impl Snippet {
+    /// Parses a snippet.
+    ///
+    /// Like clients do, a `$` that does not start a valid tabstop, placeholder, choice or
+    /// variable is taken literally, so parsing never fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ls_types::snippet::{Snippet, SnippetElement};
+    ///
+    /// let snippet = Snippet::parse("fn ${1:name}($2) -> $0");
+    /// assert_eq!(
+    ///     snippet.elements[1],
+    ///     SnippetElement::Placeholder {
+    ///         index: 1,
+    ///         value: vec![SnippetElement::Text("name".to_owned())],
+    ///     }
+    /// );
+    /// assert_eq!(snippet.to_string(), "fn ${1:name}($2) -> $0");
+    /// ```
+    #[must_use]
+    pub fn parse(snippet: &str) -> Self {
+        let mut parser = Parser {
+            chars: snippet.chars().collect(),
+            pos: 0,
+        };
+        Self {
+            elements: parser.any(false),
+        }
+    }
+
+    /// Expands the snippet to plain text and the ranges of its tabstops.
+    ///
+    /// Tabstops mirror the value of the first placeholder or choice with the same index, a
+    /// choice expands to its first option. Variables expand to the value `resolve` returns, or
+    /// to their default or their name if it returns `None`. Transforms are not applied, as
+    /// they need a JavaScript regular expression engine. If the snippet has no final tabstop,
+    /// it is placed at the end of the text.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use ls_types::snippet::Snippet;
+    ///
+    /// let snippet = Snippet::parse("${1:a} = $1 + ${2|x,y|} // $TM_FILENAME");
+    /// let rendered = snippet.render(|name| (name == "TM_FILENAME").then(|| "main.rs".to_owned()));
+    /// assert_eq!(rendered.text, "a = a + x // main.rs");
+    /// assert_eq!(rendered.tabstops[0].ranges, [0..1, 4..5]);
+    /// assert_eq!(rendered.tabstops[1].ranges, [8..9]);
+    /// assert_eq!(rendered.tabstops[2].ranges, [20..20]);
+    /// ```
+    #[must_use]
+    pub fn render(&self, resolve: impl Fn(&str) -> Option<String>) -> RenderedSnippet {
+        let mut definitions = HashMap::new();
+        collect_definitions(&self.elements, &mut definitions);
+        let mut renderer = Renderer {
+            definitions,
+            resolve: &resolve,
+            text: String::new(),
+            tabstops: BTreeMap::new(),
+            expanding: vec![],
+        };
+        renderer.render(&self.elements, true);
+
+        let Renderer {
+            text, mut tabstops, ..
+        } = renderer;
+        let end = tabstops
+            .remove(&0)
+            .unwrap_or_else(|| iter::once(text.len()..text.len()).collect());
+        let mut tabstops: Vec<RenderedTabstop> = tabstops
+            .into_iter()
+            .map(|(index, ranges)| RenderedTabstop { index, ranges })
+            .collect();
+        tabstops.push(RenderedTabstop {
+            index: 0,
+            ranges: end,
+        });
+        RenderedSnippet { text, tabstops }
+    }
+}
+
+impl fmt::Display for Snippet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_elements(f, &self.elements)
+    }
+}
+
+/// Escapes `text` so that it is inserted literally when it is used in a snippet.
+///
+/// # Examples
+///
+/// ```
+/// use ls_types::snippet::escape;
+///
+/// assert_eq!(escape("${x}"), "\\${x\\}");
+/// ```
+#[must_use]
+pub fn escape(text: &str) -> String {
+    escape_with(text, TEXT_ESCAPES)
+}
+
+/// Builds a [`Snippet`] from plain text and tabstops.
+///
+/// # Examples
+///
+/// ```
+/// use ls_types::snippet::SnippetBuilder;
+///
+/// let snippet = SnippetBuilder::new()
+///     .text("format!(\"{}\", ")
+///     .placeholder(1, "value")
+///     .text(")")
+///     .final_tabstop()
+///     .build();
+/// assert_eq!(snippet.to_string(), "format!(\"{\\}\", ${1:value})$0");
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct SnippetBuilder {
+    elements: Vec<SnippetElement>,
+}
+
+impl SnippetBuilder {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            elements: Vec::new(),
+        }
+    }
+
+    /// Appends plain text.
+    #[must_use]
+    pub fn text(mut self, text: &str) -> Self {
+        if let Some(SnippetElement::Text(previous)) = self.elements.last_mut() {
+            previous.push_str(text);
+        } else if !text.is_empty() {
+            self.elements.push(SnippetElement::Text(text.to_owned()));
+        }
+        self
+    }
+
+    /// Appends a tabstop.
+    #[must_use]
+    pub fn tabstop(mut self, index: u32) -> Self {
+        self.elements.push(SnippetElement::Tabstop {
+            index,
+            transform: None,
+        });
+        self
+    }
+
+    /// Appends the final tabstop `$0`.
+    #[must_use]
+    pub fn final_tabstop(self) -> Self {
+        self.tabstop(0)
+    }
+
+    /// Appends a placeholder with plain text as its value.
+    #[must_use]
+    pub fn placeholder(self, index: u32, value: &str) -> Self {
+        self.nested_placeholder(index, Self::new().text(value).build())
+    }
+
+    /// Appends a placeholder with a snippet as its value.
+    #[must_use]
+    pub fn nested_placeholder(mut self, index: u32, value: Snippet) -> Self {
+        self.elements.push(SnippetElement::Placeholder {
+            index,
+            value: value.elements,
+        });
+        self
+    }
+
+    /// Appends a choice.
+    #[must_use]
+    pub fn choice<I>(mut self, index: u32, options: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.elements.push(SnippetElement::Choice {
+            index,
+            options: options.into_iter().map(Into::into).collect(),
+        });
+        self
+    }
+
+    /// Appends a variable.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `name` is not a valid variable name, which consists of ASCII letters, digits
+    /// and `_` and does not start with a digit.
+    #[must_use]
+    pub fn variable(mut self, name: &str, default: Option<&str>) -> Self {
+        assert!(is_variable_name(name), "invalid variable name `{name}`");
+        self.elements.push(SnippetElement::Variable {
+            name: name.to_owned(),
+            default: default.map(|default| Self::new().text(default).build().elements),
+            transform: None,
+        });
+        self
+    }
+
+    #[must_use]
+    pub fn build(self) -> Snippet {
+        Snippet {
+            elements: self.elements,
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+/// What follows a `$`.
+enum Dollar {
+    Element(SnippetElement),
+    /// A placeholder or variable default without its closing `}`, so the input ends inside of
+    /// it. Everything up to `value_start` is text, followed by the value that was parsed, so
+    /// the rest of the input does not have to be parsed again.
+    Unclosed {
+        value_start: usize,
+        value: Vec<SnippetElement>,
+    },
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        let matches = self.peek() == Some(c);
+        if matches {
+            self.pos += 1;
+        }
+        matches
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let len = s.chars().count();
+        let matches = self
+            .chars
+            .get(self.pos..self.pos + len)
+            .is_some_and(|chars| chars.iter().copied().eq(s.chars()));
+        if matches {
+            self.pos += len;
+        }
+        matches
+    }
+
+    /// Parses elements up to the end, or up to an unescaped `}` if `nested`.
+    fn any(&mut self, nested: bool) -> Vec<SnippetElement> {
+        let mut elements = vec![];
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            if nested && c == '}' {
+                break;
+            }
+            if c == '$' {
+                let start = self.pos;
+                self.pos += 1;
+                match self.dollar() {
+                    Some(Dollar::Element(element)) => {
+                        if !text.is_empty() {
+                            elements.push(SnippetElement::Text(std::mem::take(&mut text)));
+                        }
+                        elements.push(element);
+                    }
+                    Some(Dollar::Unclosed { value_start, value }) => {
+                        text.extend(&self.chars[start..value_start]);
+                        for element in value {
+                            if let SnippetElement::Text(value) = element {
+                                text.push_str(&value);
+                                continue;
+                            }
+                            if !text.is_empty() {
+                                elements.push(SnippetElement::Text(std::mem::take(&mut text)));
+                            }
+                            elements.push(element);
+                        }
+                    }
+                    None => {
+                        self.pos = start + 1;
+                        text.push('$');
+                    }
+                }
+                continue;
+            }
+            text.push(self.escaped(TEXT_ESCAPES));
+        }
+        if !text.is_empty() {
+            elements.push(SnippetElement::Text(text));
+        }
+        elements
+    }
+
+    /// Consumes a character, or an escape sequence of one of `escapes`.
+    fn escaped(&mut self, escapes: &[char]) -> char {
+        let c = self.chars[self.pos];
+        self.pos += 1;
+        if c == '\\'
+            && let Some(escaped) = self.peek().filter(|next| escapes.contains(next))
+        {
+            self.pos += 1;
+            return escaped;
+        }
+        c
+    }
+
+    fn int(&mut self) -> Option<u32> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        let digits: String = self.chars[start..self.pos].iter().collect();
+        digits.parse().ok()
+    }
+
+    fn var(&mut self) -> Option<String> {
+        if !self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        {
+            return None;
+        }
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            self.pos += 1;
+        }
+        Some(self.chars[start..self.pos].iter().collect())
+    }
+
+    /// Parses the element after a `$`.
+    fn dollar(&mut self) -> Option<Dollar> {
+        if let Some(index) = self.int() {
+            return Some(Dollar::Element(SnippetElement::Tabstop {
+                index,
+                transform: None,
+            }));
+        }
+        if let Some(name) = self.var() {
+            return Some(Dollar::Element(SnippetElement::Variable {
+                name,
+                default: None,
+                transform: None,
+            }));
+        }
+        if !self.eat('{') {
+            return None;
+        }
+        if let Some(index) = self.int() {
+            if self.eat('}') {
+                return Some(Dollar::Element(SnippetElement::Tabstop {
+                    index,
+                    transform: None,
+                }));
+            }
+            if self.eat(':') {
+                let value_start = self.pos;
+                let value = self.any(true);
+                if !self.eat('}') {
+                    return Some(Dollar::Unclosed { value_start, value });
+                }
+                return Some(Dollar::Element(SnippetElement::Placeholder {
+                    index,
+                    value,
+                }));
+            }
+            if self.eat('|') {
+                let options = self.choice_options()?;
+                return Some(Dollar::Element(SnippetElement::Choice { index, options }));
+            }
+            if self.eat('/') {
+                let transform = self.transform()?;
+                return self
+                    .eat('}')
+                    .then_some(Dollar::Element(SnippetElement::Tabstop {
+                        index,
+                        transform: Some(transform),
+                    }));
+            }
+            return None;
+        }
+        let name = self.var()?;
+        if self.eat('}') {
+            return Some(Dollar::Element(SnippetElement::Variable {
+                name,
+                default: None,
+                transform: None,
+            }));
+        }
+        if self.eat(':') {
+            let value_start = self.pos;
+            let default = self.any(true);
+            if !self.eat('}') {
+                return Some(Dollar::Unclosed {
+                    value_start,
+                    value: default,
+                });
+            }
+            return Some(Dollar::Element(SnippetElement::Variable {
+                name,
+                default: Some(default),
+                transform: None,
+            }));
+        }
+        if self.eat('/') {
+            let transform = self.transform()?;
+            return self
+                .eat('}')
+                .then_some(Dollar::Element(SnippetElement::Variable {
+                    name,
+                    default: None,
+                    transform: Some(transform),
+                }));
+        }
+        None
+    }
+
+    /// Parses the options of a choice after the `|` up to and including the closing `|}`.
+    fn choice_options(&mut self) -> Option<Vec<String>> {
+        let mut options = vec![];
+        let mut option = String::new();
+        loop {
+            match self.peek()? {
+                ',' => {
+                    self.pos += 1;
+                    options.push(std::mem::take(&mut option));
+                }
+                '|' => {
+                    self.pos += 1;
+                    options.push(option);
+                    return self.eat('}').then_some(options);
+                }
+                _ => option.push(self.escaped(CHOICE_ESCAPES)),
+            }
+        }
+    }
+
+    /// Parses a transform after the first `/` up to the options.
+    fn transform(&mut self) -> Option<Transform> {
+        let mut regex = String::new();
+        loop {
+            match self.peek()? {
+                '/' => {
+                    self.pos += 1;
+                    break;
+                }
+                '\\' if self.chars.get(self.pos + 1) == Some(&'/') => {
+                    self.pos += 2;
+                    regex.push('/');
+                }
+                '\\' => {
+                    regex.push('\\');
+                    self.pos += 1;
+                    if let Some(c) = self.peek() {
+                        regex.push(c);
+                        self.pos += 1;
+                    }
+                }
+                c => {
+                    regex.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+
+        let mut format = vec![];
+        let mut text = String::new();
+        loop {
+            match self.peek()? {
+                '/' => {
+                    self.pos += 1;
+                    break;
+                }
+                '$' => {
+                    let start = self.pos;
+                    self.pos += 1;
+                    if let Some(item) = self.format_item() {
+                        if !text.is_empty() {
+                            format.push(FormatItem::Text(std::mem::take(&mut text)));
+                        }
+                        format.push(item);
+                    } else {
+                        self.pos = start + 1;
+                        text.push('$');
+                    }
+                }
+                _ => text.push(self.escaped(FORMAT_ESCAPES)),
+            }
+        }
+        if !text.is_empty() {
+            format.push(FormatItem::Text(text));
+        }
+
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_alphabetic()) {
+            self.pos += 1;
+        }
+        let options = self.chars[start..self.pos].iter().collect();
+        Some(Transform {
+            regex,
+            format,
+            options,
+        })
+    }
+
+    /// Parses a format item after a `$`.
+    fn format_item(&mut self) -> Option<FormatItem> {
+        if let Some(group) = self.int() {
+            return Some(FormatItem::Group(group));
+        }
+        if !self.eat('{') {
+            return None;
+        }
+        let group = self.int()?;
+        if self.eat('}') {
+            return Some(FormatItem::Group(group));
+        }
+        if !self.eat(':') {
+            return None;
+        }
+        if self.eat('/') {
+            let modifier = CaseModifier::ALL
+                .into_iter()
+                .find(|modifier| self.eat_str(modifier.as_str()))?;
+            return self
+                .eat('}')
+                .then_some(FormatItem::Case { group, modifier });
+        }
+        let (if_text, else_text) = if self.eat('+') {
+            (Some(self.conditional_text(false)?), None)
+        } else if self.eat('?') {
+            let if_text = self.conditional_text(true)?;
+            self.eat(':').then_some(())?;
+            (Some(if_text), Some(self.conditional_text(false)?))
+        } else {
+            self.eat('-');
+            (None, Some(self.conditional_text(false)?))
+        };
+        self.eat('}').then_some(FormatItem::Conditional {
+            group,
+            if_text,
+            else_text,
+        })
+    }
+
+    /// Parses the text of a conditional up to an unescaped `}`, or `:` if `until_colon`.
+    fn conditional_text(&mut self, until_colon: bool) -> Option<String> {
+        let mut text = String::new();
+        loop {
+            match self.peek()? {
+                '}' => return Some(text),
+                ':' if until_colon => return Some(text),
+                _ => text.push(self.escaped(CONDITIONAL_ESCAPES)),
+            }
+        }
+    }
+}
+
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn escape_with(text: &str, special: &[char]) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn write_elements(f: &mut fmt::Formatter<'_>, elements: &[SnippetElement]) -> fmt::Result {
+    for (position, element) in elements.iter().enumerate() {
+        match element {
+            SnippetElement::Text(text) => f.write_str(&escape(text))?,
+            SnippetElement::Tabstop {
+                index,
+                transform: None,
+            } => {
+                // `$1` followed by `2` would parse as `$12`.
+                let digit_follows = matches!(
+                    elements.get(position + 1),
+                    Some(SnippetElement::Text(text)) if text.starts_with(|c: char| c.is_ascii_digit())
+                );
+                if digit_follows {
+                    write!(f, "${{{index}}}")?;
+                } else {
+                    write!(f, "${index}")?;
+                }
+            }
+            SnippetElement::Tabstop {
+                index,
+                transform: Some(transform),
+            } => {
+                write!(f, "${{{index}")?;
+                write_transform(f, transform)?;
+                f.write_str("}")?;
+            }
+            SnippetElement::Placeholder { index, value } => {
+                write!(f, "${{{index}:")?;
+                write_elements(f, value)?;
+                f.write_str("}")?;
+            }
+            SnippetElement::Choice { index, options } => {
+                let options: Vec<String> = options
+                    .iter()
+                    .map(|option| escape_with(option, CHOICE_ESCAPES))
+                    .collect();
+                write!(f, "${{{index}|{}|}}", options.join(","))?;
+            }
+            SnippetElement::Variable {
+                name,
+                default,
+                transform,
+            } => {
+                write!(f, "${{{name}")?;
+                if let Some(default) = default {
+                    f.write_str(":")?;
+                    write_elements(f, default)?;
+                }
+                if let Some(transform) = transform {
+                    write_transform(f, transform)?;
+                }
+                f.write_str("}")?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_transform(f: &mut fmt::Formatter<'_>, transform: &Transform) -> fmt::Result {
+    write!(f, "/{}/", escape_regex(&transform.regex))?;
+    for item in &transform.format {
+        match item {
+            FormatItem::Text(text) => f.write_str(&escape_with(text, FORMAT_ESCAPES))?,
+            FormatItem::Group(group) => write!(f, "${{{group}}}")?,
+            FormatItem::Case { group, modifier } => {
+                write!(f, "${{{group}:/{}}}", modifier.as_str())?;
+            }
+            FormatItem::Conditional {
+                group,
+                if_text,
+                else_text,
+            } => {
+                let escaped = |text: &Option<String>| {
+                    escape_with(text.as_deref().unwrap_or_default(), CONDITIONAL_ESCAPES)
+                };
+                match (if_text, else_text) {
+                    (Some(_), Some(_)) => write!(
+                        f,
+                        "${{{group}:?{}:{}}}",
+                        escaped(if_text),
+                        escaped(else_text)
+                    )?,
+                    (None, Some(_)) => write!(f, "${{{group}:-{}}}", escaped(else_text))?,
+                    (_, None) => write!(f, "${{{group}:+{}}}", escaped(if_text))?,
+                }
+            }
+        }
+    }
+    write!(f, "/{}", transform.options)
+}
+
+/// Escapes the unescaped `/` of a regular expression.
+fn escape_regex(regex: &str) -> String {
+    let mut escaped = String::with_capacity(regex.len());
+    let mut chars = regex.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                escaped.push('\\');
+                if let Some(next) = chars.next() {
+                    escaped.push(next);
+                }
+            }
+            '/' => escaped.push_str("\\/"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The first placeholder or choice of each index, which its tabstops mirror.
+fn collect_definitions<'a>(
+    elements: &'a [SnippetElement],
+    definitions: &mut HashMap<u32, &'a SnippetElement>,
+) {
+    for element in elements {
+        match element {
+            SnippetElement::Placeholder { index, value } => {
+                definitions.entry(*index).or_insert(element);
+                collect_definitions(value, definitions);
+            }
+            SnippetElement::Choice { index, .. } => {
+                definitions.entry(*index).or_insert(element);
+            }
+            SnippetElement::Variable {
+                default: Some(default),
+                ..
+            } => collect_definitions(default, definitions),
+            _ => {}
+        }
+    }
+}
+
+struct Renderer<'a> {
+    definitions: HashMap<u32, &'a SnippetElement>,
+    resolve: &'a dyn Fn(&str) -> Option<String>,
+    text: String,
+    tabstops: BTreeMap<u32, Vec<ops::Range<usize>>>,
+    /// The indices of the placeholders that are being expanded, to stop recursive mirrors.
+    expanding: Vec<u32>,
+}
+
+impl<'a> Renderer<'a> {
+    /// Renders `elements`, recording the ranges of tabstops if `record`.
+    fn render(&mut self, elements: &'a [SnippetElement], record: bool) {
+        for element in elements {
+            let start = self.text.len();
+            match element {
+                SnippetElement::Text(text) => self.text.push_str(text),
+                SnippetElement::Tabstop { index, .. }
+                | SnippetElement::Placeholder { index, .. } => {
+                    let definition = self.definitions.get(index).copied();
+                    let own_value = match element {
+                        SnippetElement::Placeholder { value, .. }
+                            if definition == Some(element) =>
+                        {
+                            Some(value)
+                        }
+                        _ => None,
+                    };
+                    if let Some(value) = own_value {
+                        self.expand(*index, value, record);
+                    } else {
+                        self.mirror(*index, definition);
+                    }
+                    if record {
+                        self.record(*index, start);
+                    }
+                }
+                SnippetElement::Choice { index, options } => {
+                    self.text
+                        .push_str(options.first().map_or("", String::as_str));
+                    if record {
+                        self.record(*index, start);
+                    }
+                }
+                SnippetElement::Variable { name, default, .. } => {
+                    if let Some(value) = (self.resolve)(name) {
+                        self.text.push_str(&value);
+                    } else if let Some(default) = default {
+                        self.render(default, record);
+                    } else {
+                        self.text.push_str(name);
+                    }
+                }
+            }
+        }
+    }
+
+    fn expand(&mut self, index: u32, value: &'a [SnippetElement], record: bool) {
+        if self.expanding.contains(&index) {
+            return;
+        }
+        self.expanding.push(index);
+        self.render(value, record);
+        self.expanding.pop();
+    }
+
+    /// Renders the text of the placeholder or choice a tabstop mirrors.
+    fn mirror(&mut self, index: u32, definition: Option<&'a SnippetElement>) {
+        match definition {
+            Some(SnippetElement::Placeholder { value, .. }) => self.expand(index, value, false),
+            Some(SnippetElement::Choice { options, .. }) => self
+                .text
+                .push_str(options.first().map_or("", String::as_str)),
+            _ => {}
+        }
+    }
+
+    fn record(&mut self, index: u32, start: usize) {
+        self.tabstops
+            .entry(index)
+            .or_default()
+            .push(start..self.text.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> SnippetElement {
+        SnippetElement::Text(text.to_owned())
+    }
+
+    fn tabstop(index: u32) -> SnippetElement {
+        SnippetElement::Tabstop {
+            index,
+            transform: None,
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let snippet = Snippet::parse(
+            r"${1:outer ${2:inner}} ${3|a,b\,c|} ${TM_FILENAME/(.*)\/x/${1:/upcase}-$1\//gi} \$4 $ ${5",
+        );
+        assert_eq!(
+            snippet.elements,
+            [
+                SnippetElement::Placeholder {
+                    index: 1,
+                    value: vec![
+                        text("outer "),
+                        SnippetElement::Placeholder {
+                            index: 2,
+                            value: vec![text("inner")],
+                        },
+                    ],
+                },
+                text(" "),
+                SnippetElement::Choice {
+                    index: 3,
+                    options: vec!["a".to_owned(), "b,c".to_owned()],
+                },
+                text(" "),
+                SnippetElement::Variable {
+                    name: "TM_FILENAME".to_owned(),
+                    default: None,
+                    transform: Some(Transform {
+                        regex: "(.*)/x".to_owned(),
+                        format: vec![
+                            FormatItem::Case {
+                                group: 1,
+                                modifier: CaseModifier::Upcase,
+                            },
+                            FormatItem::Text("-".to_owned()),
+                            FormatItem::Group(1),
+                            FormatItem::Text("/".to_owned()),
+                        ],
+                        options: "gi".to_owned(),
+                    }),
+                },
+                text(" $4 $ ${5"),
+            ]
+        );
+
+        let conditional = Snippet::parse(r"${1/(a)|(b)/${1:?yes\::no}${2:+two}${2:-none}/}");
+        let SnippetElement::Tabstop {
+            index: 1,
+            transform: Some(transform),
+        } = &conditional.elements[0]
+        else {
+            panic!("{conditional:?}");
+        };
+        assert_eq!(
+            transform.format,
+            [
+                FormatItem::Conditional {
+                    group: 1,
+                    if_text: Some("yes:".to_owned()),
+                    else_text: Some("no".to_owned()),
+                },
+                FormatItem::Conditional {
+                    group: 2,
+                    if_text: Some("two".to_owned()),
+                    else_text: None,
+                },
+                FormatItem::Conditional {
+                    group: 2,
+                    if_text: None,
+                    else_text: Some("none".to_owned()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_unclosed() {
+        let unclosed = "${1:".repeat(64);
+        assert_eq!(Snippet::parse(&unclosed).elements, [text(&unclosed)]);
+
+        assert_eq!(
+            Snippet::parse("${1:a ${2:b} ${VAR:c $3").elements,
+            [
+                text("${1:a "),
+                SnippetElement::Placeholder {
+                    index: 2,
+                    value: vec![text("b")],
+                },
+                text(" ${VAR:c "),
+                tabstop(3),
+            ]
+        );
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for snippet in [
+            "plain",
+            r"\$ \} \\ ${1:a\}b} $0",
+            r"${1|one,t\|wo,th\,ree|}",
+            r"${TM_SELECTED_TEXT:${1:default}} ${VAR/a\/b/${1:?x\:y:z}-\$/g}",
+            r"${2/(\w+)/${1:/capitalize}${1:-empty}/}",
+            "${1}2 $3a",
+        ] {
+            let parsed = Snippet::parse(snippet);
+            assert_eq!(parsed.to_string(), snippet);
+            assert_eq!(Snippet::parse(&parsed.to_string()), parsed);
+        }
+        // Literal `$`s that start nothing are escaped when printed.
+        assert_eq!(Snippet::parse("a $ b").to_string(), r"a \$ b");
+    }
+
+    #[test]
+    fn builder() {
+        let snippet = SnippetBuilder::new()
+            .text("let ")
+            .placeholder(1, "${name}")
+            .text(" = ")
+            .choice(2, ["a|b", "c"])
+            .text(" ")
+            .variable("CLIPBOARD", Some("}"))
+            .text(";")
+            .final_tabstop()
+            .build();
+        assert_eq!(
+            snippet.to_string(),
+            r"let ${1:\${name\}} = ${2|a\|b,c|} ${CLIPBOARD:\}};$0"
+        );
+        assert_eq!(Snippet::parse(&snippet.to_string()), snippet);
+        assert_eq!(
+            SnippetBuilder::new()
+                .text("a")
+                .text("b")
+                .tabstop(1)
+                .build()
+                .elements,
+            [text("ab"), tabstop(1)]
+        );
+
+        let snippet = SnippetBuilder::new().tabstop(1).text("2").build();
+        assert_eq!(snippet.to_string(), "${1}2");
+        assert_eq!(Snippet::parse(&snippet.to_string()), snippet);
+    }
+
+    #[test]
+    #[should_panic = "invalid variable name `1x`"]
+    fn builder_invalid_variable() {
+        let _ = SnippetBuilder::new().variable("1x", None);
+    }
+
+    #[test]
+    #[expect(
+        clippy::single_range_in_vec_init,
+        reason = "each tabstop holds a list of ranges"
+    )]
+    fn render() {
+        let snippet = Snippet::parse("${1:fn ${2:name}}($3) $2 ${UNKNOWN} ${4:${4}}");
+        let rendered = snippet.render(|_| None);
+        assert_eq!(rendered.text, "fn name() name UNKNOWN ");
+        assert_eq!(
+            rendered.tabstops,
+            [
+                RenderedTabstop {
+                    index: 1,
+                    ranges: vec![0..7],
+                },
+                RenderedTabstop {
+                    index: 2,
+                    ranges: vec![3..7, 10..14],
+                },
+                RenderedTabstop {
+                    index: 3,
+                    ranges: vec![8..8],
+                },
+                RenderedTabstop {
+                    index: 4,
+                    ranges: vec![23..23, 23..23],
+                },
+                RenderedTabstop {
+                    index: 0,
+                    ranges: vec![23..23],
+                },
+            ]
+        );
+
+        let rendered = Snippet::parse("$0a").render(|_| None);
+        assert_eq!(
+            rendered.tabstops,
+            [RenderedTabstop {
+                index: 0,
+                ranges: vec![0..0]
+            }]
+        );
+    }
+}
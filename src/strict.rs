@@ -0,0 +1,265 @@
+//! Strict validation that reports the JSON properties a type does not know.
+//!
+//! The types of this crate ignore unknown properties when they are deserialized, as the
+//! specification requires for forward compatibility. [`validate_strict`] instead reports every
+//! property that would be ignored, which is useful to check that a message only contains
+//! properties of the specification, for example in conformance tests.
+
+use std::fmt;
+
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+
+/// An error returned by [`validate_strict`].
+#[derive(Debug)]
+pub enum StrictError {
+    /// The value can not be deserialized at all.
+    Invalid(serde_json::Error),
+    /// The value contains properties that are ignored when it is deserialized.
+    ///
+    /// Each property is given as a JSON path like `$.capabilities.textDocument.hover`.
+    UnknownProperties(Vec<String>),
+}
+
+impl fmt::Display for StrictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(error) => write!(f, "invalid value: {error}"),
+            Self::UnknownProperties(paths) => {
+                write!(f, "unknown properties: {}", paths.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for StrictError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Invalid(error) => Some(error),
+            Self::UnknownProperties(_) => None,
+        }
+    }
+}
+
+/// Checks that `value` can be deserialized as a `T` without ignoring any of its properties.
+///
+/// A property is unknown if `T` deserializes to the same value no matter what the property
+/// contains. Properties nested in an unknown property are not reported separately.
+///
+/// # Errors
+///
+/// Returns [`StrictError::Invalid`] if `value` is not a valid `T` and
+/// [`StrictError::UnknownProperties`] with the paths of all unknown properties otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use ls_types::{lsp::ClientCapabilities, strict::validate_strict};
+/// use serde_json::json;
+///
+/// let capabilities = json!({ "textDocument": { "hover": { "dynamicRegistraton": true } } });
+/// let error = validate_strict::<ClientCapabilities>(&capabilities).unwrap_err();
+/// assert_eq!(
+///     error.to_string(),
+///     "unknown properties: $.textDocument.hover.dynamicRegistraton"
+/// );
+/// ```
+pub fn validate_strict<T>(value: &Value) -> Result<(), StrictError>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let expected = round_trip::<T>(value).map_err(StrictError::Invalid)?;
+    let mut finder = Finder {
+        root: value,
+        expected: &expected,
+        probe: round_trip::<T>,
+        path: vec![],
+        unknown: vec![],
+    };
+    finder.visit(value, Some(&expected));
+    if finder.unknown.is_empty() {
+        Ok(())
+    } else {
+        Err(StrictError::UnknownProperties(finder.unknown))
+    }
+}
+
+/// Deserializes `value` as a `T` and serializes it again.
+fn round_trip<T>(value: &Value) -> serde_json::Result<Value>
+where
+    T: DeserializeOwned + Serialize,
+{
+    serde_json::to_value(T::deserialize(value)?)
+}
+
+#[derive(Clone, Copy)]
+enum Segment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+struct Finder<'a> {
+    root: &'a Value,
+    /// The round trip of `root`.
+    expected: &'a Value,
+    probe: fn(&Value) -> serde_json::Result<Value>,
+    path: Vec<Segment<'a>>,
+    unknown: Vec<String>,
+}
+
+impl<'a> Finder<'a> {
+    /// Visits `value` at the current path. `round_tripped` is the corresponding part of the
+    /// round trip, if it was serialized again.
+    fn visit(&mut self, value: &'a Value, round_tripped: Option<&Value>) {
+        if round_tripped == Some(value) {
+            return;
+        }
+        match value {
+            Value::Object(properties) => {
+                for (key, property) in properties {
+                    self.path.push(Segment::Key(key));
+                    let round_tripped = round_tripped.and_then(|value| value.get(key));
+                    if round_tripped.is_none() && self.is_ignored() {
+                        self.unknown.push(self.json_path());
+                    } else {
+                        self.visit(property, round_tripped);
+                    }
+                    self.path.pop();
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.path.push(Segment::Index(index));
+                    self.visit(item, round_tripped.and_then(|value| value.get(index)));
+                    self.path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether the property at the current path is ignored, that is replacing it with values
+    /// no known property accepts unchanged does not change the round trip.
+    fn is_ignored(&self) -> bool {
+        let pointer = self.json_pointer();
+        [Value::from("\u{0}"), Value::from(-0.5)]
+            .into_iter()
+            .all(|probe| {
+                let mut value = self.root.clone();
+                if let Some(property) = value.pointer_mut(&pointer) {
+                    *property = probe;
+                }
+                (self.probe)(&value).is_ok_and(|round_tripped| &round_tripped == self.expected)
+            })
+    }
+
+    fn json_pointer(&self) -> String {
+        let mut pointer = String::new();
+        for segment in &self.path {
+            pointer.push('/');
+            match segment {
+                Segment::Key(key) => pointer.push_str(&key.replace('~', "~0").replace('/', "~1")),
+                Segment::Index(index) => pointer.push_str(&index.to_string()),
+            }
+        }
+        pointer
+    }
+
+    fn json_path(&self) -> String {
+        let mut path = String::from("$");
+        for segment in &self.path {
+            match segment {
+                Segment::Key(key) if is_identifier(key) => {
+                    path.push('.');
+                    path.push_str(key);
+                }
+                Segment::Key(key) => {
+                    path.push('[');
+                    path.push_str(&Value::from(*key).to_string());
+                    path.push(']');
+                }
+                Segment::Index(index) => {
+                    path.push('[');
+                    path.push_str(&index.to_string());
+                    path.push(']');
+                }
+            }
+        }
+        path
+    }
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::lsp::{ClientCapabilities, InitializeParams};
+
+    fn unknown<T>(value: &Value) -> Vec<String>
+    where
+        T: DeserializeOwned + Serialize,
+    {
+        match validate_strict::<T>(value) {
+            Ok(()) => vec![],
+            Err(StrictError::UnknownProperties(paths)) => paths,
+            Err(error) => panic!("{error}"),
+        }
+    }
+
+    #[test]
+    fn known() {
+        let capabilities = json!({
+            "workspace": { "applyEdit": true, "workspaceEdit": {} },
+            "textDocument": {
+                "hover": { "contentFormat": ["markdown"] },
+                "synchronization": { "didSave": null }
+            },
+            "experimental": { "anything": { "goes": [1] } }
+        });
+        assert_eq!(
+            unknown::<ClientCapabilities>(&capabilities),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn unknown_properties() {
+        let params = json!({
+            "processId": null,
+            "rootUri": null,
+            "capabilities": {
+                "textDocument": {
+                    "hover": { "dynamicRegistraton": true, "contentFormat": ["plaintext"] },
+                    "completion": null,
+                    "future": { "nested": true }
+                }
+            },
+            "workspaceFolders": [
+                { "uri": "file:///a", "name": "a" },
+                { "uri": "file:///b", "name": "b", "weird key": 1 }
+            ]
+        });
+        assert_eq!(
+            unknown::<InitializeParams>(&params),
+            vec![
+                "$.capabilities.textDocument.future",
+                "$.capabilities.textDocument.hover.dynamicRegistraton",
+                r#"$.workspaceFolders[1]["weird key"]"#,
+            ]
+        );
+
+        assert!(matches!(
+            validate_strict::<InitializeParams>(&json!({ "processId": "one" })),
+            Err(StrictError::Invalid(_))
+        ));
+    }
+}
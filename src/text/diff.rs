@@ -0,0 +1,335 @@
+use std::ops;
+
+use crate::{
+    lsp::{PositionEncodingKind, TextEdit},
+    text::LineIndex,
+};
+
+/// The maximum number of line insertions and deletions the line diff searches for before it
+/// falls back to replacing all changed lines at once.
+const MAX_LINE_EDITS: usize = 1000;
+
+/// The maximum number of character insertions and deletions the character diff of changed
+/// lines searches for before it falls back to replacing the lines at once.
+const MAX_CHAR_EDITS: usize = 200;
+
+/// Computes the text edits that turn `old` into `new`.
+///
+/// The texts are first compared line by line and the changed lines are then compared
+/// character by character, so that unchanged text, and with it the cursor position and the
+/// undo history of the client, is preserved. The returned edits are sorted and do not
+/// overlap. Their ranges refer to `old` and are encoded with `encoding`.
+///
+/// To keep the diff fast, large changes are replaced as a whole rather than diffed precisely.
+#[must_use]
+pub fn diff_text_edits(old: &str, new: &str, encoding: &PositionEncodingKind) -> Vec<TextEdit> {
+    let line_index = LineIndex::new(old);
+    let old_lines = Lines::new(old);
+    let new_lines = Lines::new(new);
+
+    let mut edits = vec![];
+    for (old_hunk, new_hunk) in diff_slices(&old_lines.lines, &new_lines.lines, MAX_LINE_EDITS) {
+        let old_hunk = old_lines.offsets(old_hunk);
+        let new_hunk = new_lines.offsets(new_hunk);
+        let (old_text, new_text) = (&old[old_hunk.clone()], &new[new_hunk]);
+        for (old_range, new_range) in diff_chars(old_text, new_text) {
+            let mut old_range = old_hunk.start + old_range.start..old_hunk.start + old_range.end;
+            let mut new_text = new_text[new_range].to_owned();
+            // Positions can not point between the `\r` and `\n` of a line terminator, so
+            // edits that start or end there are extended to the whole terminator.
+            if is_in_crlf(old, old_range.start) {
+                old_range.start -= 1;
+                new_text.insert(0, '\r');
+            }
+            if is_in_crlf(old, old_range.end) {
+                old_range.end += 1;
+                new_text.push('\n');
+            }
+            edits.push(TextEdit::new(
+                line_index.range(old_range, encoding),
+                new_text,
+            ));
+        }
+    }
+    edits
+}
+
+/// Whether `offset` points between the `\r` and `\n` of a line terminator.
+fn is_in_crlf(text: &str, offset: usize) -> bool {
+    offset > 0 && text[..offset].ends_with('\r') && text[offset..].starts_with('\n')
+}
+
+/// The lines of a text, including their line terminators.
+struct Lines<'a> {
+    lines: Vec<&'a str>,
+    /// The byte offset of each line, followed by the length of the text.
+    starts: Vec<usize>,
+}
+
+impl<'a> Lines<'a> {
+    fn new(text: &'a str) -> Self {
+        let lines: Vec<&str> = text.split_inclusive('\n').collect();
+        let mut starts = Vec::with_capacity(lines.len() + 1);
+        let mut start = 0;
+        starts.push(start);
+        for line in &lines {
+            start += line.len();
+            starts.push(start);
+        }
+        Self { lines, starts }
+    }
+
+    /// The byte range of a range of lines.
+    fn offsets(&self, lines: ops::Range<usize>) -> ops::Range<usize> {
+        self.starts[lines.start]..self.starts[lines.end]
+    }
+}
+
+/// Diffs the characters of two texts, returning pairs of byte ranges.
+fn diff_chars(old: &str, new: &str) -> Vec<(ops::Range<usize>, ops::Range<usize>)> {
+    let (old_chars, old_starts) = chars(old);
+    let (new_chars, new_starts) = chars(new);
+    diff_slices(&old_chars, &new_chars, MAX_CHAR_EDITS)
+        .into_iter()
+        .map(|(old_range, new_range)| {
+            (
+                old_starts[old_range.start]..old_starts[old_range.end],
+                new_starts[new_range.start]..new_starts[new_range.end],
+            )
+        })
+        .collect()
+}
+
+/// The characters of a text and the byte offset of each character, followed by the length
+/// of the text.
+fn chars(text: &str) -> (Vec<char>, Vec<usize>) {
+    let (starts, chars): (Vec<usize>, Vec<char>) = text.char_indices().unzip();
+    let mut starts = starts;
+    starts.push(text.len());
+    (chars, starts)
+}
+
+/// Diffs two sequences, returning the pairs of ranges of `old` that have to be replaced with
+/// the ranges of `new`, in order.
+///
+/// If more than `max_edits` insertions and deletions are needed, everything between the
+/// common prefix and suffix is replaced at once.
+pub fn diff_slices<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    max_edits: usize,
+) -> Vec<(ops::Range<usize>, ops::Range<usize>)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let (old_rest, new_rest) = (&old[prefix..], &new[prefix..]);
+    let suffix = old_rest
+        .iter()
+        .rev()
+        .zip(new_rest.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old_rest[..old_rest.len() - suffix];
+    let new_middle = &new_rest[..new_rest.len() - suffix];
+    if old_middle.is_empty() && new_middle.is_empty() {
+        return vec![];
+    }
+
+    let hunks = if old_middle.is_empty() || new_middle.is_empty() {
+        None
+    } else {
+        myers(old_middle, new_middle, max_edits)
+    };
+    hunks
+        .unwrap_or_else(|| vec![(0..old_middle.len(), 0..new_middle.len())])
+        .into_iter()
+        .map(|(old_range, new_range)| {
+            (
+                prefix + old_range.start..prefix + old_range.end,
+                prefix + new_range.start..prefix + new_range.end,
+            )
+        })
+        .collect()
+}
+
+/// The greedy shortest edit script algorithm of Eugene W. Myers, "An O(ND) Difference
+/// Algorithm and Its Variations".
+///
+/// Returns `None` if more than `max_edits` insertions and deletions are needed.
+#[expect(
+    clippy::suspicious_operation_groupings,
+    reason = "`x` indexes `old` and `y` indexes `new`"
+)]
+fn myers<T: PartialEq>(
+    old: &[T],
+    new: &[T],
+    max_edits: usize,
+) -> Option<Vec<(ops::Range<usize>, ops::Range<usize>)>> {
+    let (old_len, new_len) = (old.len(), new.len());
+    let max = (old_len + new_len).min(max_edits);
+    // The furthest `x` reached on each diagonal `k = x - y`, indexed by `k + offset`.
+    let offset = max + 1;
+    let mut v = vec![0; 2 * offset + 1];
+    let mut trace = vec![];
+
+    let mut end = None;
+    'search: for d in 0..=max {
+        trace.push(v.clone());
+        for k in (offset - d..=offset + d).step_by(2) {
+            let mut x = if k == offset - d || (k != offset + d && v[k - 1] < v[k + 1]) {
+                v[k + 1]
+            } else {
+                v[k - 1] + 1
+            };
+            let mut y = x + offset - k;
+            while x < old_len && y < new_len && old[x] == new[y] {
+                x += 1;
+                y += 1;
+            }
+            v[k] = x;
+            if x >= old_len && y >= new_len {
+                end = Some(d);
+                break 'search;
+            }
+        }
+    }
+    let end = end?;
+
+    // Walk back from the end to collect the single insertions and deletions.
+    let mut operations = vec![];
+    let (mut x, mut y) = (old_len, new_len);
+    for d in (1..=end).rev() {
+        let v = &trace[d];
+        let k = x + offset - y;
+        let previous_k = if k == offset - d || (k != offset + d && v[k - 1] < v[k + 1]) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let previous_x = v[previous_k];
+        let previous_y = previous_x + offset - previous_k;
+        while x > previous_x && y > previous_y {
+            x -= 1;
+            y -= 1;
+        }
+        if x == previous_x {
+            operations.push((x..x, previous_y..y));
+        } else {
+            operations.push((previous_x..x, y..y));
+        }
+        (x, y) = (previous_x, previous_y);
+    }
+    operations.reverse();
+
+    // Join adjacent insertions and deletions into hunks.
+    let mut hunks: Vec<(ops::Range<usize>, ops::Range<usize>)> = vec![];
+    for (old_range, new_range) in operations {
+        if let Some((last_old, last_new)) = hunks.last_mut()
+            && last_old.end == old_range.start
+            && last_new.end == new_range.start
+        {
+            last_old.end = old_range.end;
+            last_new.end = new_range.end;
+        } else {
+            hunks.push((old_range, new_range));
+        }
+    }
+    Some(hunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::{Position, Range};
+
+    fn apply(old: &str, edits: &[TextEdit], encoding: &PositionEncodingKind) -> String {
+        let line_index = LineIndex::new(old);
+        let mut text = old.to_owned();
+        for edit in edits.iter().rev() {
+            text.replace_range(
+                line_index.offset_range(edit.range, encoding),
+                &edit.new_text,
+            );
+        }
+        text
+    }
+
+    fn edit(start: (u32, u32), end: (u32, u32), new_text: &str) -> TextEdit {
+        TextEdit::new(
+            Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1)),
+            new_text.to_owned(),
+        )
+    }
+
+    #[test]
+    fn minimal() {
+        let utf16 = PositionEncodingKind::UTF16;
+        assert_eq!(diff_text_edits("same\n", "same\n", &utf16), vec![]);
+        assert_eq!(
+            diff_text_edits("fn  main(){\n}\n", "fn main() {\n}\n", &utf16),
+            vec![edit((0, 3), (0, 4), ""), edit((0, 10), (0, 10), " ")]
+        );
+        assert_eq!(
+            diff_text_edits("a\nb\nc\n", "a\nc\nd\n", &utf16),
+            vec![edit((1, 0), (2, 0), ""), edit((3, 0), (3, 0), "d\n")]
+        );
+        assert_eq!(
+            diff_text_edits("𐐀 = 1", "𐐀 = 2", &utf16),
+            vec![edit((0, 5), (0, 6), "2")]
+        );
+        assert_eq!(
+            diff_text_edits("𐐀 = 1", "𐐀 = 2", &PositionEncodingKind::UTF8),
+            vec![edit((0, 7), (0, 8), "2")]
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let cases = [
+            ("", "abc\n"),
+            ("abc\n", ""),
+            ("a\r\nb\r\n", "a\nb\n"),
+            (
+                "struct A{x:i32,y:i32}",
+                "struct A {\n    x: i32,\n    y: i32,\n}\n",
+            ),
+            ("ä\nö\nü\n", "ö\nä\nü\nß"),
+            ("one\ntwo\nthree\nfour\n", "zero\none\nthree\nfive\nfour\n"),
+        ];
+        for encoding in [
+            PositionEncodingKind::UTF8,
+            PositionEncodingKind::UTF16,
+            PositionEncodingKind::UTF32,
+        ] {
+            for (old, new) in cases {
+                let edits = diff_text_edits(old, new, &encoding);
+                assert_eq!(crate::text::validate_text_edits(&edits), Ok(()));
+                assert_eq!(apply(old, &edits, &encoding), new, "{old:?} -> {new:?}");
+            }
+        }
+
+        // Pseudo-random texts over a small alphabet produce many small hunks.
+        let mut seed = 17_u32;
+        let mut random_text = |len: u32| -> String {
+            (0..len)
+                .map(|_| {
+                    seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    ['a', 'b', '\n', 'ä'][(seed >> 16) as usize % 4]
+                })
+                .collect()
+        };
+        for len in 0..40 {
+            let (old, new) = (random_text(len), random_text(40 - len));
+            let edits = diff_text_edits(&old, &new, &PositionEncodingKind::UTF16);
+            assert_eq!(
+                apply(&old, &edits, &PositionEncodingKind::UTF16),
+                new,
+                "{old:?} -> {new:?}"
+            );
+        }
+
+        // Changes beyond the search limit are replaced at once.
+        let old: String = (0..3000).map(|i| i.to_string() + "\n").collect();
+        let new: String = (0..3000).map(|i| (i * 7).to_string() + "\n").collect();
+        let edits = diff_text_edits(&old, &new, &PositionEncodingKind::UTF16);
+        assert_eq!(apply(&old, &edits, &PositionEncodingKind::UTF16), new);
+    }
+}
@@ -0,0 +1,312 @@
+use std::fmt;
+
+use crate::{
+    Uri,
+    lsp::{
+        DidChangeTextDocumentParams, PositionEncodingKind, Range, TextDocumentContentChangeEvent,
+        TextDocumentItem,
+    },
+    text::LineIndex,
+};
+
+/// An error encountered while applying changes to a [`TextDocument`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextDocumentError {
+    /// The changes are for the document `uri` rather than this document.
+    UriMismatch { uri: Uri },
+    /// The version of the changes is not greater than the version of the document.
+    VersionNotIncreasing { current: i32, new: i32 },
+    /// The version of the changes skips versions although consecutive versions are required,
+    /// see [`TextDocument::require_consecutive_versions`].
+    VersionGap { current: i32, new: i32 },
+    /// The range of the content change at `index` is not within the document.
+    RangeOutOfBounds { index: usize, range: Range },
+}
+
+impl fmt::Display for TextDocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UriMismatch { uri } => {
+                write!(f, "changes for `{}` can not be applied", uri.as_str())
+            }
+            Self::VersionNotIncreasing { current, new } => write!(
+                f,
+                "version {new} is not greater than the current version {current}"
+            ),
+            Self::VersionGap { current, new } => {
+                write!(
+                    f,
+                    "version {new} does not follow the current version {current}"
+                )
+            }
+            Self::RangeOutOfBounds { index, range } => write!(
+                f,
+                "range {}:{}-{}:{} of content change {index} is out of bounds",
+                range.start.line, range.start.character, range.end.line, range.end.character
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TextDocumentError {}
+
+/// A text document that is kept in sync with the client.
+///
+/// The document is created from the [`TextDocumentItem`] of a `textDocument/didOpen`
+/// notification and updated with the content changes of `textDocument/didChange`
+/// notifications, both for [`TextDocumentSyncKind::FULL`] and
+/// [`TextDocumentSyncKind::INCREMENTAL`].
+///
+/// [`TextDocumentSyncKind::FULL`]: crate::lsp::TextDocumentSyncKind::FULL
+/// [`TextDocumentSyncKind::INCREMENTAL`]: crate::lsp::TextDocumentSyncKind::INCREMENTAL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextDocument {
+    uri: Uri,
+    language_id: String,
+    version: i32,
+    text: String,
+    line_index: LineIndex,
+    encoding: PositionEncodingKind,
+    consecutive_versions: bool,
+}
+
+impl TextDocument {
+    /// Creates a document whose positions are encoded with the negotiated `encoding`.
+    #[must_use]
+    pub fn new(item: TextDocumentItem, encoding: PositionEncodingKind) -> Self {
+        Self {
+            line_index: LineIndex::new(&item.text),
+            uri: item.uri,
+            language_id: item.language_id,
+            version: item.version,
+            text: item.text,
+            encoding,
+            consecutive_versions: false,
+        }
+    }
+
+    /// Requires each change to increase the version by exactly one.
+    ///
+    /// The specification only requires versions to strictly increase, but many clients
+    /// number them consecutively, so a gap indicates a lost notification.
+    #[must_use]
+    pub const fn require_consecutive_versions(mut self) -> Self {
+        self.consecutive_versions = true;
+        self
+    }
+
+    #[must_use]
+    pub const fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    #[must_use]
+    pub fn language_id(&self) -> &str {
+        &self.language_id
+    }
+
+    #[must_use]
+    pub const fn version(&self) -> i32 {
+        self.version
+    }
+
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    #[must_use]
+    pub const fn line_index(&self) -> &LineIndex {
+        &self.line_index
+    }
+
+    #[must_use]
+    pub const fn encoding(&self) -> &PositionEncodingKind {
+        &self.encoding
+    }
+
+    /// Applies the content changes of a `textDocument/didChange` notification in order.
+    ///
+    /// Either all changes are applied or, if an error is returned, the document is left
+    /// unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the changes are for another document, the version does not
+    /// increase or the range of a change is not within the document.
+    pub fn apply_changes(
+        &mut self,
+        params: &DidChangeTextDocumentParams,
+    ) -> Result<(), TextDocumentError> {
+        let identifier = &params.text_document;
+        if identifier.uri != self.uri {
+            return Err(TextDocumentError::UriMismatch {
+                uri: identifier.uri.clone(),
+            });
+        }
+        let (current, new) = (self.version, identifier.version);
+        if new <= current {
+            return Err(TextDocumentError::VersionNotIncreasing { current, new });
+        }
+        if self.consecutive_versions && current.checked_add(1) != Some(new) {
+            return Err(TextDocumentError::VersionGap { current, new });
+        }
+
+        match params.content_changes.as_slice() {
+            [] => {}
+            // Avoid copying the text for the common case of a single change.
+            [change] => {
+                let (text, line_index) =
+                    self.apply_change(&self.text, &self.line_index, 0, change)?;
+                self.text = text;
+                self.line_index = line_index;
+            }
+            changes => {
+                let mut text = self.text.clone();
+                let mut line_index = self.line_index.clone();
+                for (index, change) in changes.iter().enumerate() {
+                    (text, line_index) = self.apply_change(&text, &line_index, index, change)?;
+                }
+                self.text = text;
+                self.line_index = line_index;
+            }
+        }
+        self.version = new;
+        Ok(())
+    }
+
+    fn apply_change(
+        &self,
+        text: &str,
+        line_index: &LineIndex,
+        index: usize,
+        change: &TextDocumentContentChangeEvent,
+    ) -> Result<(String, LineIndex), TextDocumentError> {
+        let text = match change.range {
+            None => change.text.clone(),
+            Some(range) => {
+                let offsets = line_index
+                    .checked_offset_range(range, &self.encoding)
+                    .ok_or(TextDocumentError::RangeOutOfBounds { index, range })?;
+                let mut new_text =
+                    String::with_capacity(text.len() - offsets.len() + change.text.len());
+                new_text.push_str(&text[..offsets.start]);
+                new_text.push_str(&change.text);
+                new_text.push_str(&text[offsets.end..]);
+                new_text
+            }
+        };
+        let line_index = LineIndex::new(&text);
+        Ok((text, line_index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::{Position, VersionedTextDocumentIdentifier};
+
+    fn uri() -> Uri {
+        "file:///a.txt".parse().unwrap()
+    }
+
+    fn document(text: &str) -> TextDocument {
+        TextDocument::new(
+            TextDocumentItem::new(uri(), "plaintext".to_owned(), 1, text.to_owned()),
+            PositionEncodingKind::UTF16,
+        )
+    }
+
+    fn change(range: Option<Range>, text: &str) -> TextDocumentContentChangeEvent {
+        TextDocumentContentChangeEvent {
+            range,
+            range_length: None,
+            text: text.to_owned(),
+        }
+    }
+
+    fn params(
+        version: i32,
+        changes: Vec<TextDocumentContentChangeEvent>,
+    ) -> DidChangeTextDocumentParams {
+        DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri(), version),
+            content_changes: changes,
+        }
+    }
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+        Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+    }
+
+    #[test]
+    fn incremental() {
+        let mut document = document("fn 𐐀() {\n}\n");
+        document
+            .apply_changes(&params(
+                2,
+                vec![
+                    change(Some(range((0, 3), (0, 5))), "main"),
+                    change(Some(range((1, 0), (1, 0))), "    todo!();\n"),
+                    change(Some(range((3, 0), (3, 0))), "// end"),
+                ],
+            ))
+            .unwrap();
+        assert_eq!(document.text(), "fn main() {\n    todo!();\n}\n// end");
+        assert_eq!(document.version(), 2);
+        assert_eq!(document.line_index().line_count(), 4);
+    }
+
+    #[test]
+    fn full() {
+        let mut document = document("a\nb");
+        document
+            .apply_changes(&params(
+                5,
+                vec![change(Some(range((0, 0), (0, 1))), "c"), change(None, "d")],
+            ))
+            .unwrap();
+        assert_eq!(document.text(), "d");
+        assert_eq!(document.version(), 5);
+    }
+
+    #[test]
+    fn errors() {
+        let mut document = document("abc");
+
+        assert_eq!(
+            document.apply_changes(&params(1, vec![change(None, "")])),
+            Err(TextDocumentError::VersionNotIncreasing { current: 1, new: 1 })
+        );
+
+        let out_of_bounds = range((0, 1), (0, 4));
+        assert_eq!(
+            document.apply_changes(&params(
+                2,
+                vec![change(None, "x"), change(Some(out_of_bounds), "")]
+            )),
+            Err(TextDocumentError::RangeOutOfBounds {
+                index: 1,
+                range: out_of_bounds
+            })
+        );
+        // The document is left unchanged.
+        assert_eq!(document.text(), "abc");
+        assert_eq!(document.version(), 1);
+
+        let mut other = params(2, vec![]);
+        other.text_document.uri = "file:///b.txt".parse().unwrap();
+        assert!(matches!(
+            document.apply_changes(&other),
+            Err(TextDocumentError::UriMismatch { .. })
+        ));
+
+        let mut document = document.require_consecutive_versions();
+        assert_eq!(
+            document.apply_changes(&params(3, vec![])),
+            Err(TextDocumentError::VersionGap { current: 1, new: 3 })
+        );
+        document.apply_changes(&params(2, vec![])).unwrap();
+        assert_eq!(document.version(), 2);
+    }
+}
@@ -0,0 +1,352 @@
+use std::fmt;
+
+use crate::lsp::{AnnotatedTextEdit, OneOf, Position, Range, TextDocumentEditItem, TextEdit};
+
+/// An edit of a range of a document, usually a [`TextEdit`].
+pub trait AsTextEdit {
+    /// The range the edit replaces.
+    fn range(&self) -> Range;
+
+    /// The wrapped text edit, or `None` for an edit that is not a plain text edit, such as a
+    /// snippet edit, which is never merged with other edits.
+    fn text_edit(&self) -> Option<&TextEdit>;
+
+    fn text_edit_mut(&mut self) -> Option<&mut TextEdit>;
+
+    /// The change annotation of the edit, if any.
+    fn annotation_id(&self) -> Option<&str> {
+        None
+    }
+}
+
+impl AsTextEdit for TextEdit {
+    fn range(&self) -> Range {
+        self.range
+    }
+
+    fn text_edit(&self) -> Option<&TextEdit> {
+        Some(self)
+    }
+
+    fn text_edit_mut(&mut self) -> Option<&mut TextEdit> {
+        Some(self)
+    }
+}
+
+impl AsTextEdit for AnnotatedTextEdit {
+    fn range(&self) -> Range {
+        self.text_edit.range
+    }
+
+    fn text_edit(&self) -> Option<&TextEdit> {
+        Some(&self.text_edit)
+    }
+
+    fn text_edit_mut(&mut self) -> Option<&mut TextEdit> {
+        Some(&mut self.text_edit)
+    }
+
+    fn annotation_id(&self) -> Option<&str> {
+        Some(&self.annotation_id)
+    }
+}
+
+impl AsTextEdit for OneOf<TextEdit, AnnotatedTextEdit> {
+    fn range(&self) -> Range {
+        match self {
+            Self::Left(edit) => edit.range,
+            Self::Right(edit) => edit.text_edit.range,
+        }
+    }
+
+    fn text_edit(&self) -> Option<&TextEdit> {
+        match self {
+            Self::Left(edit) => Some(edit),
+            Self::Right(edit) => Some(&edit.text_edit),
+        }
+    }
+
+    fn text_edit_mut(&mut self) -> Option<&mut TextEdit> {
+        match self {
+            Self::Left(edit) => Some(edit),
+            Self::Right(edit) => Some(&mut edit.text_edit),
+        }
+    }
+
+    fn annotation_id(&self) -> Option<&str> {
+        match self {
+            Self::Left(_) => None,
+            Self::Right(edit) => Some(&edit.annotation_id),
+        }
+    }
+}
+
+impl AsTextEdit for TextDocumentEditItem {
+    fn range(&self) -> Range {
+        match self {
+            Self::Annotated(edit) => edit.text_edit.range,
+            Self::Snippet(edit) => edit.range,
+            Self::Text(edit) => edit.range,
+        }
+    }
+
+    fn text_edit(&self) -> Option<&TextEdit> {
+        match self {
+            Self::Annotated(edit) => Some(&edit.text_edit),
+            Self::Snippet(_) => None,
+            Self::Text(edit) => Some(edit),
+        }
+    }
+
+    fn text_edit_mut(&mut self) -> Option<&mut TextEdit> {
+        match self {
+            Self::Annotated(edit) => Some(&mut edit.text_edit),
+            Self::Snippet(_) => None,
+            Self::Text(edit) => Some(edit),
+        }
+    }
+
+    fn annotation_id(&self) -> Option<&str> {
+        match self {
+            Self::Annotated(edit) => Some(&edit.annotation_id),
+            Self::Snippet(edit) => edit.annotation_id.as_deref(),
+            Self::Text(_) => None,
+        }
+    }
+}
+
+/// An error in a list of text edits that are applied together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEditError {
+    /// The end of the range of the edit at `index` is before its start.
+    ReversedRange { index: usize },
+    /// The ranges of the edits at `first` and `second` overlap.
+    Overlapping { first: usize, second: usize },
+}
+
+impl fmt::Display for TextEditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ReversedRange { index } => {
+                write!(f, "the range of edit {index} ends before it starts")
+            }
+            Self::Overlapping { first, second } => {
+                write!(f, "the ranges of edits {first} and {second} overlap")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TextEditError {}
+
+/// Checks that the edits can be applied together.
+///
+/// No range may end before it starts and no two ranges may overlap.
+/// Ranges that only touch do not overlap, so multiple inserts at the same position and an
+/// insert at the start or end of a replaced range are valid.
+///
+/// # Errors
+///
+/// Returns the first reversed range or the first pair of overlapping ranges in document order.
+pub fn validate_text_edits<E: AsTextEdit>(edits: &[E]) -> Result<(), TextEditError> {
+    if let Some(index) = edits.iter().position(|edit| {
+        let range = edit.range();
+        range.end < range.start
+    }) {
+        return Err(TextEditError::ReversedRange { index });
+    }
+
+    let mut order: Vec<usize> = (0..edits.len()).collect();
+    order.sort_by_key(|&index| sort_key(&edits[index]));
+    for pair in order.windows(2) {
+        let (first, second) = (pair[0], pair[1]);
+        if edits[second].range().start < edits[first].range().end {
+            return Err(TextEditError::Overlapping { first, second });
+        }
+    }
+    Ok(())
+}
+
+/// Sorts the edits into the canonical order.
+///
+/// Edits are ordered by the start of their range and inserts come before a replacement at
+/// the same position. Inserts at the same position keep the order they were given in, which
+/// is the order in which their texts appear in the result.
+pub fn sort_text_edits<E: AsTextEdit>(edits: &mut [E]) {
+    edits.sort_by_key(sort_key);
+}
+
+/// Validates and sorts the edits, then merges text edits whose ranges touch and whose change
+/// annotations are equal into a single edit. Snippet edits are kept as they are.
+///
+/// # Errors
+///
+/// Returns an error if the edits are not valid, see [`validate_text_edits`].
+pub fn merge_text_edits<E: AsTextEdit>(mut edits: Vec<E>) -> Result<Vec<E>, TextEditError> {
+    validate_text_edits(&edits)?;
+    sort_text_edits(&mut edits);
+
+    let mut merged: Vec<E> = Vec::with_capacity(edits.len());
+    for edit in edits {
+        if let Some(last) = merged.last_mut()
+            && last.annotation_id() == edit.annotation_id()
+            && let (Some(last), Some(next)) = (last.text_edit_mut(), edit.text_edit())
+            && last.range.end == next.range.start
+        {
+            last.range.end = next.range.end;
+            last.new_text.push_str(&next.new_text);
+            continue;
+        }
+        merged.push(edit);
+    }
+    Ok(merged)
+}
+
+fn sort_key<E: AsTextEdit>(edit: &E) -> (Position, Position) {
+    let range = edit.range();
+    (range.start, range.end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lsp::{
+        OptionalVersionedTextDocumentIdentifier, SnippetTextEdit, StringValue, TextDocumentEdit,
+    };
+
+    fn edit(start: u32, end: u32, new_text: &str) -> TextEdit {
+        TextEdit::new(
+            Range::new(Position::new(0, start), Position::new(0, end)),
+            new_text.to_owned(),
+        )
+    }
+
+    #[test]
+    fn validate() {
+        assert_eq!(
+            validate_text_edits(&[
+                edit(4, 6, "a"),
+                edit(0, 2, "b"),
+                edit(2, 2, "c"),
+                edit(2, 2, "d")
+            ]),
+            Ok(())
+        );
+        assert_eq!(
+            validate_text_edits(&[edit(4, 6, "a"), edit(0, 2, "b"), edit(1, 5, "c")]),
+            Err(TextEditError::Overlapping {
+                first: 1,
+                second: 2
+            })
+        );
+        assert_eq!(
+            validate_text_edits(&[edit(0, 1, ""), edit(0, 1, "")]),
+            Err(TextEditError::Overlapping {
+                first: 0,
+                second: 1
+            })
+        );
+        assert_eq!(
+            validate_text_edits(&[edit(0, 1, ""), edit(3, 2, "")]),
+            Err(TextEditError::ReversedRange { index: 1 })
+        );
+    }
+
+    #[test]
+    fn sort() {
+        let mut edits = vec![
+            edit(2, 3, "a"),
+            edit(2, 2, "b"),
+            edit(0, 1, "c"),
+            edit(2, 2, "d"),
+        ];
+        sort_text_edits(&mut edits);
+        assert_eq!(
+            edits,
+            vec![
+                edit(0, 1, "c"),
+                edit(2, 2, "b"),
+                edit(2, 2, "d"),
+                edit(2, 3, "a")
+            ]
+        );
+    }
+
+    #[test]
+    fn merge() {
+        let annotated = |start, end, new_text, annotation_id: &str| {
+            OneOf::Right(AnnotatedTextEdit {
+                text_edit: edit(start, end, new_text),
+                annotation_id: annotation_id.to_owned(),
+            })
+        };
+        let edits = vec![
+            OneOf::Left(edit(2, 3, "b")),
+            OneOf::Left(edit(0, 2, "a")),
+            OneOf::Left(edit(5, 6, "c")),
+            annotated(6, 7, "d", "x"),
+            annotated(7, 7, "e", "x"),
+            annotated(7, 8, "f", "y"),
+        ];
+        assert_eq!(
+            merge_text_edits(edits),
+            Ok(vec![
+                OneOf::Left(edit(0, 3, "ab")),
+                OneOf::Left(edit(5, 6, "c")),
+                annotated(6, 7, "de", "x"),
+                annotated(7, 8, "f", "y"),
+            ])
+        );
+    }
+
+    #[test]
+    fn text_document_edit() {
+        let snippet = |start, end| {
+            TextDocumentEditItem::Snippet(SnippetTextEdit {
+                range: Range::new(Position::new(0, start), Position::new(0, end)),
+                snippet: StringValue::new("$1".to_owned()),
+                annotation_id: None,
+            })
+        };
+        let mut text_document_edit = TextDocumentEdit {
+            text_document: OptionalVersionedTextDocumentIdentifier {
+                uri: "file:///a.rs".parse().unwrap(),
+                version: None,
+            },
+            edits: vec![
+                snippet(2, 3),
+                edit(1, 2, "b").into(),
+                edit(0, 1, "a").into(),
+                edit(3, 4, "c").into(),
+            ],
+        };
+        assert_eq!(validate_text_edits(&text_document_edit.edits), Ok(()));
+        assert_eq!(
+            validate_text_edits(&[snippet(2, 4), edit(3, 5, "").into()]),
+            Err(TextEditError::Overlapping {
+                first: 0,
+                second: 1
+            })
+        );
+
+        sort_text_edits(&mut text_document_edit.edits);
+        assert_eq!(
+            text_document_edit.edits,
+            vec![
+                edit(0, 1, "a").into(),
+                edit(1, 2, "b").into(),
+                snippet(2, 3),
+                edit(3, 4, "c").into(),
+            ]
+        );
+
+        assert_eq!(
+            merge_text_edits(text_document_edit.edits),
+            Ok(vec![
+                edit(0, 2, "ab").into(),
+                snippet(2, 3),
+                edit(3, 4, "c").into(),
+            ])
+        );
+    }
+}
@@ -0,0 +1,393 @@
+use std::ops;
+
+use crate::lsp::{Position, PositionEncodingKind, Range};
+
+/// The code units a [`PositionEncodingKind`] counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Utf8,
+    Utf16,
+    Utf32,
+}
+
+impl Encoding {
+    /// Unknown encodings fall back to UTF-16, which every client and server must support.
+    fn of(kind: &PositionEncodingKind) -> Self {
+        match kind.as_str() {
+            "utf-8" => Self::Utf8,
+            "utf-32" => Self::Utf32,
+            _ => Self::Utf16,
+        }
+    }
+
+    /// The number of code units of a character of `len_utf8` bytes.
+    const fn units(self, len_utf8: usize) -> usize {
+        match self {
+            Self::Utf8 => len_utf8,
+            Self::Utf16 if len_utf8 == 4 => 2,
+            Self::Utf16 | Self::Utf32 => 1,
+        }
+    }
+}
+
+/// A character that is encoded with more than one UTF-8 code unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct WideChar {
+    /// The byte offset of the character in the text.
+    offset: usize,
+    /// The number of UTF-8 code units of the character.
+    len: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Line {
+    /// The byte offset of the first character of the line.
+    start: usize,
+    /// The byte offset of the line terminator, or of the end of the text for the last line.
+    end: usize,
+}
+
+/// Maps between byte offsets in a text and [`Position`]s in any [`PositionEncodingKind`].
+///
+/// Lines are terminated by `\n`, `\r\n` or `\r`, as required by the specification.
+///
+/// Positions are resolved leniently: a `character` beyond the end of its line defaults back
+/// to the end of the line, a `line` beyond the last line defaults to the end of the text and a
+/// position in the middle of a character defaults to the start of that character. The
+/// `checked_*` methods return `None` for such positions instead.
+///
+/// Unknown position encodings are treated as [`PositionEncodingKind::UTF16`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    lines: Vec<Line>,
+    /// The characters of the text that are wider than one byte, ordered by offset.
+    wide_chars: Vec<WideChar>,
+    len: usize,
+}
+
+impl LineIndex {
+    #[must_use]
+    pub fn new(text: &str) -> Self {
+        let mut lines = vec![];
+        let mut wide_chars = vec![];
+        let mut start = 0;
+        let mut chars = text.char_indices().peekable();
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '\n' => {
+                    lines.push(Line { start, end: offset });
+                    start = offset + 1;
+                }
+                '\r' => {
+                    lines.push(Line { start, end: offset });
+                    start = offset + 1;
+                    if chars.next_if(|&(_, c)| c == '\n').is_some() {
+                        start += 1;
+                    }
+                }
+                _ if !c.is_ascii() => wide_chars.push(WideChar {
+                    offset,
+                    len: c.len_utf8(),
+                }),
+                _ => {}
+            }
+        }
+        lines.push(Line {
+            start,
+            end: text.len(),
+        });
+
+        Self {
+            lines,
+            wide_chars,
+            len: text.len(),
+        }
+    }
+
+    /// The number of lines of the text. This is always at least one.
+    #[must_use]
+    pub const fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// The length of the text in bytes.
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The byte range of `line`, excluding its line terminator.
+    #[must_use]
+    pub fn line_range(&self, line: u32) -> Option<ops::Range<usize>> {
+        let line = self.lines.get(usize::try_from(line).ok()?)?;
+        Some(line.start..line.end)
+    }
+
+    /// The length of `line` in code units of `encoding`, excluding its line terminator.
+    #[must_use]
+    pub fn line_len(&self, line: u32, encoding: &PositionEncodingKind) -> Option<u32> {
+        let line = *self.lines.get(usize::try_from(line).ok()?)?;
+        Some(self.column(line, line.end, Encoding::of(encoding)))
+    }
+
+    /// Converts `position` to a byte offset, clamping it into the text.
+    #[must_use]
+    pub fn offset(&self, position: Position, encoding: &PositionEncodingKind) -> usize {
+        self.resolve(position, Encoding::of(encoding))
+            .unwrap_or_else(|clamped| clamped)
+    }
+
+    /// Converts `position` to a byte offset.
+    ///
+    /// Returns `None` if the position is beyond the end of its line, beyond the last line or in
+    /// the middle of a character. The position directly after the last line, `(line_count, 0)`,
+    /// is accepted as the end of the text.
+    #[must_use]
+    pub fn checked_offset(
+        &self,
+        position: Position,
+        encoding: &PositionEncodingKind,
+    ) -> Option<usize> {
+        self.resolve(position, Encoding::of(encoding)).ok()
+    }
+
+    /// Converts `range` to a byte range, clamping it into the text.
+    ///
+    /// An end before the start is treated as an empty range at the start.
+    #[must_use]
+    pub fn offset_range(&self, range: Range, encoding: &PositionEncodingKind) -> ops::Range<usize> {
+        let start = self.offset(range.start, encoding);
+        let end = self.offset(range.end, encoding);
+        start..end.max(start)
+    }
+
+    /// Converts `range` to a byte range.
+    ///
+    /// Returns `None` if either position is invalid, see [`Self::checked_offset`], or the end is
+    /// before the start.
+    #[must_use]
+    pub fn checked_offset_range(
+        &self,
+        range: Range,
+        encoding: &PositionEncodingKind,
+    ) -> Option<ops::Range<usize>> {
+        let start = self.checked_offset(range.start, encoding)?;
+        let end = self.checked_offset(range.end, encoding)?;
+        (start <= end).then_some(start..end)
+    }
+
+    /// Converts a byte offset to a [`Position`].
+    ///
+    /// Offsets beyond the end of the text are clamped to the end of the text, offsets in the
+    /// middle of a character or a `\r\n` line terminator are moved to its start.
+    #[must_use]
+    pub fn position(&self, offset: usize, encoding: &PositionEncodingKind) -> Position {
+        let offset = offset.min(self.len);
+        let line_number = self.lines.partition_point(|line| line.start <= offset) - 1;
+        let line = self.lines[line_number];
+        let mut offset = offset.min(line.end);
+        if let Some(wide_char) = self.wide_char_at(offset) {
+            offset = wide_char.offset;
+        }
+        Position::new(
+            saturating_u32(line_number),
+            self.column(line, offset, Encoding::of(encoding)),
+        )
+    }
+
+    /// Converts a byte range to a [`Range`], see [`Self::position`].
+    #[must_use]
+    pub fn range(&self, range: ops::Range<usize>, encoding: &PositionEncodingKind) -> Range {
+        Range::new(
+            self.position(range.start, encoding),
+            self.position(range.end, encoding),
+        )
+    }
+
+    /// Converts `position` from the encoding `from` to the encoding `to`.
+    #[must_use]
+    pub fn convert_position(
+        &self,
+        position: Position,
+        from: &PositionEncodingKind,
+        to: &PositionEncodingKind,
+    ) -> Position {
+        self.position(self.offset(position, from), to)
+    }
+
+    /// Converts `range` from the encoding `from` to the encoding `to`.
+    #[must_use]
+    pub fn convert_range(
+        &self,
+        range: Range,
+        from: &PositionEncodingKind,
+        to: &PositionEncodingKind,
+    ) -> Range {
+        Range::new(
+            self.convert_position(range.start, from, to),
+            self.convert_position(range.end, from, to),
+        )
+    }
+
+    /// Resolves `position` to the exact byte offset it denotes, or the clamped offset if it is
+    /// not valid.
+    fn resolve(&self, position: Position, encoding: Encoding) -> Result<usize, usize> {
+        let line_number = usize::try_from(position.line).unwrap_or(usize::MAX);
+        let Some(&line) = self.lines.get(line_number) else {
+            return if line_number == self.lines.len() && position.character == 0 {
+                Ok(self.len)
+            } else {
+                Err(self.len)
+            };
+        };
+
+        let mut remaining = usize::try_from(position.character).unwrap_or(usize::MAX);
+        let mut offset = line.start;
+        for wide_char in self.wide_chars_in(line.start..line.end) {
+            let narrow = wide_char.offset - offset;
+            if remaining <= narrow {
+                return Ok(offset + remaining);
+            }
+            remaining -= narrow;
+
+            let units = encoding.units(wide_char.len);
+            if remaining < units {
+                return Err(wide_char.offset);
+            }
+            remaining -= units;
+            offset = wide_char.offset + wide_char.len;
+        }
+
+        if remaining <= line.end - offset {
+            Ok(offset + remaining)
+        } else {
+            Err(line.end)
+        }
+    }
+
+    /// The column of the byte `offset` in `line`, which must be on a character boundary.
+    fn column(&self, line: Line, offset: usize, encoding: Encoding) -> u32 {
+        let extra: usize = self
+            .wide_chars_in(line.start..offset)
+            .iter()
+            .map(|wide_char| wide_char.len - encoding.units(wide_char.len))
+            .sum();
+        saturating_u32(offset - line.start - extra)
+    }
+
+    fn wide_chars_in(&self, range: ops::Range<usize>) -> &[WideChar] {
+        let start = self
+            .wide_chars
+            .partition_point(|wide_char| wide_char.offset < range.start);
+        let end = self
+            .wide_chars
+            .partition_point(|wide_char| wide_char.offset < range.end);
+        &self.wide_chars[start..end]
+    }
+
+    /// The wide character that `offset` points into, excluding its first byte.
+    fn wide_char_at(&self, offset: usize) -> Option<WideChar> {
+        let index = self
+            .wide_chars
+            .partition_point(|wide_char| wide_char.offset < offset);
+        let wide_char = *self.wide_chars.get(index.checked_sub(1)?)?;
+        (offset < wide_char.offset + wide_char.len).then_some(wide_char)
+    }
+}
+
+fn saturating_u32(value: usize) -> u32 {
+    u32::try_from(value).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UTF8: PositionEncodingKind = PositionEncodingKind::UTF8;
+    const UTF16: PositionEncodingKind = PositionEncodingKind::UTF16;
+    const UTF32: PositionEncodingKind = PositionEncodingKind::UTF32;
+
+    #[test]
+    fn line_terminators() {
+        let index = LineIndex::new("a\nb\r\nc\rd");
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.line_range(0), Some(0..1));
+        assert_eq!(index.line_range(1), Some(2..3));
+        assert_eq!(index.line_range(2), Some(5..6));
+        assert_eq!(index.line_range(3), Some(7..8));
+        assert_eq!(index.line_range(4), None);
+
+        assert_eq!(LineIndex::new("").line_count(), 1);
+        assert_eq!(LineIndex::new("a\n").line_count(), 2);
+    }
+
+    #[test]
+    fn encodings() {
+        // `ä` is 2 UTF-8 code units and 1 UTF-16 code unit, `𐐀` is 4 UTF-8 code units and
+        // 2 UTF-16 code units.
+        let text = "aä𐐀b\n𐐀";
+        let index = LineIndex::new(text);
+        let b = text.find('b').unwrap();
+
+        assert_eq!(index.offset(Position::new(0, 7), &UTF8), b);
+        assert_eq!(index.offset(Position::new(0, 4), &UTF16), b);
+        assert_eq!(index.offset(Position::new(0, 3), &UTF32), b);
+        assert_eq!(
+            index.offset(Position::new(0, 2), &"unknown".into()),
+            text.find('𐐀').unwrap()
+        );
+
+        assert_eq!(index.position(b, &UTF8), Position::new(0, 7));
+        assert_eq!(index.position(b, &UTF16), Position::new(0, 4));
+        assert_eq!(index.position(b, &UTF32), Position::new(0, 3));
+
+        assert_eq!(index.line_len(0, &UTF8), Some(8));
+        assert_eq!(index.line_len(0, &UTF16), Some(5));
+        assert_eq!(index.line_len(1, &UTF32), Some(1));
+
+        assert_eq!(
+            index.convert_range(
+                Range::new(Position::new(0, 2), Position::new(1, 2)),
+                &UTF16,
+                &UTF8
+            ),
+            Range::new(Position::new(0, 3), Position::new(1, 4))
+        );
+    }
+
+    #[test]
+    fn clamping() {
+        let text = "a𐐀\r\nb";
+        let index = LineIndex::new(text);
+
+        // Beyond the end of the line.
+        assert_eq!(index.offset(Position::new(0, 10), &UTF16), 5);
+        assert_eq!(index.checked_offset(Position::new(0, 10), &UTF16), None);
+        assert_eq!(index.checked_offset(Position::new(0, 3), &UTF16), Some(5));
+
+        // In the middle of a surrogate pair or a UTF-8 sequence.
+        assert_eq!(index.offset(Position::new(0, 2), &UTF16), 1);
+        assert_eq!(index.checked_offset(Position::new(0, 2), &UTF16), None);
+        assert_eq!(index.offset(Position::new(0, 3), &UTF8), 1);
+        assert_eq!(index.position(3, &UTF16), Position::new(0, 1));
+
+        // In the middle of a line terminator.
+        assert_eq!(index.position(6, &UTF16), Position::new(0, 3));
+
+        // Beyond the last line.
+        assert_eq!(index.checked_offset(Position::new(2, 0), &UTF16), Some(8));
+        assert_eq!(index.checked_offset(Position::new(2, 1), &UTF16), None);
+        assert_eq!(index.offset(Position::new(5, 0), &UTF16), 8);
+        assert_eq!(index.position(100, &UTF16), Position::new(1, 1));
+
+        // Reversed ranges.
+        let reversed = Range::new(Position::new(1, 0), Position::new(0, 0));
+        assert_eq!(index.offset_range(reversed, &UTF16), 7..7);
+        assert_eq!(index.checked_offset_range(reversed, &UTF16), None);
+    }
+}
@@ -0,0 +1,12 @@
+//! Utilities for working with the text of documents.
+
+mod diff;
+mod document;
+mod edits;
+mod line_index;
+
+pub(crate) use diff::diff_slices;
+pub use diff::diff_text_edits;
+pub use document::*;
+pub use edits::*;
+pub use line_index::*;
@@ -0,0 +1,718 @@
+//! An in-memory file system that [`WorkspaceEdit`]s can be applied to.
+//!
+//! This is useful to test servers that compute workspace edits, for example for
+//! `textDocument/rename` or `textDocument/codeAction`, without an editor.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fmt, ops,
+};
+
+use crate::{
+    Uri,
+    lsp::{
+        ApplyWorkspaceEditResponse, CreateFile, DeleteFile, DocumentChangeOperation,
+        DocumentChanges, FailureHandlingKind, PositionEncodingKind, Range, RenameFile, ResourceOp,
+        TextDocumentEdit, TextDocumentEditItem, TextEdit, WorkspaceEdit,
+    },
+    snippet::Snippet,
+    text::LineIndex,
+};
+
+/// The reason a change of a [`WorkspaceEdit`] could not be applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VfsErrorKind {
+    /// The file or folder does not exist.
+    NotFound(Uri),
+    /// The file or folder already exists.
+    AlreadyExists(Uri),
+    /// The folder is not empty and the deletion is not recursive.
+    FolderNotEmpty(Uri),
+    /// The version of a text document edit does not match the version of the file.
+    VersionMismatch {
+        uri: Uri,
+        expected: i32,
+        actual: i32,
+    },
+    /// The range of a text edit is not within the file.
+    RangeOutOfBounds { uri: Uri, range: Range },
+    /// The range of a text edit overlaps the range of another edit of the same file.
+    OverlappingEdits { uri: Uri, range: Range },
+    /// Renaming a folder produced an invalid URI.
+    InvalidUri(String),
+}
+
+impl fmt::Display for VfsErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(uri) => write!(f, "`{}` does not exist", uri.as_str()),
+            Self::AlreadyExists(uri) => write!(f, "`{}` already exists", uri.as_str()),
+            Self::FolderNotEmpty(uri) => write!(f, "folder `{}` is not empty", uri.as_str()),
+            Self::VersionMismatch {
+                uri,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "expected version {expected} of `{}` but found version {actual}",
+                uri.as_str()
+            ),
+            Self::RangeOutOfBounds { uri, range } => write!(
+                f,
+                "range {}:{}-{}:{} is out of bounds in `{}`",
+                range.start.line,
+                range.start.character,
+                range.end.line,
+                range.end.character,
+                uri.as_str()
+            ),
+            Self::OverlappingEdits { uri, range } => write!(
+                f,
+                "edit at {}:{}-{}:{} overlaps another edit in `{}`",
+                range.start.line,
+                range.start.character,
+                range.end.line,
+                range.end.character,
+                uri.as_str()
+            ),
+            Self::InvalidUri(uri) => write!(f, "invalid uri `{uri}`"),
+        }
+    }
+}
+
+/// An error encountered while applying a [`WorkspaceEdit`] to a [`Vfs`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VfsError {
+    /// The index of the change that failed.
+    pub failed_change: usize,
+    /// Whether the changes applied before the failed change were rolled back.
+    pub rolled_back: bool,
+    pub kind: VfsErrorKind,
+}
+
+impl fmt::Display for VfsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "change {} failed: {}", self.failed_change, self.kind)
+    }
+}
+
+impl std::error::Error for VfsError {}
+
+impl From<VfsError> for ApplyWorkspaceEditResponse {
+    fn from(from: VfsError) -> Self {
+        Self {
+            applied: false,
+            failure_reason: Some(from.kind.to_string()),
+            failed_change: u32::try_from(from.failed_change).ok(),
+        }
+    }
+}
+
+/// A single change of a [`WorkspaceEdit`], in the order they are applied.
+enum Change<'a> {
+    Edit {
+        uri: &'a Uri,
+        version: Option<i32>,
+        edits: Vec<Cow<'a, TextEdit>>,
+    },
+    Op(&'a ResourceOp),
+}
+
+impl<'a> Change<'a> {
+    fn from_document_edit(document_edit: &'a TextDocumentEdit) -> Self {
+        let edits = document_edit
+            .edits
+            .iter()
+            .map(|edit| match edit {
+                TextDocumentEditItem::Annotated(annotated) => Cow::Borrowed(&annotated.text_edit),
+                TextDocumentEditItem::Snippet(snippet) => Cow::Owned(TextEdit::new(
+                    snippet.range,
+                    Snippet::parse(&snippet.snippet.value).render(|_| None).text,
+                )),
+                TextDocumentEditItem::Text(edit) => Cow::Borrowed(edit),
+            })
+            .collect();
+        Self::Edit {
+            uri: &document_edit.text_document.uri,
+            version: document_edit.text_document.version,
+            edits,
+        }
+    }
+}
+
+/// An in-memory file system mapping [`Uri`]s to the text of files.
+///
+/// Folders are not stored explicitly: a folder exists as long as it contains a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vfs {
+    files: BTreeMap<Uri, String>,
+    versions: HashMap<Uri, i32>,
+    encoding: PositionEncodingKind,
+}
+
+impl Default for Vfs {
+    fn default() -> Self {
+        Self::new(PositionEncodingKind::UTF16)
+    }
+}
+
+impl Vfs {
+    /// Creates an empty file system whose text edits are encoded with `encoding`.
+    #[must_use]
+    pub fn new(encoding: PositionEncodingKind) -> Self {
+        Self {
+            files: BTreeMap::new(),
+            versions: HashMap::new(),
+            encoding,
+        }
+    }
+
+    /// Inserts a file, returning its previous text.
+    pub fn insert(&mut self, uri: Uri, text: String) -> Option<String> {
+        self.files.insert(uri, text)
+    }
+
+    /// Removes a file, returning its text.
+    pub fn remove(&mut self, uri: &Uri) -> Option<String> {
+        self.versions.remove(uri);
+        self.files.remove(uri)
+    }
+
+    #[must_use]
+    pub fn get(&self, uri: &Uri) -> Option<&str> {
+        self.files.get(uri).map(String::as_str)
+    }
+
+    /// The files ordered by their URIs.
+    pub fn files(&self) -> impl Iterator<Item = (&Uri, &str)> {
+        self.files.iter().map(|(uri, text)| (uri, text.as_str()))
+    }
+
+    /// Sets the version of a file.
+    ///
+    /// The versions of text document edits are only checked for files with a version. The
+    /// version is incremented whenever text edits are applied to the file.
+    pub fn set_version(&mut self, uri: Uri, version: i32) {
+        self.versions.insert(uri, version);
+    }
+
+    #[must_use]
+    pub fn version(&self, uri: &Uri) -> Option<i32> {
+        self.versions.get(uri).copied()
+    }
+
+    /// Applies `edit` with the semantics of the client's `failure_handling` strategy.
+    ///
+    /// `document_changes` are preferred over `changes` if both are present. The entries of
+    /// `changes` are applied ordered by their URIs. Snippet edits insert the text of the
+    /// snippet as [`Snippet::render`] expands it, without its tabstops.
+    ///
+    /// On failure, [`FailureHandlingKind::Abort`] keeps the changes applied before the failed
+    /// change, [`FailureHandlingKind::Transactional`] and [`FailureHandlingKind::Undo`] roll
+    /// them back and [`FailureHandlingKind::TextOnlyTransactional`] rolls them back only if
+    /// the edit does not contain resource operations.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error with the index of the change that could not be applied.
+    pub fn apply(
+        &mut self,
+        edit: &WorkspaceEdit,
+        failure_handling: FailureHandlingKind,
+    ) -> Result<(), VfsError> {
+        let changes: Vec<Change<'_>> = match (&edit.document_changes, &edit.changes) {
+            (Some(DocumentChanges::Edits(edits)), _) => {
+                edits.iter().map(Change::from_document_edit).collect()
+            }
+            (Some(DocumentChanges::Operations(operations)), _) => operations
+                .iter()
+                .map(|operation| match operation {
+                    DocumentChangeOperation::Op(op) => Change::Op(op),
+                    DocumentChangeOperation::Edit(edit) => Change::from_document_edit(edit),
+                })
+                .collect(),
+            (None, Some(changes)) => {
+                let mut changes: Vec<_> = changes
+                    .iter()
+                    .map(|(uri, edits)| Change::Edit {
+                        uri,
+                        version: None,
+                        edits: edits.iter().map(Cow::Borrowed).collect(),
+                    })
+                    .collect();
+                changes.sort_by_key(|change| match change {
+                    Change::Edit { uri, .. } => Some(*uri),
+                    Change::Op(_) => None,
+                });
+                changes
+            }
+            (None, None) => vec![],
+        };
+
+        let transactional = match failure_handling {
+            FailureHandlingKind::Abort => false,
+            FailureHandlingKind::Transactional | FailureHandlingKind::Undo => true,
+            FailureHandlingKind::TextOnlyTransactional => changes
+                .iter()
+                .all(|change| !matches!(change, Change::Op(_))),
+        };
+        let snapshot = transactional.then(|| (self.files.clone(), self.versions.clone()));
+
+        for (index, change) in changes.into_iter().enumerate() {
+            let result = match change {
+                Change::Edit {
+                    uri,
+                    version,
+                    edits,
+                } => self.apply_text_edits(uri, version, &edits),
+                Change::Op(ResourceOp::Create(op)) => self.create(op),
+                Change::Op(ResourceOp::Rename(op)) => self.rename(op),
+                Change::Op(ResourceOp::Delete(op)) => self.delete(op),
+            };
+            if let Err(kind) = result {
+                let rolled_back = snapshot.is_some();
+                if let Some((files, versions)) = snapshot {
+                    self.files = files;
+                    self.versions = versions;
+                }
+                return Err(VfsError {
+                    failed_change: index,
+                    rolled_back,
+                    kind,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_text_edits(
+        &mut self,
+        uri: &Uri,
+        version: Option<i32>,
+        edits: &[Cow<'_, TextEdit>],
+    ) -> Result<(), VfsErrorKind> {
+        let text = self
+            .files
+            .get(uri)
+            .ok_or_else(|| VfsErrorKind::NotFound(uri.clone()))?;
+        if let Some(expected) = version
+            && let Some(&actual) = self.versions.get(uri)
+            && expected != actual
+        {
+            return Err(VfsErrorKind::VersionMismatch {
+                uri: uri.clone(),
+                expected,
+                actual,
+            });
+        }
+
+        let line_index = LineIndex::new(text);
+        let mut resolved = edits
+            .iter()
+            .map(|edit| {
+                line_index
+                    .checked_offset_range(edit.range, &self.encoding)
+                    .map(|offsets| (offsets, edit.as_ref()))
+                    .ok_or_else(|| VfsErrorKind::RangeOutOfBounds {
+                        uri: uri.clone(),
+                        range: edit.range,
+                    })
+            })
+            .collect::<Result<Vec<(ops::Range<usize>, &TextEdit)>, _>>()?;
+        // A stable sort keeps inserts at the same position in the order they were given.
+        resolved.sort_by_key(|(offsets, _)| (offsets.start, offsets.end));
+        if let Some(pair) = resolved
+            .windows(2)
+            .find(|pair| pair[1].0.start < pair[0].0.end)
+        {
+            return Err(VfsErrorKind::OverlappingEdits {
+                uri: uri.clone(),
+                range: pair[1].1.range,
+            });
+        }
+
+        let mut new_text = String::with_capacity(text.len());
+        let mut last = 0;
+        for (offsets, edit) in resolved {
+            new_text.push_str(&text[last..offsets.start]);
+            new_text.push_str(&edit.new_text);
+            last = offsets.end;
+        }
+        new_text.push_str(&text[last..]);
+
+        self.files.insert(uri.clone(), new_text);
+        if let Some(version) = self.versions.get_mut(uri) {
+            *version += 1;
+        }
+        Ok(())
+    }
+
+    fn create(&mut self, op: &CreateFile) -> Result<(), VfsErrorKind> {
+        let options = op.options.as_ref();
+        if self.exists(&op.uri) {
+            if options.and_then(|options| options.overwrite) == Some(true) {
+                self.remove_all(&op.uri);
+            } else if options.and_then(|options| options.ignore_if_exists) == Some(true) {
+                return Ok(());
+            } else {
+                return Err(VfsErrorKind::AlreadyExists(op.uri.clone()));
+            }
+        }
+        self.files.insert(op.uri.clone(), String::new());
+        Ok(())
+    }
+
+    fn rename(&mut self, op: &RenameFile) -> Result<(), VfsErrorKind> {
+        if !self.exists(&op.old_uri) {
+            return Err(VfsErrorKind::NotFound(op.old_uri.clone()));
+        }
+        let options = op.options.as_ref();
+        if self.exists(&op.new_uri) {
+            if options.and_then(|options| options.overwrite) == Some(true) {
+                self.remove_all(&op.new_uri);
+            } else if options.and_then(|options| options.ignore_if_exists) == Some(true) {
+                return Ok(());
+            } else {
+                return Err(VfsErrorKind::AlreadyExists(op.new_uri.clone()));
+            }
+        }
+
+        if self.files.contains_key(&op.old_uri) {
+            self.move_file(&op.old_uri, op.new_uri.clone());
+            return Ok(());
+        }
+        let new_prefix = folder_prefix(&op.new_uri);
+        let moves = self
+            .folder_entries(&op.old_uri)
+            .into_iter()
+            .map(|old| {
+                let suffix = &old.as_str()[folder_prefix(&op.old_uri).len()..];
+                let new = format!("{new_prefix}{suffix}");
+                new.parse()
+                    .map(|new| (old, new))
+                    .map_err(|_| VfsErrorKind::InvalidUri(new))
+            })
+            .collect::<Result<Vec<(Uri, Uri)>, _>>()?;
+        for (old, new) in moves {
+            self.move_file(&old, new);
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, op: &DeleteFile) -> Result<(), VfsErrorKind> {
+        let options = op.options.as_ref();
+        if self.files.contains_key(&op.uri) {
+            self.remove(&op.uri);
+        } else if self.exists(&op.uri) {
+            if options.and_then(|options| options.recursive) != Some(true) {
+                return Err(VfsErrorKind::FolderNotEmpty(op.uri.clone()));
+            }
+            self.remove_all(&op.uri);
+        } else if options.and_then(|options| options.ignore_if_not_exists) != Some(true) {
+            return Err(VfsErrorKind::NotFound(op.uri.clone()));
+        }
+        Ok(())
+    }
+
+    /// Whether `uri` is a file or a folder containing files.
+    fn exists(&self, uri: &Uri) -> bool {
+        self.files.contains_key(uri) || !self.folder_entries(uri).is_empty()
+    }
+
+    /// The files in the folder `uri` and its subfolders.
+    fn folder_entries(&self, uri: &Uri) -> Vec<Uri> {
+        let prefix = folder_prefix(uri);
+        self.files
+            .keys()
+            .filter(|file| file.as_str().starts_with(&prefix))
+            .cloned()
+            .collect()
+    }
+
+    /// Removes the file or folder `uri`.
+    fn remove_all(&mut self, uri: &Uri) {
+        self.remove(uri);
+        for file in self.folder_entries(uri) {
+            self.remove(&file);
+        }
+    }
+
+    fn move_file(&mut self, old: &Uri, new: Uri) {
+        if let Some(version) = self.versions.remove(old) {
+            self.versions.insert(new.clone(), version);
+        }
+        if let Some(text) = self.files.remove(old) {
+            self.files.insert(new, text);
+        }
+    }
+}
+
+fn folder_prefix(uri: &Uri) -> String {
+    format!("{}/", uri.as_str().trim_end_matches('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        lsp::{
+            AnnotatedTextEdit, CreateFileOptions, DeleteFileOptions,
+            OptionalVersionedTextDocumentIdentifier, Position, RenameFileOptions, SnippetTextEdit,
+            StringValue,
+        },
+        snippet::SnippetBuilder,
+    };
+
+    fn uri(path: &str) -> Uri {
+        format!("file:///{path}").parse().unwrap()
+    }
+
+    fn edit(start: (u32, u32), end: (u32, u32), new_text: &str) -> TextEdit {
+        TextEdit::new(
+            Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1)),
+            new_text.to_owned(),
+        )
+    }
+
+    fn document_edit(
+        path: &str,
+        version: Option<i32>,
+        edits: Vec<TextEdit>,
+    ) -> DocumentChangeOperation {
+        DocumentChangeOperation::Edit(TextDocumentEdit {
+            text_document: OptionalVersionedTextDocumentIdentifier {
+                uri: uri(path),
+                version,
+            },
+            edits: edits.into_iter().map(TextDocumentEditItem::Text).collect(),
+        })
+    }
+
+    fn operations(operations: Vec<DocumentChangeOperation>) -> WorkspaceEdit {
+        WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Operations(operations)),
+            ..WorkspaceEdit::default()
+        }
+    }
+
+    fn vfs(files: &[(&str, &str)]) -> Vfs {
+        let mut vfs = Vfs::default();
+        for (path, text) in files {
+            vfs.insert(uri(path), (*text).to_owned());
+        }
+        vfs
+    }
+
+    #[test]
+    fn text_edits() {
+        let mut vfs = vfs(&[("a.rs", "let a = 1;\nlet b = a;"), ("b.rs", "a")]);
+        vfs.set_version(uri("a.rs"), 3);
+
+        let workspace_edit = WorkspaceEdit {
+            document_changes: Some(DocumentChanges::Edits(vec![TextDocumentEdit {
+                text_document: OptionalVersionedTextDocumentIdentifier {
+                    uri: uri("a.rs"),
+                    version: Some(3),
+                },
+                edits: vec![
+                    TextDocumentEditItem::Text(edit((1, 8), (1, 9), "x")),
+                    TextDocumentEditItem::Annotated(AnnotatedTextEdit {
+                        text_edit: edit((0, 4), (0, 5), "x"),
+                        annotation_id: "rename".to_owned(),
+                    }),
+                    TextDocumentEditItem::Text(edit((1, 0), (1, 0), "// ")),
+                    TextDocumentEditItem::Text(edit((1, 0), (1, 0), "!")),
+                ],
+            }])),
+            ..WorkspaceEdit::default()
+        };
+        vfs.apply(&workspace_edit, FailureHandlingKind::Abort)
+            .unwrap();
+        assert_eq!(vfs.get(&uri("a.rs")), Some("let x = 1;\n// !let b = x;"));
+        assert_eq!(vfs.version(&uri("a.rs")), Some(4));
+
+        let changes = WorkspaceEdit::new(HashMap::from([(
+            uri("b.rs"),
+            vec![edit((0, 0), (0, 1), "b")],
+        )]));
+        vfs.apply(&changes, FailureHandlingKind::Abort).unwrap();
+        assert_eq!(vfs.get(&uri("b.rs")), Some("b"));
+
+        let snippet = SnippetBuilder::new()
+            .placeholder(1, "c")
+            .text("d")
+            .final_tabstop()
+            .build();
+        let snippet = operations(vec![DocumentChangeOperation::Edit(TextDocumentEdit {
+            text_document: OptionalVersionedTextDocumentIdentifier {
+                uri: uri("b.rs"),
+                version: None,
+            },
+            edits: vec![TextDocumentEditItem::Snippet(SnippetTextEdit {
+                range: edit((0, 1), (0, 1), "").range,
+                snippet: StringValue::new(snippet.to_string()),
+                annotation_id: None,
+            })],
+        })]);
+        vfs.apply(&snippet, FailureHandlingKind::Abort).unwrap();
+        assert_eq!(vfs.get(&uri("b.rs")), Some("bcd"));
+
+        // The version is outdated now.
+        let error = vfs
+            .apply(&workspace_edit, FailureHandlingKind::Abort)
+            .unwrap_err();
+        assert_eq!(
+            error.kind,
+            VfsErrorKind::VersionMismatch {
+                uri: uri("a.rs"),
+                expected: 3,
+                actual: 4
+            }
+        );
+
+        let overlapping = operations(vec![document_edit(
+            "b.rs",
+            None,
+            vec![edit((0, 0), (0, 1), "c"), edit((0, 0), (0, 1), "d")],
+        )]);
+        assert!(matches!(
+            vfs.apply(&overlapping, FailureHandlingKind::Abort)
+                .unwrap_err()
+                .kind,
+            VfsErrorKind::OverlappingEdits { .. }
+        ));
+    }
+
+    #[test]
+    fn resource_operations() {
+        let mut vfs = vfs(&[("src/a.rs", "a"), ("src/b/c.rs", "c"), ("d.rs", "d")]);
+        let workspace_edit = operations(vec![
+            DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+                uri: uri("d.rs"),
+                options: Some(CreateFileOptions {
+                    overwrite: None,
+                    ignore_if_exists: Some(true),
+                }),
+                annotation_id: None,
+            })),
+            DocumentChangeOperation::Op(ResourceOp::Rename(RenameFile {
+                old_uri: uri("src"),
+                new_uri: uri("lib"),
+                options: None,
+                annotation_id: None,
+            })),
+            DocumentChangeOperation::Op(ResourceOp::Create(CreateFile {
+                uri: uri("lib/b/c.rs"),
+                options: Some(CreateFileOptions {
+                    overwrite: Some(true),
+                    ignore_if_exists: Some(true),
+                }),
+                annotation_id: None,
+            })),
+            document_edit("lib/b/c.rs", None, vec![edit((0, 0), (0, 0), "new")]),
+            DocumentChangeOperation::Op(ResourceOp::Delete(DeleteFile {
+                uri: uri("missing.rs"),
+                options: Some(DeleteFileOptions {
+                    recursive: None,
+                    ignore_if_not_exists: Some(true),
+                }),
+                annotation_id: None,
+            })),
+        ]);
+        vfs.apply(&workspace_edit, FailureHandlingKind::Transactional)
+            .unwrap();
+        assert_eq!(
+            vfs.files().collect::<Vec<_>>(),
+            vec![
+                (&uri("d.rs"), "d"),
+                (&uri("lib/a.rs"), "a"),
+                (&uri("lib/b/c.rs"), "new"),
+            ]
+        );
+
+        let delete = |recursive| {
+            operations(vec![DocumentChangeOperation::Op(ResourceOp::Delete(
+                DeleteFile {
+                    uri: uri("lib"),
+                    options: Some(DeleteFileOptions {
+                        recursive,
+                        ignore_if_not_exists: None,
+                    }),
+                    annotation_id: None,
+                },
+            ))])
+        };
+        assert_eq!(
+            vfs.apply(&delete(None), FailureHandlingKind::Abort)
+                .unwrap_err()
+                .kind,
+            VfsErrorKind::FolderNotEmpty(uri("lib"))
+        );
+        vfs.apply(&delete(Some(true)), FailureHandlingKind::Abort)
+            .unwrap();
+        assert_eq!(vfs.files().count(), 1);
+
+        let rename = operations(vec![DocumentChangeOperation::Op(ResourceOp::Rename(
+            RenameFile {
+                old_uri: uri("d.rs"),
+                new_uri: uri("d.rs"),
+                options: Some(RenameFileOptions {
+                    overwrite: None,
+                    ignore_if_exists: None,
+                }),
+                annotation_id: None,
+            },
+        ))]);
+        assert_eq!(
+            vfs.apply(&rename, FailureHandlingKind::Abort)
+                .unwrap_err()
+                .kind,
+            VfsErrorKind::AlreadyExists(uri("d.rs"))
+        );
+    }
+
+    #[test]
+    fn failure_handling() {
+        let initial = vfs(&[("a.rs", "a")]);
+        let text_only = operations(vec![
+            document_edit("a.rs", None, vec![edit((0, 0), (0, 1), "b")]),
+            document_edit("a.rs", None, vec![edit((5, 0), (5, 0), "c")]),
+        ]);
+        let with_operation = operations(vec![
+            document_edit("a.rs", None, vec![edit((0, 0), (0, 1), "b")]),
+            DocumentChangeOperation::Op(ResourceOp::Delete(DeleteFile {
+                uri: uri("missing.rs"),
+                options: None,
+                annotation_id: None,
+            })),
+        ]);
+
+        for (workspace_edit, failure_handling, rolled_back) in [
+            (&text_only, FailureHandlingKind::Abort, false),
+            (&text_only, FailureHandlingKind::Transactional, true),
+            (&text_only, FailureHandlingKind::Undo, true),
+            (&text_only, FailureHandlingKind::TextOnlyTransactional, true),
+            (
+                &with_operation,
+                FailureHandlingKind::TextOnlyTransactional,
+                false,
+            ),
+        ] {
+            let mut vfs = initial.clone();
+            let error = vfs.apply(workspace_edit, failure_handling).unwrap_err();
+            assert_eq!(error.failed_change, 1);
+            assert_eq!(error.rolled_back, rolled_back);
+            let expected = if rolled_back { "a" } else { "b" };
+            assert_eq!(
+                vfs.get(&uri("a.rs")),
+                Some(expected),
+                "{failure_handling:?}"
+            );
+
+            let response = ApplyWorkspaceEditResponse::from(error);
+            assert!(!response.applied);
+            assert_eq!(response.failed_change, Some(1));
+        }
+    }
+}